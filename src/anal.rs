@@ -3,7 +3,19 @@
 pub enum Error {
     WrongActualCount(std::string::String),
     UnknownLabel(std::string::String),
+    DuplicateLabel(std::string::String),
     UnknownRoutine(std::string::String),
+    NotContractive(std::string::String),
+    RaiseOutsideTry,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    UnknownConstructor(std::string::String),
+    DuplicateConstructor(std::string::String),
+    MissingConstructor(std::string::String),
+    WrongFieldCount(std::string::String),
+    DuplicateType(std::string::String),
+    DuplicateTest(std::string::String),
+    Unreachable(crate::location::Location),
 }
 
 impl std::fmt::Display for Error {
@@ -15,9 +27,45 @@ impl std::fmt::Display for Error {
             Error::UnknownLabel(name) => {
                 write!(f, "unknown label {:?}", name)?;
             }
+            Error::DuplicateLabel(name) => {
+                write!(f, "duplicate label {:?}", name)?;
+            }
             Error::UnknownRoutine(name) => {
                 write!(f, "unknown routine {:?}", name)?;
             }
+            Error::NotContractive(name) => {
+                write!(f, "type {:?} is not contractive", name)?;
+            }
+            Error::RaiseOutsideTry => {
+                write!(f, "raise outside try")?;
+            }
+            Error::BreakOutsideLoop => {
+                write!(f, "break outside loop")?;
+            }
+            Error::ContinueOutsideLoop => {
+                write!(f, "continue outside loop")?;
+            }
+            Error::UnknownConstructor(name) => {
+                write!(f, "unknown constructor {:?}", name)?;
+            }
+            Error::DuplicateConstructor(name) => {
+                write!(f, "duplicate constructor {:?}", name)?;
+            }
+            Error::MissingConstructor(name) => {
+                write!(f, "missing case for constructor {:?}", name)?;
+            }
+            Error::WrongFieldCount(name) => {
+                write!(f, "incorrect number of fields for {:?}", name)?;
+            }
+            Error::DuplicateType(name) => {
+                write!(f, "duplicate type {:?}", name)?;
+            }
+            Error::DuplicateTest(name) => {
+                write!(f, "duplicate test {:?}", name)?;
+            }
+            Error::Unreachable(location) => {
+                write!(f, "unreachable statement at {}", location)?;
+            }
         }
         Ok(())
     }
@@ -29,20 +77,98 @@ pub fn anal(
     tree: crate::tree::Tree,
 ) -> std::result::Result<crate::graph::Graph, std::boxed::Box<dyn std::error::Error>> {
     let mut anal = Anal::new();
+    for data in tree.dataes {
+        anal.anal_data(data)?;
+    }
+    for r#type in tree.typees {
+        anal.anal_type(r#type)?;
+    }
+    for routine in tree.routinees {
+        anal.anal_routine(routine)?;
+    }
+    for test in tree.testes {
+        anal.anal_test(test)?;
+    }
+    anal.into_graph()
+}
+
+pub fn anal_with_type_expressions(
+    tree: crate::tree::Tree,
+    expressions: std::vec::Vec<crate::tree::TypeExpression>,
+) -> std::result::Result<
+    (crate::graph::Graph, std::vec::Vec<usize>),
+    std::boxed::Box<dyn std::error::Error>,
+> {
+    let mut anal = Anal::new();
+    for data in tree.dataes {
+        anal.anal_data(data)?;
+    }
     for r#type in tree.typees {
         anal.anal_type(r#type)?;
     }
+    let mut indexs = std::vec::Vec::with_capacity(expressions.len());
+    for expression in expressions {
+        indexs.push(anal.anal_type_expression(expression)?);
+    }
     for routine in tree.routinees {
         anal.anal_routine(routine)?;
     }
-    Ok(anal.into_graph()?)
+    Ok((anal.into_graph()?, indexs))
+}
+
+pub fn get_data_type(data: &crate::tree::Data) -> crate::tree::Type {
+    let mut value = None;
+    for constructor in data.constructors.iter().rev() {
+        let mut fields = constructor.fields.iter().rev();
+        let mut branch = fields
+            .next()
+            .cloned()
+            .unwrap_or(crate::tree::TypeExpression::One);
+        for field in fields {
+            branch = crate::tree::TypeExpression::Times {
+                value: std::boxed::Box::new(field.clone()),
+                next: std::boxed::Box::new(branch),
+            };
+        }
+        value = Some(match value {
+            Some(deny) => crate::tree::TypeExpression::Plus {
+                accept: std::boxed::Box::new(branch),
+                deny: std::boxed::Box::new(deny),
+            },
+            None => branch,
+        });
+    }
+    crate::tree::Type {
+        name: data.name.clone(),
+        value: std::boxed::Box::new(value.unwrap_or(crate::tree::TypeExpression::Zero)),
+    }
+}
+
+struct Constructor {
+    data: std::string::String,
+    index: usize,
+    arity: usize,
 }
 
 struct Anal {
     typees: std::vec::Vec<crate::graph::TypeNode>,
     nodees: std::vec::Vec<crate::graph::Node>,
-    type_end: usize,
-    node_end: usize,
+    type_one: usize,
+    type_bottom: usize,
+    type_zero: usize,
+    type_top: usize,
+    node_end: std::option::Option<usize>,
+    type_scopes: std::vec::Vec<(std::string::String, usize)>,
+    constructors: std::collections::HashMap<std::string::String, Constructor>,
+    dataes: std::collections::HashMap<std::string::String, std::vec::Vec<std::string::String>>,
+    literals: std::collections::BTreeMap<std::string::String, (u32, usize)>,
+    forkes: std::vec::Vec<crate::graph::Routine>,
+    lifteds: std::collections::HashSet<usize>,
+    tryes: std::vec::Vec<crate::graph::Try>,
+    node_tryes: std::collections::HashMap<usize, usize>,
+    node_locations: std::collections::HashMap<usize, crate::location::Location>,
+    node_labels: std::collections::HashMap<usize, std::string::String>,
+    testes: std::vec::Vec<crate::graph::Test>,
     type_patchs: std::collections::HashMap<
         std::string::String,
         Patch<std::vec::Vec<crate::graph::TypeNode>, usize>,
@@ -56,10 +182,29 @@ struct Anal {
 impl Anal {
     fn new() -> Self {
         Anal {
-            typees: vec![crate::graph::TypeNode::One],
-            nodees: vec![crate::graph::Node::End],
-            type_end: 0,
-            node_end: 0,
+            typees: vec![
+                crate::graph::TypeNode::One { dual: 1 },
+                crate::graph::TypeNode::Bottom { dual: 0 },
+                crate::graph::TypeNode::Zero { dual: 3 },
+                crate::graph::TypeNode::Top { dual: 2 },
+            ],
+            nodees: std::vec::Vec::new(),
+            type_one: 0,
+            type_bottom: 1,
+            type_zero: 2,
+            type_top: 3,
+            node_end: None,
+            type_scopes: std::vec::Vec::new(),
+            constructors: std::collections::HashMap::new(),
+            dataes: std::collections::HashMap::new(),
+            literals: std::collections::BTreeMap::new(),
+            forkes: std::vec::Vec::new(),
+            lifteds: std::collections::HashSet::new(),
+            tryes: std::vec::Vec::new(),
+            node_tryes: std::collections::HashMap::new(),
+            node_locations: std::collections::HashMap::new(),
+            node_labels: std::collections::HashMap::new(),
+            testes: std::vec::Vec::new(),
             type_patchs: std::collections::HashMap::new(),
             routine_patchs: std::collections::HashMap::new(),
         }
@@ -134,14 +279,104 @@ impl Anal {
         });
         index
     }
+    fn add_shared(&mut self, value: usize) -> usize {
+        let index = self.typees.len();
+        self.typees.push(crate::graph::TypeNode::Shared {
+            value,
+            dual: index + 1,
+        });
+        self.typees.push(crate::graph::TypeNode::Service {
+            value: crate::graph::get_dual(&self.typees, value),
+            dual: index,
+        });
+        index
+    }
+    fn add_release(&mut self, value: usize) -> usize {
+        let index = self.typees.len();
+        self.typees.push(crate::graph::TypeNode::Release {
+            value,
+            dual: index + 1,
+        });
+        self.typees.push(crate::graph::TypeNode::Detach {
+            value: crate::graph::get_dual(&self.typees, value),
+            dual: index,
+        });
+        index
+    }
     fn add_node(&mut self, node: crate::graph::Node) -> usize {
         let index = self.nodees.len();
         self.nodees.push(node);
         index
     }
+    fn is_contractive(&self, mut index: usize) -> bool {
+        for _ in 0..self.typees.len() {
+            match &self.typees[index] {
+                crate::graph::TypeNode::Variable { node, .. } => index = *node,
+                _ => return true,
+            }
+        }
+        false
+    }
+    fn get_end(&mut self) -> usize {
+        if let Some(end) = self.node_end {
+            end
+        } else {
+            let end = self.add_node(crate::graph::Node::End);
+            self.node_end = Some(end);
+            end
+        }
+    }
+    fn anal_literal(
+        &mut self,
+        name: std::string::String,
+        value: u32,
+        r#type: usize,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        let formal = std::string::String::from("n");
+        let end = self.get_end();
+        let mut start = self.add_node(crate::graph::Node::Wait {
+            name: formal.clone(),
+            next: end,
+        });
+        start = self.add_node(crate::graph::Node::Accept {
+            server: formal.clone(),
+            next: start,
+        });
+        for _ in 0..value {
+            start = self.add_node(crate::graph::Node::Deny {
+                server: formal.clone(),
+                next: start,
+            });
+        }
+        let formals = vec![crate::graph::Formal {
+            name: formal,
+            r#type: crate::graph::get_dual(&self.typees, r#type),
+        }];
+        self.routine_patchs
+            .entry(name)
+            .or_insert(Patch::new())
+            .patch(&mut self.nodees, crate::graph::Routine { start, formals })
+    }
     fn into_graph(
-        self,
+        mut self,
     ) -> std::result::Result<crate::graph::Graph, std::boxed::Box<dyn std::error::Error>> {
+        let mut literales = std::vec::Vec::new();
+        for (name, (value, r#type)) in std::mem::take(&mut self.literals) {
+            literales.push(crate::graph::Literal {
+                name: name.clone(),
+                r#type,
+            });
+            self.anal_literal(name, value, r#type)?;
+        }
+        let mut names = self.type_patchs.keys().collect::<std::vec::Vec<_>>();
+        names.sort();
+        for name in names {
+            if let Some(index) = self.type_patchs[name].get()
+                && !self.is_contractive(*index)
+            {
+                return Err(std::boxed::Box::new(Error::NotContractive(name.clone())));
+            }
+        }
         let mut routinees = std::collections::HashMap::new();
         for (name, patch) in self.routine_patchs {
             if let Some(routine) = patch.into_inner() {
@@ -154,25 +389,36 @@ impl Anal {
             typees: self.typees,
             nodees: self.nodees,
             routinees,
+            forkes: self.forkes,
+            tryes: self.tryes,
+            node_tryes: self.node_tryes,
+            node_locations: self.node_locations,
+            node_labels: self.node_labels,
+            testes: self.testes,
+            literales,
         })
     }
-    fn check_expression(
+    fn anal_expression(
         &mut self,
-        expression: &crate::tree::Expression,
-    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        expression: crate::tree::Expression,
+    ) -> std::result::Result<crate::graph::Expression, std::boxed::Box<dyn std::error::Error>> {
         match expression {
-            crate::tree::Expression::Variable { .. } => Ok(()),
+            crate::tree::Expression::Variable { name } => {
+                Ok(crate::graph::Expression::Variable { name })
+            }
             crate::tree::Expression::Call {
                 name,
                 before,
                 after,
             } => {
-                for actual in before {
-                    self.check_expression(actual)?;
-                }
-                for actual in after {
-                    self.check_expression(actual)?;
-                }
+                let before = before
+                    .into_iter()
+                    .map(|actual| self.anal_expression(actual))
+                    .collect::<std::result::Result<std::vec::Vec<_>, _>>()?;
+                let after = after
+                    .into_iter()
+                    .map(|actual| self.anal_expression(actual))
+                    .collect::<std::result::Result<std::vec::Vec<_>, _>>()?;
                 let actual_count = before.len() + 1 + after.len();
                 let name_clone = name.clone();
                 let callback = move |_: &mut std::vec::Vec<crate::graph::Node>,
@@ -190,14 +436,103 @@ impl Anal {
                     .entry(name.clone())
                     .or_insert(Patch::new())
                     .call_back(&mut self.nodees, std::boxed::Box::new(callback))?;
-                Ok(())
+                Ok(crate::graph::Expression::Call {
+                    name,
+                    before,
+                    after,
+                })
+            }
+            crate::tree::Expression::Literal { value, r#type } => {
+                let name = format!("{} : {}", value, r#type);
+                if !self.literals.contains_key(&name) {
+                    let r#type = self.anal_type_expression(r#type)?;
+                    self.literals.insert(name.clone(), (value, r#type));
+                }
+                Ok(crate::graph::Expression::Call {
+                    name,
+                    before: vec![],
+                    after: vec![],
+                })
+            }
+            crate::tree::Expression::Fork { formal, body } => {
+                let mut names = std::collections::BTreeSet::new();
+                collect_names(&body, &mut names);
+                names.remove(&formal.name);
+                let r#type = self.anal_type_expression(formal.r#type)?;
+                let low = self.nodees.len();
+                let end = is_completing(&body).then(|| self.get_end());
+                let start = RoutineAnal::with_anal(self).anal_statements(end, body)?;
+                self.lifteds.extend(low..self.nodees.len());
+                let fork = self.forkes.len();
+                self.forkes.push(crate::graph::Routine {
+                    start,
+                    formals: vec![crate::graph::Formal {
+                        name: formal.name,
+                        r#type,
+                    }],
+                });
+                Ok(crate::graph::Expression::Fork {
+                    fork,
+                    names: names.into_iter().collect(),
+                })
+            }
+        }
+    }
+    fn anal_data(
+        &mut self,
+        data: crate::tree::Data,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        let mut names = std::vec::Vec::with_capacity(data.constructors.len());
+        for (index, constructor) in data.constructors.iter().enumerate() {
+            if self.constructors.contains_key(&constructor.name) {
+                return Err(std::boxed::Box::new(Error::DuplicateConstructor(
+                    constructor.name.clone(),
+                )));
             }
+            self.constructors.insert(
+                constructor.name.clone(),
+                Constructor {
+                    data: data.name.clone(),
+                    index,
+                    arity: constructor.fields.len(),
+                },
+            );
+            names.push(constructor.name.clone());
+        }
+        self.anal_type(get_data_type(&data))?;
+        self.dataes.insert(data.name, names);
+        Ok(())
+    }
+    fn get_constructor(
+        &self,
+        name: &str,
+        arity: usize,
+    ) -> std::result::Result<&Constructor, std::boxed::Box<dyn std::error::Error>> {
+        let constructor = if let Some(constructor) = self.constructors.get(name) {
+            constructor
+        } else {
+            return Err(std::boxed::Box::new(Error::UnknownConstructor(
+                name.to_string(),
+            )));
+        };
+        if constructor.arity != arity {
+            return Err(std::boxed::Box::new(Error::WrongFieldCount(
+                name.to_string(),
+            )));
         }
+        Ok(constructor)
     }
     fn anal_type(
         &mut self,
         r#type: crate::tree::Type,
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        if self
+            .type_patchs
+            .get(&r#type.name)
+            .is_some_and(|patch| patch.get().is_some())
+        {
+            return Err(std::boxed::Box::new(Error::DuplicateType(r#type.name)));
+        }
         let value = self.anal_type_expression(*r#type.value)?;
         self.type_patchs
             .entry(r#type.name.clone())
@@ -211,6 +546,18 @@ impl Anal {
     ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
         match expression {
             crate::tree::TypeExpression::Variable { name, is_dual } => {
+                if let Some((_, index)) = self
+                    .type_scopes
+                    .iter()
+                    .rev()
+                    .find(|(scope_name, _)| *scope_name == name)
+                {
+                    return Ok(if is_dual {
+                        crate::graph::get_dual(&self.typees, *index)
+                    } else {
+                        *index
+                    });
+                }
                 let index = self.add_variable(0, is_dual);
                 let callback = move |typees: &mut std::vec::Vec<crate::graph::TypeNode>,
                                      node: &usize|
@@ -218,7 +565,7 @@ impl Anal {
                     (),
                     std::boxed::Box<dyn std::error::Error>,
                 > {
-                    let dual = crate::graph::get_dual(&typees, index);
+                    let dual = crate::graph::get_dual(typees, index);
                     match &mut typees[index] {
                         crate::graph::TypeNode::Variable { node: pointer, .. } => *pointer = *node,
                         _ => unreachable!(),
@@ -255,14 +602,53 @@ impl Anal {
                 let deny = self.anal_type_expression(*deny)?;
                 Ok(self.add_choose(accept, deny))
             }
-            crate::tree::TypeExpression::One => Ok(self.type_end),
+            crate::tree::TypeExpression::Dual { value } => {
+                let value = self.anal_type_expression(*value)?;
+                Ok(crate::graph::get_dual(&self.typees, value))
+            }
+            crate::tree::TypeExpression::Recursive { name, value } => {
+                let index = self.add_variable(0, false);
+                self.type_scopes.push((name, index));
+                let value = self.anal_type_expression(*value);
+                let (name, _) = self.type_scopes.pop().unwrap();
+                let value = value?;
+                let dual = crate::graph::get_dual(&self.typees, index);
+                for variable in [index, dual] {
+                    match &mut self.typees[variable] {
+                        crate::graph::TypeNode::Variable { node, .. } => *node = value,
+                        _ => unreachable!(),
+                    }
+                }
+                if !self.is_contractive(index) {
+                    return Err(std::boxed::Box::new(Error::NotContractive(name)));
+                }
+                Ok(value)
+            }
+            crate::tree::TypeExpression::Shared { value } => {
+                let value = self.anal_type_expression(*value)?;
+                Ok(self.add_shared(value))
+            }
+            crate::tree::TypeExpression::Release { value } => {
+                let value = self.anal_type_expression(*value)?;
+                Ok(self.add_release(value))
+            }
+            crate::tree::TypeExpression::One => Ok(self.type_one),
+            crate::tree::TypeExpression::Bottom => Ok(self.type_bottom),
+            crate::tree::TypeExpression::Zero => Ok(self.type_zero),
+            crate::tree::TypeExpression::Top => Ok(self.type_top),
         }
     }
     fn anal_routine(
         &mut self,
         routine: crate::tree::Routine,
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
-        let end = self.node_end;
+        let end = if !is_completing(&routine.body) {
+            None
+        } else if routine.is_affine {
+            Some(self.add_node(crate::graph::Node::Drop))
+        } else {
+            Some(self.get_end())
+        };
         let mut formals = std::vec::Vec::with_capacity(routine.formals.len());
         for crate::tree::Formal { name, r#type } in routine.formals {
             let r#type = self.anal_type_expression(r#type)?;
@@ -270,88 +656,212 @@ impl Anal {
         }
         let mut routine_anal = RoutineAnal::with_anal(self);
         let start = routine_anal.anal_statements(end, routine.body)?;
-        routine_anal.finish()?;
         self.routine_patchs
             .entry(routine.name.clone())
             .or_insert(Patch::new())
             .patch(&mut self.nodees, crate::graph::Routine { start, formals })?;
         Ok(())
     }
+    fn anal_test(
+        &mut self,
+        test: crate::tree::Test,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        if self.testes.iter().any(|other| other.name == test.name) {
+            return Err(std::boxed::Box::new(Error::DuplicateTest(test.name)));
+        }
+        let end = is_completing(&test.body).then(|| self.get_end());
+        let start = RoutineAnal::with_anal(self).anal_statements(end, test.body)?;
+        self.testes.push(crate::graph::Test {
+            name: test.name,
+            start,
+        });
+        Ok(())
+    }
+}
+
+type Label = Patch<std::vec::Vec<crate::graph::Node>, usize>;
+
+struct Loop {
+    start: Label,
+    end: std::option::Option<usize>,
 }
 
 struct RoutineAnal<'a> {
     anal: &'a mut Anal,
-    patchs: std::collections::HashMap<
-        std::string::String,
-        Patch<std::vec::Vec<crate::graph::Node>, usize>,
-    >,
+    scopes: std::vec::Vec<std::collections::HashMap<std::string::String, Label>>,
+    loops: std::vec::Vec<Loop>,
+    tryes: std::vec::Vec<usize>,
 }
 
 impl<'a> RoutineAnal<'a> {
     fn with_anal(anal: &'a mut Anal) -> Self {
         RoutineAnal {
             anal,
-            patchs: std::collections::HashMap::new(),
+            scopes: std::vec::Vec::new(),
+            loops: std::vec::Vec::new(),
+            tryes: std::vec::Vec::new(),
         }
     }
-    fn finish(self) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
-        for (name, patch) in self.patchs {
-            if patch.get().is_none() {
-                return Err(std::boxed::Box::new(Error::UnknownLabel(name)));
+    fn add_branch(&mut self) -> (usize, Callback<std::vec::Vec<crate::graph::Node>, usize>) {
+        let index = self.anal.add_node(crate::graph::Node::Branch { next: 0 });
+        let callback =
+            move |nodees: &mut std::vec::Vec<crate::graph::Node>,
+                  next: &usize|
+                  -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+                match &mut nodees[index] {
+                    crate::graph::Node::Branch { next: pointer } => *pointer = *next,
+                    _ => unreachable!(),
+                }
+                Ok(())
+            };
+        (index, std::boxed::Box::new(callback))
+    }
+    fn anal_statements(
+        &mut self,
+        last: std::option::Option<usize>,
+        statements: std::vec::Vec<crate::tree::Statement>,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let mut scope = std::collections::HashMap::new();
+        for statement in statements.iter() {
+            if let crate::tree::Statement::Label { name } = statement
+                && scope.insert(name.clone(), Patch::new()).is_some()
+            {
+                return Err(std::boxed::Box::new(Error::DuplicateLabel(name.clone())));
             }
         }
-        Ok(())
+        self.scopes.push(scope);
+        let last = self.anal_block(last, statements);
+        self.scopes.pop();
+        last
     }
-    fn anal_statements(
+    fn anal_block(
         &mut self,
-        mut last: usize,
+        last: std::option::Option<usize>,
         statements: std::vec::Vec<crate::tree::Statement>,
     ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let mut is_reachable = true;
+        for (index, statement) in statements.iter().enumerate() {
+            match statement {
+                crate::tree::Statement::Label { name } => {
+                    is_reachable = is_reachable || has_branch(&statements, name);
+                }
+                crate::tree::Statement::Mark { location } => {
+                    if !is_reachable
+                        && !matches!(
+                            statements.get(index + 1),
+                            Some(crate::tree::Statement::Label { name })
+                                if has_branch(&statements, name)
+                        )
+                    {
+                        return Err(std::boxed::Box::new(Error::Unreachable(location.clone())));
+                    }
+                }
+                statement => {
+                    if is_reachable {
+                        is_reachable = is_statement_completing(statement);
+                    }
+                }
+            }
+        }
+        let mut last = last;
         for statement in statements.into_iter().rev() {
             match statement {
                 crate::tree::Statement::Branch { name } => {
-                    last = self.anal.add_node(crate::graph::Node::Branch { next: 0 });
-                    let index = last;
-                    let callback = move |nodees: &mut std::vec::Vec<crate::graph::Node>,
-                                         next: &usize|
-                          -> std::result::Result<
-                        (),
-                        std::boxed::Box<dyn std::error::Error>,
-                    > {
-                        match &mut nodees[index] {
-                            crate::graph::Node::Branch { next: pointer } => *pointer = *next,
-                            _ => unreachable!(),
-                        }
-                        Ok(())
+                    let (index, callback) = self.add_branch();
+                    last = Some(index);
+                    let label = if let Some(label) = self
+                        .scopes
+                        .iter_mut()
+                        .rev()
+                        .find_map(|scope| scope.get_mut(&name))
+                    {
+                        label
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnknownLabel(name)));
                     };
-                    self.patchs
-                        .entry(name)
-                        .or_insert(Patch::new())
-                        .call_back(&mut self.anal.nodees, std::boxed::Box::new(callback))?;
+                    label.call_back(&mut self.anal.nodees, callback)?;
+                }
+                crate::tree::Statement::Label { name } => {
+                    self.scopes
+                        .last_mut()
+                        .unwrap()
+                        .get_mut(&name)
+                        .unwrap()
+                        .patch(&mut self.anal.nodees, last.unwrap())?;
+                    if last != self.anal.node_end {
+                        self.anal.node_labels.entry(last.unwrap()).or_insert(name);
+                    }
+                }
+                crate::tree::Statement::Mark { location } => {
+                    if let Some(last) = last
+                        && Some(last) != self.anal.node_end
+                    {
+                        self.anal.node_locations.entry(last).or_insert(location);
+                    }
+                }
+                crate::tree::Statement::Loop { body } => {
+                    self.loops.push(Loop {
+                        start: Patch::new(),
+                        end: last,
+                    });
+                    let back = is_completing(&body).then(|| self.add_branch());
+                    let body = self.anal_statements(back.as_ref().map(|(index, _)| *index), body);
+                    let mut r#loop = self.loops.pop().unwrap();
+                    let start = body?;
+                    if let Some((_, callback)) = back {
+                        r#loop.start.call_back(&mut self.anal.nodees, callback)?;
+                    }
+                    r#loop.start.patch(&mut self.anal.nodees, start)?;
+                    last = Some(start);
+                }
+                crate::tree::Statement::While { client, body } => {
+                    let body = vec![crate::tree::Statement::Offer {
+                        client,
+                        accepted: body,
+                        denied: vec![crate::tree::Statement::Break],
+                    }];
+                    last =
+                        Some(self.anal_block(last, vec![crate::tree::Statement::Loop { body }])?);
+                }
+                crate::tree::Statement::Break => {
+                    let end = if let Some(r#loop) = self.loops.last() {
+                        r#loop.end.unwrap()
+                    } else {
+                        return Err(std::boxed::Box::new(Error::BreakOutsideLoop));
+                    };
+                    last = Some(self.anal.add_node(crate::graph::Node::Branch { next: end }));
+                }
+                crate::tree::Statement::Continue => {
+                    if self.loops.is_empty() {
+                        return Err(std::boxed::Box::new(Error::ContinueOutsideLoop));
+                    }
+                    let (index, callback) = self.add_branch();
+                    last = Some(index);
+                    self.loops
+                        .last_mut()
+                        .unwrap()
+                        .start
+                        .call_back(&mut self.anal.nodees, callback)?;
                 }
-                crate::tree::Statement::Label { name } => self
-                    .patchs
-                    .entry(name)
-                    .or_insert(Patch::new())
-                    .patch(&mut self.anal.nodees, last)?,
                 crate::tree::Statement::Assign { name, value } => {
-                    self.anal.check_expression(&value)?;
-                    last = self.anal.add_node(crate::graph::Node::Assign {
+                    let value = self.anal.anal_expression(*value)?;
+                    last = Some(self.anal.add_node(crate::graph::Node::Assign {
                         name,
-                        value,
-                        next: last,
-                    });
+                        value: std::boxed::Box::new(value),
+                        next: last.unwrap(),
+                    }));
                 }
                 crate::tree::Statement::Call { name, actuals } => {
-                    for actual in actuals.iter() {
-                        self.anal.check_expression(actual)?;
-                    }
+                    let actuals = actuals
+                        .into_iter()
+                        .map(|actual| self.anal.anal_expression(actual))
+                        .collect::<std::result::Result<std::vec::Vec<_>, _>>()?;
                     let actual_count = actuals.len();
-                    last = self.anal.add_node(crate::graph::Node::Call {
+                    last = Some(self.anal.add_node(crate::graph::Node::Call {
                         name: name.clone(),
                         actuals,
-                        next: last,
-                    });
+                        next: last.unwrap(),
+                    }));
                     let name_clone = name.clone();
                     let callback = move |_: &mut std::vec::Vec<crate::graph::Node>,
                                          routine: &crate::graph::Routine|
@@ -371,21 +881,21 @@ impl<'a> RoutineAnal<'a> {
                         .call_back(&mut self.anal.nodees, std::boxed::Box::new(callback))?;
                 }
                 crate::tree::Statement::Receive { source, variable } => {
-                    last = self.anal.add_node(crate::graph::Node::Receive {
+                    last = Some(self.anal.add_node(crate::graph::Node::Receive {
                         source,
                         variable,
-                        next: last,
-                    });
+                        next: last.unwrap(),
+                    }));
                 }
                 crate::tree::Statement::Send {
                     destination,
                     variable,
                 } => {
-                    last = self.anal.add_node(crate::graph::Node::Send {
+                    last = Some(self.anal.add_node(crate::graph::Node::Send {
                         destination,
                         variable,
-                        next: last,
-                    });
+                        next: last.unwrap(),
+                    }));
                 }
                 crate::tree::Statement::Offer {
                     client,
@@ -394,52 +904,403 @@ impl<'a> RoutineAnal<'a> {
                 } => {
                     let accepted = self.anal_statements(last, accepted)?;
                     let denied = self.anal_statements(last, denied)?;
-                    last = self.anal.add_node(crate::graph::Node::Offer {
+                    last = Some(self.anal.add_node(crate::graph::Node::Offer {
                         client,
                         accepted,
                         denied,
-                    });
+                    }));
+                }
+                crate::tree::Statement::Select { arms } => {
+                    let mut graph_arms = std::vec::Vec::with_capacity(arms.len());
+                    for arm in arms {
+                        graph_arms.push(match arm {
+                            crate::tree::Arm::Offer {
+                                client,
+                                accepted,
+                                denied,
+                            } => {
+                                let accepted = self.anal_statements(last, accepted)?;
+                                let denied = self.anal_statements(last, denied)?;
+                                crate::graph::Arm::Offer {
+                                    client,
+                                    accepted,
+                                    denied,
+                                }
+                            }
+                            crate::tree::Arm::Receive {
+                                source,
+                                variable,
+                                body,
+                            } => {
+                                let next = self.anal_statements(last, body)?;
+                                crate::graph::Arm::Receive {
+                                    source,
+                                    variable,
+                                    next,
+                                }
+                            }
+                        });
+                    }
+                    last = Some(
+                        self.anal
+                            .add_node(crate::graph::Node::Select { arms: graph_arms }),
+                    );
                 }
                 crate::tree::Statement::Accept { server } => {
-                    last = self
-                        .anal
-                        .add_node(crate::graph::Node::Accept { server, next: last });
+                    last = Some(self.anal.add_node(crate::graph::Node::Accept {
+                        server,
+                        next: last.unwrap(),
+                    }));
                 }
                 crate::tree::Statement::Deny { server } => {
-                    last = self
-                        .anal
-                        .add_node(crate::graph::Node::Deny { server, next: last });
+                    last = Some(self.anal.add_node(crate::graph::Node::Deny {
+                        server,
+                        next: last.unwrap(),
+                    }));
                 }
                 crate::tree::Statement::Close { name } => {
-                    last = self
-                        .anal
-                        .add_node(crate::graph::Node::Close { name, next: last });
+                    last = Some(self.anal.add_node(crate::graph::Node::Close {
+                        name,
+                        next: last.unwrap(),
+                    }));
+                }
+                crate::tree::Statement::Wait { name } => {
+                    last = Some(self.anal.add_node(crate::graph::Node::Wait {
+                        name,
+                        next: last.unwrap(),
+                    }));
+                }
+                crate::tree::Statement::Absurd { name } => {
+                    last = Some(self.anal.add_node(crate::graph::Node::Absurd { name }));
                 }
                 crate::tree::Statement::Connect { left, right } => {
-                    last = self.anal.add_node(crate::graph::Node::Connect {
+                    last = Some(self.anal.add_node(crate::graph::Node::Connect {
                         left,
                         right,
-                        next: last,
+                        next: last.unwrap(),
+                    }));
+                }
+                crate::tree::Statement::Acquire { name } => {
+                    last = Some(self.anal.add_node(crate::graph::Node::Acquire {
+                        name,
+                        next: last.unwrap(),
+                    }));
+                }
+                crate::tree::Statement::Release { name } => {
+                    last = Some(self.anal.add_node(crate::graph::Node::Release {
+                        name,
+                        next: last.unwrap(),
+                    }));
+                }
+                crate::tree::Statement::Share { name, copy } => {
+                    last = Some(self.anal.add_node(crate::graph::Node::Share {
+                        name,
+                        copy,
+                        next: last.unwrap(),
+                    }));
+                }
+                crate::tree::Statement::Construct {
+                    name,
+                    constructor,
+                    mut fields,
+                } => {
+                    let constructor = self.anal.get_constructor(&constructor, fields.len())?;
+                    let count = self.anal.dataes[&constructor.data].len();
+                    let mut statements = std::vec::Vec::new();
+                    for _ in 0..constructor.index {
+                        statements.push(crate::tree::Statement::Deny {
+                            server: name.clone(),
+                        });
+                    }
+                    if constructor.index + 1 < count {
+                        statements.push(crate::tree::Statement::Accept {
+                            server: name.clone(),
+                        });
+                    }
+                    if let Some(right) = fields.pop() {
+                        for variable in fields {
+                            statements.push(crate::tree::Statement::Send {
+                                destination: name.clone(),
+                                variable,
+                            });
+                        }
+                        statements.push(crate::tree::Statement::Connect { left: name, right });
+                    } else {
+                        statements.push(crate::tree::Statement::Wait { name });
+                    }
+                    last = Some(self.anal_statements(last, statements)?);
+                }
+                crate::tree::Statement::Match { name, cases } => {
+                    let mut data = None;
+                    let mut bodies = std::collections::BTreeMap::new();
+                    for crate::tree::Case {
+                        constructor,
+                        mut variables,
+                        body,
+                    } in cases
+                    {
+                        let entry = self.anal.get_constructor(&constructor, variables.len())?;
+                        if data.get_or_insert_with(|| entry.data.clone()) != &entry.data {
+                            return Err(std::boxed::Box::new(Error::UnknownConstructor(
+                                constructor,
+                            )));
+                        }
+                        let index = entry.index;
+                        let mut statements = std::vec::Vec::new();
+                        if let Some(rest) = variables.pop() {
+                            for variable in variables {
+                                statements.push(crate::tree::Statement::Receive {
+                                    source: name.clone(),
+                                    variable,
+                                });
+                            }
+                            if rest != name {
+                                statements.push(crate::tree::Statement::Assign {
+                                    name: rest,
+                                    value: std::boxed::Box::new(
+                                        crate::tree::Expression::Variable { name: name.clone() },
+                                    ),
+                                });
+                            }
+                        } else {
+                            statements.push(crate::tree::Statement::Close { name: name.clone() });
+                        }
+                        statements.extend(body);
+                        if bodies.insert(index, statements).is_some() {
+                            return Err(std::boxed::Box::new(Error::DuplicateConstructor(
+                                constructor,
+                            )));
+                        }
+                    }
+                    let data = if let Some(data) = data {
+                        data
+                    } else {
+                        return Err(std::boxed::Box::new(Error::MissingConstructor(name)));
+                    };
+                    let constructors = &self.anal.dataes[&data];
+                    if let Some(missing) =
+                        (0..constructors.len()).find(|index| !bodies.contains_key(index))
+                    {
+                        return Err(std::boxed::Box::new(Error::MissingConstructor(
+                            constructors[missing].clone(),
+                        )));
+                    }
+                    let mut bodies = bodies.into_values().rev();
+                    let mut statements = bodies.next().unwrap();
+                    for accepted in bodies {
+                        statements = vec![crate::tree::Statement::Offer {
+                            client: name.clone(),
+                            accepted,
+                            denied: statements,
+                        }];
+                    }
+                    last = Some(self.anal_statements(last, statements)?);
+                }
+                crate::tree::Statement::Try { body, handler } => {
+                    let mut names = std::collections::BTreeSet::new();
+                    collect_names(&body, &mut names);
+                    let handler = self.anal_statements(last, handler)?;
+                    let r#try = self.anal.tryes.len();
+                    self.anal.tryes.push(crate::graph::Try {
+                        handler,
+                        names: names.into_iter().collect(),
+                    });
+                    let low = self.anal.nodees.len();
+                    self.tryes.push(r#try);
+                    let body = self.anal_statements(last, body);
+                    self.tryes.pop();
+                    last = Some(body?);
+                    for index in low..self.anal.nodees.len() {
+                        if !self.anal.lifteds.contains(&index) {
+                            self.anal.node_tryes.entry(index).or_insert(r#try);
+                        }
+                    }
+                }
+                crate::tree::Statement::Raise => {
+                    let r#try = if let Some(r#try) = self.tryes.last() {
+                        *r#try
+                    } else {
+                        return Err(std::boxed::Box::new(Error::RaiseOutsideTry));
+                    };
+                    last = Some(self.anal.add_node(crate::graph::Node::Raise { r#try }));
+                }
+                crate::tree::Statement::Assert { value, location } => {
+                    let (name, value) = match *value {
+                        crate::tree::Expression::Variable { name } => (name, None),
+                        value => (
+                            std::string::String::from("assert"),
+                            Some(self.anal.anal_expression(value)?),
+                        ),
+                    };
+                    let denied = self.anal.add_node(crate::graph::Node::Fail { location });
+                    let accepted = self.anal.add_node(crate::graph::Node::Close {
+                        name: name.clone(),
+                        next: last.unwrap(),
                     });
+                    last = Some(self.anal.add_node(crate::graph::Node::Offer {
+                        client: name.clone(),
+                        accepted,
+                        denied,
+                    }));
+                    if let Some(value) = value {
+                        last = Some(self.anal.add_node(crate::graph::Node::Assign {
+                            name,
+                            value: std::boxed::Box::new(value),
+                            next: last.unwrap(),
+                        }));
+                    }
                 }
             }
         }
-        Ok(last)
+        Ok(last.unwrap())
     }
 }
 
+fn collect_names(
+    statements: &[crate::tree::Statement],
+    names: &mut std::collections::BTreeSet<std::string::String>,
+) {
+    fn collect_expression_names(
+        expression: &crate::tree::Expression,
+        names: &mut std::collections::BTreeSet<std::string::String>,
+    ) {
+        match expression {
+            crate::tree::Expression::Variable { name } => {
+                names.insert(name.clone());
+            }
+            crate::tree::Expression::Call { before, after, .. } => {
+                for actual in before.iter().chain(after.iter()) {
+                    collect_expression_names(actual, names);
+                }
+            }
+            crate::tree::Expression::Literal { .. } => {}
+            crate::tree::Expression::Fork { formal, body } => {
+                names.insert(formal.name.clone());
+                collect_names(body, names);
+            }
+        }
+    }
+    for statement in statements {
+        match statement {
+            crate::tree::Statement::Branch { .. } => {}
+            crate::tree::Statement::Label { .. } => {}
+            crate::tree::Statement::Mark { .. } => {}
+            crate::tree::Statement::Assign { name, value } => {
+                names.insert(name.clone());
+                collect_expression_names(value, names);
+            }
+            crate::tree::Statement::Call { actuals, .. } => {
+                for actual in actuals {
+                    collect_expression_names(actual, names);
+                }
+            }
+            crate::tree::Statement::Receive { source, variable } => {
+                names.insert(source.clone());
+                names.insert(variable.clone());
+            }
+            crate::tree::Statement::Send {
+                destination,
+                variable,
+            } => {
+                names.insert(destination.clone());
+                names.insert(variable.clone());
+            }
+            crate::tree::Statement::Offer {
+                client,
+                accepted,
+                denied,
+            } => {
+                names.insert(client.clone());
+                collect_names(accepted, names);
+                collect_names(denied, names);
+            }
+            crate::tree::Statement::Select { arms } => {
+                for arm in arms {
+                    match arm {
+                        crate::tree::Arm::Offer {
+                            client,
+                            accepted,
+                            denied,
+                        } => {
+                            names.insert(client.clone());
+                            collect_names(accepted, names);
+                            collect_names(denied, names);
+                        }
+                        crate::tree::Arm::Receive {
+                            source,
+                            variable,
+                            body,
+                        } => {
+                            names.insert(source.clone());
+                            names.insert(variable.clone());
+                            collect_names(body, names);
+                        }
+                    }
+                }
+            }
+            crate::tree::Statement::Accept { server } => {
+                names.insert(server.clone());
+            }
+            crate::tree::Statement::Deny { server } => {
+                names.insert(server.clone());
+            }
+            crate::tree::Statement::Close { name } => {
+                names.insert(name.clone());
+            }
+            crate::tree::Statement::Wait { name } => {
+                names.insert(name.clone());
+            }
+            crate::tree::Statement::Absurd { name } => {
+                names.insert(name.clone());
+            }
+            crate::tree::Statement::Connect { left, right } => {
+                names.insert(left.clone());
+                names.insert(right.clone());
+            }
+            crate::tree::Statement::Acquire { name } => {
+                names.insert(name.clone());
+            }
+            crate::tree::Statement::Release { name } => {
+                names.insert(name.clone());
+            }
+            crate::tree::Statement::Share { name, copy } => {
+                names.insert(name.clone());
+                names.insert(copy.clone());
+            }
+            crate::tree::Statement::Loop { body } => collect_names(body, names),
+            crate::tree::Statement::While { client, body } => {
+                names.insert(client.clone());
+                collect_names(body, names);
+            }
+            crate::tree::Statement::Break => {}
+            crate::tree::Statement::Continue => {}
+            crate::tree::Statement::Construct { name, fields, .. } => {
+                names.insert(name.clone());
+                names.extend(fields.iter().cloned());
+            }
+            crate::tree::Statement::Match { name, cases } => {
+                names.insert(name.clone());
+                for case in cases {
+                    names.extend(case.variables.iter().cloned());
+                    collect_names(&case.body, names);
+                }
+            }
+            crate::tree::Statement::Try { body, handler } => {
+                collect_names(body, names);
+                collect_names(handler, names);
+            }
+            crate::tree::Statement::Raise => {}
+            crate::tree::Statement::Assert { value, .. } => collect_expression_names(value, names),
+        }
+    }
+}
+
+type Callback<C, T> = std::boxed::Box<
+    dyn FnOnce(&mut C, &T) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>>,
+>;
+
 enum Patch<C, T> {
-    Unpatched(
-        std::vec::Vec<
-            std::boxed::Box<
-                dyn FnOnce(
-                    &mut C,
-                    &T,
-                )
-                    -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>>,
-            >,
-        >,
-    ),
+    Unpatched(std::vec::Vec<Callback<C, T>>),
     Patched(T),
 }
 
@@ -480,13 +1341,7 @@ impl<C, T> Patch<C, T> {
     fn call_back(
         &mut self,
         context: &mut C,
-        callback: std::boxed::Box<
-            dyn FnOnce(
-                &mut C,
-                &T,
-            )
-                -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>>,
-        >,
+        callback: Callback<C, T>,
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
         match self {
             Patch::Unpatched(callbacks) => callbacks.push(callback),
@@ -495,3 +1350,89 @@ impl<C, T> Patch<C, T> {
         Ok(())
     }
 }
+
+fn is_completing(statements: &[crate::tree::Statement]) -> bool {
+    let mut is_reachable = true;
+    for statement in statements {
+        match statement {
+            crate::tree::Statement::Label { name } => {
+                is_reachable = is_reachable || has_branch(statements, name);
+            }
+            crate::tree::Statement::Mark { .. } => {}
+            statement => is_reachable = is_reachable && is_statement_completing(statement),
+        }
+    }
+    is_reachable
+}
+
+fn is_statement_completing(statement: &crate::tree::Statement) -> bool {
+    match statement {
+        crate::tree::Statement::Branch { .. }
+        | crate::tree::Statement::Break
+        | crate::tree::Statement::Continue
+        | crate::tree::Statement::Raise
+        | crate::tree::Statement::Absurd { .. } => false,
+        crate::tree::Statement::Offer {
+            accepted, denied, ..
+        } => is_completing(accepted) || is_completing(denied),
+        crate::tree::Statement::Select { arms } => arms.iter().any(|arm| match arm {
+            crate::tree::Arm::Offer {
+                accepted, denied, ..
+            } => is_completing(accepted) || is_completing(denied),
+            crate::tree::Arm::Receive { body, .. } => is_completing(body),
+        }),
+        crate::tree::Statement::Loop { body } => has_break(body),
+        crate::tree::Statement::Match { cases, .. } => {
+            cases.iter().any(|case| is_completing(&case.body))
+        }
+        crate::tree::Statement::Try { body, handler } => {
+            is_completing(body) || is_completing(handler)
+        }
+        _ => true,
+    }
+}
+
+fn has_break(statements: &[crate::tree::Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        crate::tree::Statement::Break => true,
+        crate::tree::Statement::Offer {
+            accepted, denied, ..
+        } => has_break(accepted) || has_break(denied),
+        crate::tree::Statement::Select { arms } => arms.iter().any(|arm| match arm {
+            crate::tree::Arm::Offer {
+                accepted, denied, ..
+            } => has_break(accepted) || has_break(denied),
+            crate::tree::Arm::Receive { body, .. } => has_break(body),
+        }),
+        crate::tree::Statement::Match { cases, .. } => {
+            cases.iter().any(|case| has_break(&case.body))
+        }
+        crate::tree::Statement::Try { body, handler } => has_break(body) || has_break(handler),
+        _ => false,
+    })
+}
+
+fn has_branch(statements: &[crate::tree::Statement], label: &str) -> bool {
+    statements.iter().any(|statement| match statement {
+        crate::tree::Statement::Branch { name } => name == label,
+        crate::tree::Statement::Offer {
+            accepted, denied, ..
+        } => has_branch(accepted, label) || has_branch(denied, label),
+        crate::tree::Statement::Select { arms } => arms.iter().any(|arm| match arm {
+            crate::tree::Arm::Offer {
+                accepted, denied, ..
+            } => has_branch(accepted, label) || has_branch(denied, label),
+            crate::tree::Arm::Receive { body, .. } => has_branch(body, label),
+        }),
+        crate::tree::Statement::Loop { body } | crate::tree::Statement::While { body, .. } => {
+            has_branch(body, label)
+        }
+        crate::tree::Statement::Match { cases, .. } => {
+            cases.iter().any(|case| has_branch(&case.body, label))
+        }
+        crate::tree::Statement::Try { body, handler } => {
+            has_branch(body, label) || has_branch(handler, label)
+        }
+        _ => false,
+    })
+}
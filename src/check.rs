@@ -8,6 +8,12 @@ pub enum Error {
     NotWith(std::string::String),
     NotPlus(std::string::String),
     NotOne(std::string::String),
+    NotBottom(std::string::String),
+    NotZero(std::string::String),
+    NotShared(std::string::String),
+    NotRelease(std::string::String),
+    ReleaseMismatch(std::string::String),
+    NotUnary(std::string::String),
     TypeMismatch,
     NotInReverseTopologicalOrder,
 }
@@ -36,6 +42,24 @@ impl std::fmt::Display for Error {
             Error::NotOne(name) => {
                 write!(f, "{} is not one", name)?;
             }
+            Error::NotBottom(name) => {
+                write!(f, "{} is not bottom", name)?;
+            }
+            Error::NotZero(name) => {
+                write!(f, "{} is not zero", name)?;
+            }
+            Error::NotShared(name) => {
+                write!(f, "{} is not shared", name)?;
+            }
+            Error::NotRelease(name) => {
+                write!(f, "{} is not release", name)?;
+            }
+            Error::ReleaseMismatch(name) => {
+                write!(f, "{} is not released at the type it was acquired at", name)?;
+            }
+            Error::NotUnary(name) => {
+                write!(f, "literal {} does not have a type like zero | succ", name)?;
+            }
             Error::TypeMismatch => {
                 write!(f, "type mismatch")?;
             }
@@ -52,14 +76,86 @@ impl std::error::Error for Error {}
 pub fn check(
     graph: &crate::graph::Graph,
 ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    get_gammas(graph)?;
+    Ok(())
+}
+
+pub type Gamma = std::collections::HashMap<std::string::String, usize>;
+
+pub fn get_gammas(
+    graph: &crate::graph::Graph,
+) -> std::result::Result<
+    std::vec::Vec<std::option::Option<Gamma>>,
+    std::boxed::Box<dyn std::error::Error>,
+> {
     let mut checker = Checker::with_graph(graph);
     for routine in graph.routinees.values() {
         checker.initialize_routine(routine);
     }
+    for test in graph.testes.iter() {
+        checker.set_gamma(test.start, std::collections::HashMap::new())?;
+    }
+    for literal in graph.literales.iter() {
+        if !checker.is_unary(literal.r#type) {
+            return Err(std::boxed::Box::new(Error::NotUnary(literal.name.clone())));
+        }
+    }
     for index in (0..graph.nodees.len()).rev() {
         checker.check_node(index)?;
     }
-    Ok(())
+    Ok(checker.typees)
+}
+
+pub fn is_subtype(graph: &crate::graph::Graph, sub: usize, sup: usize) -> bool {
+    let mut checker = Checker::with_graph(graph);
+    checker.is_subtype(sub, sup)
+}
+
+pub fn find_counterexample(
+    graph: &crate::graph::Graph,
+    sub: usize,
+    sup: usize,
+) -> std::option::Option<Counterexample> {
+    let mut checker = Checker::with_graph(graph);
+    checker.find_counterexample(sub, sup)
+}
+
+type Entry = (usize, usize, bool, std::option::Option<(usize, Step)>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Step {
+    Value,
+    Next,
+    Accept,
+    Deny,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Value => {
+                write!(f, "value")?;
+            }
+            Step::Next => {
+                write!(f, "next")?;
+            }
+            Step::Accept => {
+                write!(f, "accept")?;
+            }
+            Step::Deny => {
+                write!(f, "deny")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Counterexample {
+    pub trace: std::vec::Vec<Step>,
+    pub sub: usize,
+    pub sup: usize,
+    pub is_flipped: bool,
 }
 
 struct Epsilon(std::vec::Vec<usize>);
@@ -111,20 +207,24 @@ impl<'a> Checker<'a> {
         let mut times = std::vec::Vec::new();
         let mut with = std::vec::Vec::new();
         let mut plus = std::vec::Vec::new();
+        let mut shared = std::vec::Vec::new();
+        let mut service = std::vec::Vec::new();
+        let mut release = std::vec::Vec::new();
+        let mut detach = std::vec::Vec::new();
         let mut one = std::vec::Vec::new();
+        let mut bottom = std::vec::Vec::new();
+        let mut zero = std::vec::Vec::new();
+        let mut top = std::vec::Vec::new();
         for (index, node) in graph.typees.iter().enumerate() {
-            match node {
-                crate::graph::TypeNode::Variable { node, is_dual, .. } => {
-                    epsilon.add(
-                        index,
-                        if *is_dual {
-                            crate::graph::get_dual(&graph.typees, *node)
-                        } else {
-                            *node
-                        },
-                    );
-                }
-                _ => {}
+            if let crate::graph::TypeNode::Variable { node, is_dual, .. } = node {
+                epsilon.add(
+                    index,
+                    if *is_dual {
+                        crate::graph::get_dual(&graph.typees, *node)
+                    } else {
+                        *node
+                    },
+                );
             }
         }
         for (index, node) in graph.typees.iter().enumerate() {
@@ -149,11 +249,46 @@ impl<'a> Checker<'a> {
                     right[epsilon.get(*deny)].push(index);
                     plus.push(index);
                 }
-                crate::graph::TypeNode::One => {
+                crate::graph::TypeNode::Shared { value, .. } => {
+                    left[epsilon.get(*value)].push(index);
+                    right[graph.typees.len()].push(index);
+                    shared.push(index);
+                }
+                crate::graph::TypeNode::Service { value, .. } => {
+                    left[epsilon.get(*value)].push(index);
+                    right[graph.typees.len()].push(index);
+                    service.push(index);
+                }
+                crate::graph::TypeNode::Release { value, .. } => {
+                    left[epsilon.get(*value)].push(index);
+                    right[graph.typees.len()].push(index);
+                    release.push(index);
+                }
+                crate::graph::TypeNode::Detach { value, .. } => {
+                    left[epsilon.get(*value)].push(index);
+                    right[graph.typees.len()].push(index);
+                    detach.push(index);
+                }
+                crate::graph::TypeNode::One { .. } => {
                     left[graph.typees.len()].push(index);
                     right[graph.typees.len()].push(index);
                     one.push(index);
                 }
+                crate::graph::TypeNode::Bottom { .. } => {
+                    left[graph.typees.len()].push(index);
+                    right[graph.typees.len()].push(index);
+                    bottom.push(index);
+                }
+                crate::graph::TypeNode::Zero { .. } => {
+                    left[graph.typees.len()].push(index);
+                    right[graph.typees.len()].push(index);
+                    zero.push(index);
+                }
+                crate::graph::TypeNode::Top { .. } => {
+                    left[graph.typees.len()].push(index);
+                    right[graph.typees.len()].push(index);
+                    top.push(index);
+                }
                 _ => {}
             }
         }
@@ -162,7 +297,10 @@ impl<'a> Checker<'a> {
         let mut next_partitions = std::collections::BTreeSet::new();
         let mut worklist = std::collections::BTreeSet::new();
         let mut last = 0;
-        for list in [&lollipop, &times, &with, &plus, &one] {
+        for list in [
+            &lollipop, &times, &with, &plus, &shared, &service, &release, &detach, &one, &bottom,
+            &zero, &top,
+        ] {
             for index in list.iter() {
                 permutation.push(*index);
             }
@@ -230,6 +368,184 @@ impl<'a> Checker<'a> {
             classs,
         }
     }
+    fn is_subtype(&mut self, sub: usize, sup: usize) -> bool {
+        self.find_counterexample(sub, sup).is_none()
+    }
+    fn is_unary(&mut self, r#type: usize) -> bool {
+        let r#type = self.epsilon.get(r#type);
+        match self.graph.typees[r#type] {
+            crate::graph::TypeNode::Plus { accept, deny, .. } => {
+                let accept = self.epsilon.get(accept);
+                let deny = self.epsilon.get(deny);
+                matches!(
+                    self.graph.typees[accept],
+                    crate::graph::TypeNode::One { .. }
+                ) && self.classs[deny] == self.classs[r#type]
+            }
+            _ => false,
+        }
+    }
+    fn find_counterexample(
+        &mut self,
+        sub: usize,
+        sup: usize,
+    ) -> std::option::Option<Counterexample> {
+        let mut visited = std::collections::HashSet::new();
+        let mut entries: std::vec::Vec<Entry> = vec![(sub, sup, false, None)];
+        let mut worklist = vec![0];
+        while let Some(entry) = worklist.pop() {
+            let (sub, sup, is_flipped, _) = entries[entry];
+            let sub = self.epsilon.get(sub);
+            let sup = self.epsilon.get(sup);
+            if self.classs[sub] == self.classs[sup]
+                || !visited.insert((self.classs[sub], self.classs[sup]))
+            {
+                continue;
+            }
+            let successors = match (&self.graph.typees[sub], &self.graph.typees[sup]) {
+                (crate::graph::TypeNode::Zero { .. }, _) => vec![],
+                (
+                    crate::graph::TypeNode::Lollipop {
+                        value: sub_value,
+                        next: sub_next,
+                        ..
+                    },
+                    crate::graph::TypeNode::Lollipop {
+                        value: sup_value,
+                        next: sup_next,
+                        ..
+                    },
+                ) => vec![
+                    (*sup_value, *sub_value, !is_flipped, Step::Value),
+                    (*sub_next, *sup_next, is_flipped, Step::Next),
+                ],
+                (
+                    crate::graph::TypeNode::Times {
+                        value: sub_value,
+                        next: sub_next,
+                        ..
+                    },
+                    crate::graph::TypeNode::Times {
+                        value: sup_value,
+                        next: sup_next,
+                        ..
+                    },
+                ) => vec![
+                    (*sub_value, *sup_value, is_flipped, Step::Value),
+                    (*sub_next, *sup_next, is_flipped, Step::Next),
+                ],
+                (
+                    crate::graph::TypeNode::With {
+                        accept: sub_accept,
+                        deny: sub_deny,
+                        ..
+                    },
+                    crate::graph::TypeNode::With {
+                        accept: sup_accept,
+                        deny: sup_deny,
+                        ..
+                    },
+                ) => vec![
+                    (*sub_accept, *sup_accept, is_flipped, Step::Accept),
+                    (*sub_deny, *sup_deny, is_flipped, Step::Deny),
+                ],
+                (
+                    crate::graph::TypeNode::Plus {
+                        accept: sub_accept,
+                        deny: sub_deny,
+                        ..
+                    },
+                    crate::graph::TypeNode::Plus {
+                        accept: sup_accept,
+                        deny: sup_deny,
+                        ..
+                    },
+                ) => vec![
+                    (*sub_accept, *sup_accept, is_flipped, Step::Accept),
+                    (*sub_deny, *sup_deny, is_flipped, Step::Deny),
+                ],
+                (
+                    crate::graph::TypeNode::Shared {
+                        value: sub_value, ..
+                    },
+                    crate::graph::TypeNode::Shared {
+                        value: sup_value, ..
+                    },
+                )
+                | (
+                    crate::graph::TypeNode::Service {
+                        value: sub_value, ..
+                    },
+                    crate::graph::TypeNode::Service {
+                        value: sup_value, ..
+                    },
+                )
+                | (
+                    crate::graph::TypeNode::Release {
+                        value: sub_value, ..
+                    },
+                    crate::graph::TypeNode::Release {
+                        value: sup_value, ..
+                    },
+                )
+                | (
+                    crate::graph::TypeNode::Detach {
+                        value: sub_value, ..
+                    },
+                    crate::graph::TypeNode::Detach {
+                        value: sup_value, ..
+                    },
+                ) => vec![(*sub_value, *sup_value, is_flipped, Step::Next)],
+                _ => {
+                    let mut trace = vec![];
+                    let mut current = entry;
+                    while let (_, _, _, Some((parent, step))) = entries[current] {
+                        trace.push(step);
+                        current = parent;
+                    }
+                    trace.reverse();
+                    return Some(Counterexample {
+                        trace,
+                        sub,
+                        sup,
+                        is_flipped,
+                    });
+                }
+            };
+            for (next_sub, next_sup, is_flipped, step) in successors {
+                worklist.push(entries.len());
+                entries.push((next_sub, next_sup, is_flipped, Some((entry, step))));
+            }
+        }
+        None
+    }
+    fn is_released_at(&mut self, value: usize, shared: usize) -> bool {
+        let mut visited = std::collections::HashSet::new();
+        let mut indexs = vec![value];
+        while let Some(index) = indexs.pop() {
+            let index = self.epsilon.get(index);
+            if !visited.insert(index) {
+                continue;
+            }
+            match self.graph.typees[index] {
+                crate::graph::TypeNode::Lollipop { next, .. }
+                | crate::graph::TypeNode::Times { next, .. } => indexs.push(next),
+                crate::graph::TypeNode::With { accept, deny, .. }
+                | crate::graph::TypeNode::Plus { accept, deny, .. } => {
+                    indexs.push(accept);
+                    indexs.push(deny);
+                }
+                crate::graph::TypeNode::Release { value, .. }
+                | crate::graph::TypeNode::Detach { value, .. }
+                    if self.classs[self.epsilon.get(value)] != self.classs[shared] =>
+                {
+                    return false;
+                }
+                _ => {}
+            }
+        }
+        true
+    }
     fn initialize_routine(&mut self, routine: &crate::graph::Routine) {
         let mut gamma = std::collections::HashMap::new();
         for crate::graph::Formal { name, r#type } in routine.formals.iter() {
@@ -240,9 +556,19 @@ impl<'a> Checker<'a> {
     fn set_gamma(
         &mut self,
         index: usize,
-        gamma: std::collections::HashMap<std::string::String, usize>,
+        mut gamma: std::collections::HashMap<std::string::String, usize>,
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
-        if let Some(delta) = &self.typees[index] {
+        if self.graph.nodees[index] == crate::graph::Node::End {
+            gamma.retain(|_, r#type| {
+                !matches!(
+                    self.graph.typees[*r#type],
+                    crate::graph::TypeNode::Shared { .. }
+                )
+            });
+        }
+        if self.graph.nodees[index] == crate::graph::Node::Drop {
+            self.typees[index].get_or_insert(gamma);
+        } else if let Some(delta) = &self.typees[index] {
             if delta.len() > gamma.len() {
                 for name in delta.keys() {
                     if !gamma.contains_key(name) {
@@ -274,7 +600,7 @@ impl<'a> Checker<'a> {
                 if let Some(r#type) = gamma.remove(name) {
                     Ok(r#type)
                 } else {
-                    return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                    Err(std::boxed::Box::new(Error::Closed(name.clone())))
                 }
             }
             crate::graph::Expression::Call {
@@ -285,13 +611,13 @@ impl<'a> Checker<'a> {
                 let formals = &self.graph.routinees.get(name).unwrap().formals;
                 for (index, actual) in (0..).zip(before) {
                     let r#type = self.check_expression(gamma, actual)?;
-                    if self.classs[formals[index].r#type] != self.classs[r#type] {
+                    if !self.is_subtype(r#type, formals[index].r#type) {
                         return Err(std::boxed::Box::new(Error::TypeMismatch));
                     }
                 }
                 for (index, actual) in (before.len() + 1..).zip(after) {
                     let r#type = self.check_expression(gamma, actual)?;
-                    if self.classs[formals[index].r#type] != self.classs[r#type] {
+                    if !self.is_subtype(r#type, formals[index].r#type) {
                         return Err(std::boxed::Box::new(Error::TypeMismatch));
                     }
                 }
@@ -300,7 +626,31 @@ impl<'a> Checker<'a> {
                     formals[before.len()].r#type,
                 ))
             }
+            crate::graph::Expression::Fork { fork, names } => {
+                let fork = &self.graph.forkes[*fork];
+                let mut delta = std::collections::HashMap::new();
+                for name in names {
+                    if let Some(r#type) = gamma.remove(name) {
+                        delta.insert(name.clone(), r#type);
+                    }
+                }
+                let formal = &fork.formals[0];
+                delta.insert(formal.name.clone(), self.epsilon.get(formal.r#type));
+                self.set_gamma(fork.start, delta)?;
+                Ok(crate::graph::get_dual(&self.graph.typees, formal.r#type))
+            }
+        }
+    }
+    fn raise(
+        &mut self,
+        r#try: usize,
+        mut gamma: std::collections::HashMap<std::string::String, usize>,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        let r#try = &self.graph.tryes[r#try];
+        for name in r#try.names.iter() {
+            gamma.remove(name);
         }
+        self.set_gamma(r#try.handler, gamma)
     }
     fn check_node(
         &mut self,
@@ -311,6 +661,11 @@ impl<'a> Checker<'a> {
         } else {
             return Err(std::boxed::Box::new(Error::NotInReverseTopologicalOrder));
         };
+        if let Some(r#try) = self.graph.node_tryes.get(&index)
+            && !crate::graph::get_principals(&self.graph.nodees[index]).is_empty()
+        {
+            self.raise(*r#try, gamma.clone())?;
+        }
         match &self.graph.nodees[index] {
             crate::graph::Node::Branch { next } => {
                 self.set_gamma(*next, gamma)?;
@@ -333,7 +688,7 @@ impl<'a> Checker<'a> {
                 let formals = &self.graph.routinees.get(name).unwrap().formals;
                 for (formal, actual) in formals.iter().zip(actuals) {
                     let r#type = self.check_expression(&mut gamma, actual)?;
-                    if self.classs[formal.r#type] != self.classs[r#type] {
+                    if !self.is_subtype(r#type, formal.r#type) {
                         return Err(std::boxed::Box::new(Error::TypeMismatch));
                     }
                 }
@@ -384,7 +739,7 @@ impl<'a> Checker<'a> {
                         ..
                     } => {
                         if let Some(r#type) = gamma.remove(variable) {
-                            if self.classs[r#type] != self.classs[*value] {
+                            if !self.is_subtype(r#type, *value) {
                                 return Err(std::boxed::Box::new(Error::TypeMismatch));
                             }
                         } else {
@@ -421,6 +776,62 @@ impl<'a> Checker<'a> {
                     _ => return Err(std::boxed::Box::new(Error::NotPlus(client.clone()))),
                 }
             }
+            crate::graph::Node::Select { arms } => {
+                for arm in arms {
+                    let mut gamma = gamma.clone();
+                    let name = arm.get_name();
+                    let r#type = if let Some(r#type) = gamma.get(name) {
+                        self.epsilon.get(*r#type)
+                    } else {
+                        return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                    };
+                    match (arm, &self.graph.typees[r#type]) {
+                        (
+                            crate::graph::Arm::Offer {
+                                client,
+                                accepted,
+                                denied,
+                            },
+                            crate::graph::TypeNode::Plus { accept, deny, .. },
+                        ) => {
+                            let mut delta = gamma.clone();
+                            gamma.insert(client.clone(), self.epsilon.get(*accept));
+                            delta.insert(client.clone(), self.epsilon.get(*deny));
+                            self.set_gamma(*accepted, gamma)?;
+                            self.set_gamma(*denied, delta)?;
+                        }
+                        (crate::graph::Arm::Offer { client, .. }, _) => {
+                            return Err(std::boxed::Box::new(Error::NotPlus(client.clone())));
+                        }
+                        (
+                            crate::graph::Arm::Receive {
+                                source,
+                                variable,
+                                next,
+                            },
+                            crate::graph::TypeNode::Times {
+                                value,
+                                next: next_type,
+                                ..
+                            },
+                        ) => {
+                            if gamma
+                                .insert(variable.clone(), self.epsilon.get(*value))
+                                .is_some()
+                            {
+                                return Err(std::boxed::Box::new(Error::NotClosed(
+                                    variable.clone(),
+                                )));
+                            }
+                            gamma.insert(source.clone(), self.epsilon.get(*next_type));
+                            self.set_gamma(*next, gamma)?;
+                        }
+                        (crate::graph::Arm::Receive { source, .. }, _) => {
+                            return Err(std::boxed::Box::new(Error::NotTimes(source.clone())));
+                        }
+                    }
+                }
+            }
             crate::graph::Node::Accept { server, next } => {
                 let r#type = if let Some(r#type) = gamma.get(server) {
                     r#type
@@ -455,10 +866,87 @@ impl<'a> Checker<'a> {
                 } else {
                     return Err(std::boxed::Box::new(Error::Closed(name.clone())));
                 };
-                if self.graph.typees[self.epsilon.get(r#type)] != crate::graph::TypeNode::One {
-                    return Err(std::boxed::Box::new(Error::NotOne(name.clone())));
+                match &self.graph.typees[self.epsilon.get(r#type)] {
+                    crate::graph::TypeNode::One { .. } => self.set_gamma(*next, gamma)?,
+                    _ => return Err(std::boxed::Box::new(Error::NotOne(name.clone()))),
+                }
+            }
+            crate::graph::Node::Wait { name, next } => {
+                let r#type = if let Some(r#type) = gamma.remove(name) {
+                    r#type
+                } else {
+                    return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                };
+                match &self.graph.typees[self.epsilon.get(r#type)] {
+                    crate::graph::TypeNode::Bottom { .. } => self.set_gamma(*next, gamma)?,
+                    _ => return Err(std::boxed::Box::new(Error::NotBottom(name.clone()))),
+                }
+            }
+            crate::graph::Node::Absurd { name } => {
+                let r#type = if let Some(r#type) = gamma.get(name) {
+                    r#type
+                } else {
+                    return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                };
+                match &self.graph.typees[self.epsilon.get(*r#type)] {
+                    crate::graph::TypeNode::Zero { .. } => {}
+                    _ => return Err(std::boxed::Box::new(Error::NotZero(name.clone()))),
+                }
+            }
+            crate::graph::Node::Acquire { name, next } => {
+                let r#type = if let Some(r#type) = gamma.get(name) {
+                    r#type
+                } else {
+                    return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                };
+                let r#type = self.epsilon.get(*r#type);
+                match &self.graph.typees[r#type] {
+                    crate::graph::TypeNode::Shared { value, .. }
+                    | crate::graph::TypeNode::Service { value, .. } => {
+                        if !self.is_released_at(*value, r#type) {
+                            return Err(std::boxed::Box::new(Error::ReleaseMismatch(name.clone())));
+                        }
+                        gamma.insert(name.clone(), self.epsilon.get(*value));
+                        self.set_gamma(*next, gamma)?;
+                    }
+                    _ => return Err(std::boxed::Box::new(Error::NotShared(name.clone()))),
+                }
+            }
+            crate::graph::Node::Release { name, next } => {
+                let r#type = if let Some(r#type) = gamma.get(name) {
+                    r#type
+                } else {
+                    return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                };
+                let value = match &self.graph.typees[self.epsilon.get(*r#type)] {
+                    crate::graph::TypeNode::Release { value, .. }
+                    | crate::graph::TypeNode::Detach { value, .. } => self.epsilon.get(*value),
+                    _ => return Err(std::boxed::Box::new(Error::NotRelease(name.clone()))),
+                };
+                match &self.graph.typees[value] {
+                    crate::graph::TypeNode::Shared { .. }
+                    | crate::graph::TypeNode::Service { .. } => {
+                        gamma.insert(name.clone(), value);
+                        self.set_gamma(*next, gamma)?;
+                    }
+                    _ => return Err(std::boxed::Box::new(Error::NotShared(name.clone()))),
+                }
+            }
+            crate::graph::Node::Share { name, copy, next } => {
+                let r#type = if let Some(r#type) = gamma.get(name) {
+                    *r#type
+                } else {
+                    return Err(std::boxed::Box::new(Error::Closed(name.clone())));
+                };
+                match &self.graph.typees[self.epsilon.get(r#type)] {
+                    crate::graph::TypeNode::Shared { .. } => {
+                        if gamma.insert(copy.clone(), r#type).is_some() {
+                            return Err(std::boxed::Box::new(Error::NotClosed(copy.clone())));
+                        }
+                        self.set_gamma(*next, gamma)?;
+                    }
+                    _ => return Err(std::boxed::Box::new(Error::NotShared(name.clone()))),
                 }
-                self.set_gamma(*next, gamma)?;
             }
             crate::graph::Node::Connect { left, right, next } => {
                 let left_type = if let Some(left_type) = gamma.remove(left) {
@@ -485,6 +973,20 @@ impl<'a> Checker<'a> {
                     return Err(std::boxed::Box::new(Error::NotClosed(name.clone())));
                 }
             }
+            crate::graph::Node::Drop => {}
+            crate::graph::Node::Fail { .. } => {}
+            crate::graph::Node::Decide {
+                accepted, denied, ..
+            } => {
+                self.set_gamma(*accepted, gamma.clone())?;
+                self.set_gamma(*denied, gamma)?;
+            }
+            crate::graph::Node::Report { next, .. } => {
+                self.set_gamma(*next, gamma)?;
+            }
+            crate::graph::Node::Raise { r#try } => {
+                self.raise(*r#try, gamma)?;
+            }
         }
         Ok(())
     }
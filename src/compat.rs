@@ -0,0 +1,387 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    Incompatible,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Incompatible => {
+                write!(f, "incompatible changes")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Declaration {
+    Type(std::string::String),
+    Routine(std::string::String),
+}
+
+impl std::fmt::Display for Declaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Declaration::Type(name) => {
+                write!(f, "type {}", name)?;
+            }
+            Declaration::Routine(name) => {
+                write!(f, "routine {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Change {
+    Unchanged,
+    Added,
+    Removed,
+    Compatible,
+    WrongFormalCount,
+    ClientBreaking(Break),
+    ServerBreaking(Break),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Break {
+    pub formal: std::option::Option<std::string::String>,
+    pub trace: std::vec::Vec<crate::check::Step>,
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+impl Change {
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Change::Unchanged => false,
+            Change::Added => false,
+            Change::Removed => true,
+            Change::Compatible => false,
+            Change::WrongFormalCount => true,
+            Change::ClientBreaking(_) => true,
+            Change::ServerBreaking(_) => true,
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Unchanged => {
+                write!(f, "unchanged")?;
+            }
+            Change::Added => {
+                write!(f, "added, compatible")?;
+            }
+            Change::Removed => {
+                write!(f, "removed, client-breaking")?;
+            }
+            Change::Compatible => {
+                write!(f, "compatible")?;
+            }
+            Change::WrongFormalCount => {
+                write!(
+                    f,
+                    "formal count changed, client-breaking and server-breaking"
+                )?;
+            }
+            Change::ClientBreaking(r#break) => {
+                write!(f, "client-breaking{}", r#break)?;
+            }
+            Change::ServerBreaking(r#break) => {
+                write!(f, "server-breaking{}", r#break)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Break {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(formal) = &self.formal {
+            write!(f, " at formal {}", formal)?;
+        }
+        write!(f, " after [")?;
+        for (index, step) in self.trace.iter().enumerate() {
+            if index != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", step)?;
+        }
+        write!(f, "]: old is {}, new is {}", self.old, self.new)?;
+        Ok(())
+    }
+}
+
+pub type Report = std::vec::Vec<(Declaration, std::vec::Vec<Change>)>;
+
+pub fn compat(
+    old: crate::tree::Tree,
+    new: crate::tree::Tree,
+) -> std::result::Result<Report, std::boxed::Box<dyn std::error::Error>> {
+    crate::check::check(&crate::anal::anal(old.clone())?)?;
+    crate::check::check(&crate::anal::anal(new.clone())?)?;
+    let (mut old, mut new) = (old, new);
+    for tree in [&mut old, &mut new] {
+        let dataes = std::mem::take(&mut tree.dataes);
+        tree.typees
+            .extend(dataes.iter().map(crate::anal::get_data_type));
+    }
+    let mut typees = std::vec::Vec::new();
+    for r#type in old.typees.iter() {
+        typees.push(rename_type(r#type, "old"));
+    }
+    for r#type in new.typees.iter() {
+        typees.push(rename_type(r#type, "new"));
+    }
+    let mut pairs = std::collections::BTreeMap::new();
+    for r#type in old.typees.iter() {
+        pairs
+            .entry(Declaration::Type(r#type.name.clone()))
+            .or_insert((None, None))
+            .0 = Some(vec![(
+            None,
+            crate::tree::TypeExpression::Variable {
+                name: rename_name(&r#type.name, "old"),
+                is_dual: false,
+            },
+        )]);
+    }
+    for r#type in new.typees.iter() {
+        pairs
+            .entry(Declaration::Type(r#type.name.clone()))
+            .or_insert((None, None))
+            .1 = Some(vec![(
+            None,
+            crate::tree::TypeExpression::Variable {
+                name: rename_name(&r#type.name, "new"),
+                is_dual: false,
+            },
+        )]);
+    }
+    for (routinees, prefix, is_old) in [
+        (&old.routinees, "old", true),
+        (&new.routinees, "new", false),
+    ] {
+        for routine in routinees.iter() {
+            let mut formals = std::vec::Vec::with_capacity(routine.formals.len());
+            for formal in routine.formals.iter() {
+                let mut bound = std::vec::Vec::new();
+                formals.push((
+                    Some(formal.name.clone()),
+                    rename_type_expression(&formal.r#type, prefix, &mut bound),
+                ));
+            }
+            let pair = pairs
+                .entry(Declaration::Routine(routine.name.clone()))
+                .or_insert((None, None));
+            if is_old {
+                pair.0 = Some(formals);
+            } else {
+                pair.1 = Some(formals);
+            }
+        }
+    }
+    let mut expressions = std::vec::Vec::new();
+    for (old, new) in pairs.values() {
+        for formals in [old, new].into_iter().flatten() {
+            for (_, expression) in formals.iter() {
+                expressions.push(expression.clone());
+            }
+        }
+    }
+    let tree = crate::tree::Tree {
+        typees,
+        routinees: vec![],
+        protocoles: vec![],
+        dataes: vec![],
+        importes: vec![],
+        publics: std::collections::BTreeSet::new(),
+        preludees: std::collections::BTreeSet::new(),
+        testes: vec![],
+    };
+    let (graph, indexs) = crate::anal::anal_with_type_expressions(tree, expressions)?;
+    let mut indexs = indexs.into_iter();
+    let mut report = std::vec::Vec::with_capacity(pairs.len());
+    for (declaration, (old, new)) in pairs {
+        let old = old.map(|formals| {
+            formals
+                .into_iter()
+                .map(|(name, _)| (name, indexs.next().unwrap()))
+                .collect::<std::vec::Vec<_>>()
+        });
+        let new = new.map(|formals| {
+            formals
+                .into_iter()
+                .map(|(name, _)| (name, indexs.next().unwrap()))
+                .collect::<std::vec::Vec<_>>()
+        });
+        let changes = match (old, new) {
+            (Some(_), None) => vec![Change::Removed],
+            (None, Some(_)) => vec![Change::Added],
+            (Some(old), Some(new)) => {
+                if old.len() != new.len() {
+                    vec![Change::WrongFormalCount]
+                } else {
+                    let is_routine = matches!(declaration, Declaration::Routine(_));
+                    compare(&graph, &old, &new, is_routine)
+                }
+            }
+            (None, None) => unreachable!(),
+        };
+        report.push((declaration, changes));
+    }
+    report.retain(|(declaration, _)| match declaration {
+        Declaration::Type(name) | Declaration::Routine(name) => {
+            !old.preludees.contains(name) && !new.preludees.contains(name)
+        }
+    });
+    Ok(report)
+}
+
+fn compare(
+    graph: &crate::graph::Graph,
+    old: &[(std::option::Option<std::string::String>, usize)],
+    new: &[(std::option::Option<std::string::String>, usize)],
+    is_routine: bool,
+) -> std::vec::Vec<Change> {
+    let mut changes = std::vec::Vec::new();
+    let mut is_unchanged = true;
+    for ((formal, old), (_, new)) in old.iter().zip(new.iter()) {
+        let old_to_new = crate::check::find_counterexample(graph, *old, *new);
+        let new_to_old = crate::check::find_counterexample(graph, *new, *old);
+        if old_to_new.is_some() || new_to_old.is_some() {
+            is_unchanged = false;
+        }
+        let (client, server) = if is_routine {
+            (
+                old_to_new.map(|c| (c, true)),
+                new_to_old.map(|c| (c, false)),
+            )
+        } else {
+            (
+                new_to_old.map(|c| (c, false)),
+                old_to_new.map(|c| (c, true)),
+            )
+        };
+        if let Some((counterexample, is_old_sub)) = client {
+            changes.push(Change::ClientBreaking(make_break(
+                graph,
+                formal,
+                counterexample,
+                is_old_sub,
+            )));
+        }
+        if let Some((counterexample, is_old_sub)) = server {
+            changes.push(Change::ServerBreaking(make_break(
+                graph,
+                formal,
+                counterexample,
+                is_old_sub,
+            )));
+        }
+    }
+    if changes.is_empty() {
+        changes.push(if is_unchanged {
+            Change::Unchanged
+        } else {
+            Change::Compatible
+        });
+    }
+    changes
+}
+
+fn make_break(
+    graph: &crate::graph::Graph,
+    formal: &std::option::Option<std::string::String>,
+    counterexample: crate::check::Counterexample,
+    is_old_sub: bool,
+) -> Break {
+    let sub = crate::graph::get_name(&graph.typees, counterexample.sub);
+    let sup = crate::graph::get_name(&graph.typees, counterexample.sup);
+    let is_old = is_old_sub != counterexample.is_flipped;
+    let (old, new) = if is_old { (sub, sup) } else { (sup, sub) };
+    Break {
+        formal: formal.clone(),
+        trace: counterexample.trace,
+        old,
+        new,
+    }
+}
+
+fn rename_name(name: &str, prefix: &str) -> std::string::String {
+    format!("{}:{}", prefix, name)
+}
+
+fn rename_type(r#type: &crate::tree::Type, prefix: &str) -> crate::tree::Type {
+    let mut bound = std::vec::Vec::new();
+    crate::tree::Type {
+        name: rename_name(&r#type.name, prefix),
+        value: std::boxed::Box::new(rename_type_expression(&r#type.value, prefix, &mut bound)),
+    }
+}
+
+fn rename_type_expression(
+    expression: &crate::tree::TypeExpression,
+    prefix: &str,
+    bound: &mut std::vec::Vec<std::string::String>,
+) -> crate::tree::TypeExpression {
+    match expression {
+        crate::tree::TypeExpression::Variable { name, is_dual } => {
+            crate::tree::TypeExpression::Variable {
+                name: if bound.contains(name) {
+                    name.clone()
+                } else {
+                    rename_name(name, prefix)
+                },
+                is_dual: *is_dual,
+            }
+        }
+        crate::tree::TypeExpression::Lollipop { value, next } => {
+            crate::tree::TypeExpression::Lollipop {
+                value: std::boxed::Box::new(rename_type_expression(value, prefix, bound)),
+                next: std::boxed::Box::new(rename_type_expression(next, prefix, bound)),
+            }
+        }
+        crate::tree::TypeExpression::Times { value, next } => crate::tree::TypeExpression::Times {
+            value: std::boxed::Box::new(rename_type_expression(value, prefix, bound)),
+            next: std::boxed::Box::new(rename_type_expression(next, prefix, bound)),
+        },
+        crate::tree::TypeExpression::With { accept, deny } => crate::tree::TypeExpression::With {
+            accept: std::boxed::Box::new(rename_type_expression(accept, prefix, bound)),
+            deny: std::boxed::Box::new(rename_type_expression(deny, prefix, bound)),
+        },
+        crate::tree::TypeExpression::Plus { accept, deny } => crate::tree::TypeExpression::Plus {
+            accept: std::boxed::Box::new(rename_type_expression(accept, prefix, bound)),
+            deny: std::boxed::Box::new(rename_type_expression(deny, prefix, bound)),
+        },
+        crate::tree::TypeExpression::Dual { value } => crate::tree::TypeExpression::Dual {
+            value: std::boxed::Box::new(rename_type_expression(value, prefix, bound)),
+        },
+        crate::tree::TypeExpression::Recursive { name, value } => {
+            bound.push(name.clone());
+            let value = rename_type_expression(value, prefix, bound);
+            bound.pop();
+            crate::tree::TypeExpression::Recursive {
+                name: name.clone(),
+                value: std::boxed::Box::new(value),
+            }
+        }
+        crate::tree::TypeExpression::Shared { value } => crate::tree::TypeExpression::Shared {
+            value: std::boxed::Box::new(rename_type_expression(value, prefix, bound)),
+        },
+        crate::tree::TypeExpression::Release { value } => crate::tree::TypeExpression::Release {
+            value: std::boxed::Box::new(rename_type_expression(value, prefix, bound)),
+        },
+        crate::tree::TypeExpression::One => crate::tree::TypeExpression::One,
+        crate::tree::TypeExpression::Bottom => crate::tree::TypeExpression::Bottom,
+        crate::tree::TypeExpression::Zero => crate::tree::TypeExpression::Zero,
+        crate::tree::TypeExpression::Top => crate::tree::TypeExpression::Top,
+    }
+}
@@ -0,0 +1,513 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    UnknownCommand(std::string::String),
+    WrongArgumentCount,
+    InvalidArgument(std::string::String),
+    UnknownRoutine(std::string::String),
+    UnknownLabel(std::string::String),
+    UnknownBreakpoint(usize),
+    Ended,
+    NotRecording,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownCommand(command) => {
+                write!(f, "unknown command {:?}; try \"help\"", command)?;
+            }
+            Error::WrongArgumentCount => {
+                write!(f, "wrong argument count")?;
+            }
+            Error::InvalidArgument(argument) => {
+                write!(f, "invalid argument {:?}", argument)?;
+            }
+            Error::UnknownRoutine(name) => {
+                write!(f, "unknown routine {:?}", name)?;
+            }
+            Error::UnknownLabel(name) => {
+                write!(f, "unknown label {:?}", name)?;
+            }
+            Error::UnknownBreakpoint(number) => {
+                write!(f, "unknown breakpoint {}", number)?;
+            }
+            Error::Ended => {
+                write!(f, "the run has ended")?;
+            }
+            Error::NotRecording => {
+                write!(f, "not recording; debug with --record INTERVAL to go back")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+const HELP: &str = "\
+break routine NAME     stop when a process starts NAME
+break label NAME       stop before the statement after label NAME
+break line [FILE:]LINE stop before a statement on LINE
+delete NUMBER          remove a breakpoint
+breakpoints            list the breakpoints
+step [COUNT]           take COUNT steps, one by default
+continue               run until a breakpoint or the end
+until CHANNEL          run until something is sent, received, chosen or closed on CHANNEL
+reverse-step [COUNT]   go back COUNT steps, one by default
+reverse-continue       go back to the last step where a breakpoint is hit
+goto STEP              go to the run as it was after STEP steps
+children               show the active routine's channels and their types
+tree                   show the processes behind the active routine and the shared ones
+where                  show what runs next
+quit                   stop debugging";
+
+pub fn debug(
+    graph: crate::graph::Graph,
+    names: &std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    record: std::option::Option<usize>,
+    options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let gammas = crate::check::get_gammas(&graph)?;
+    let line = if let Some(line) = prompt("input: ")? {
+        line
+    } else {
+        return Ok(());
+    };
+    let input = line.trim().parse().map_err(std::boxed::Box::new)?;
+    let mut debugger = Debugger {
+        graph: &graph,
+        names,
+        gammas,
+        session: crate::interpret::Session::create(&graph, input, options)?,
+        breakpoints: std::vec::Vec::new(),
+        is_ended: false,
+        sources: std::collections::HashMap::new(),
+    };
+    if let Some(interval) = record {
+        debugger.session.record(interval);
+    }
+    debugger.show_where();
+    while let Some(line) = prompt("(debug) ")? {
+        let words: std::vec::Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        match debugger.command(&words) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(error) => println!("{}", error),
+        }
+    }
+    Ok(())
+}
+
+fn prompt(
+    text: &str,
+) -> std::result::Result<
+    std::option::Option<std::string::String>,
+    std::boxed::Box<dyn std::error::Error>,
+> {
+    print!("{}", text);
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut line = std::string::String::new();
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        println!();
+        return Ok(None);
+    }
+    Ok(Some(line))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Breakpoint {
+    Routine(std::string::String),
+    Label(std::string::String),
+    Line {
+        file: std::option::Option<std::string::String>,
+        line: u32,
+    },
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Routine(name) => {
+                write!(f, "routine {}", name)?;
+            }
+            Breakpoint::Label(name) => {
+                write!(f, "label {}", name)?;
+            }
+            Breakpoint::Line {
+                file: Some(file),
+                line,
+            } => {
+                write!(f, "line {}:{}", file, line)?;
+            }
+            Breakpoint::Line { file: None, line } => {
+                write!(f, "line {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Until {
+    Steps(usize),
+    Breakpoint,
+    Channel(std::string::String),
+}
+
+struct Debugger<'a> {
+    graph: &'a crate::graph::Graph,
+    names: &'a std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    gammas: std::vec::Vec<std::option::Option<crate::check::Gamma>>,
+    session: crate::interpret::Session<'a>,
+    breakpoints: std::vec::Vec<std::option::Option<Breakpoint>>,
+    is_ended: bool,
+    sources: std::collections::HashMap<std::string::String, std::vec::Vec<std::string::String>>,
+}
+
+impl<'a> Debugger<'a> {
+    fn command(&mut self, words: &[&str]) -> std::result::Result<bool, Error> {
+        match words {
+            ["break" | "b", kind, argument] => {
+                let breakpoint = self.read_breakpoint(kind, argument)?;
+                println!("breakpoint {}: {}", self.breakpoints.len() + 1, breakpoint);
+                self.breakpoints.push(Some(breakpoint));
+            }
+            ["delete" | "d", number] => {
+                let number = read_number(number)?;
+                match self.breakpoints.get_mut(number.wrapping_sub(1)) {
+                    Some(breakpoint @ Some(_)) => *breakpoint = None,
+                    _ => return Err(Error::UnknownBreakpoint(number)),
+                }
+            }
+            ["breakpoints"] => {
+                for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+                    if let Some(breakpoint) = breakpoint {
+                        println!("breakpoint {}: {}", index + 1, breakpoint);
+                    }
+                }
+            }
+            ["step" | "s"] => self.resume(Until::Steps(1))?,
+            ["step" | "s", count] => self.resume(Until::Steps(read_number(count)?))?,
+            ["continue" | "c"] => self.resume(Until::Breakpoint)?,
+            ["until" | "u", channel] => self.resume(Until::Channel(channel.to_string()))?,
+            ["reverse-step" | "rs"] => self.reverse_step(1)?,
+            ["reverse-step" | "rs", count] => self.reverse_step(read_number(count)?)?,
+            ["reverse-continue" | "rc"] => self.reverse_continue()?,
+            ["goto" | "g", step] => {
+                self.seek(read_number(step)?)?;
+                self.show_where();
+            }
+            ["children"] => {
+                let node = self.session.get_node();
+                for child in self.session.get_children() {
+                    self.show_child(node, &child, 1);
+                }
+            }
+            ["tree"] => {
+                self.show_where();
+                let node = self.session.get_node();
+                for child in self.session.get_children() {
+                    self.show_tree(node, &child, 1);
+                }
+                for (index, peer) in self.session.get_shareds() {
+                    println!("shared {}: {}", index, self.describe_peer(&peer, 0));
+                    if let crate::interpret::Peer::Process { node, children, .. } = &peer {
+                        for child in children {
+                            self.show_tree(*node, child, 1);
+                        }
+                    }
+                }
+            }
+            ["where" | "w"] => self.show_where(),
+            ["help" | "h"] => println!("{}", HELP),
+            ["quit" | "q"] => return Ok(false),
+            [
+                "break" | "b" | "delete" | "d" | "breakpoints" | "step" | "s" | "continue" | "c"
+                | "until" | "u" | "reverse-step" | "rs" | "reverse-continue" | "rc" | "goto" | "g"
+                | "children" | "tree" | "where" | "w" | "help" | "h" | "quit" | "q",
+                ..,
+            ] => return Err(Error::WrongArgumentCount),
+            [command, ..] => return Err(Error::UnknownCommand(command.to_string())),
+            [] => {}
+        }
+        Ok(true)
+    }
+    fn read_breakpoint(
+        &self,
+        kind: &str,
+        argument: &str,
+    ) -> std::result::Result<Breakpoint, Error> {
+        match kind {
+            "routine" => {
+                if !self.graph.routinees.contains_key(argument) {
+                    return Err(Error::UnknownRoutine(argument.to_string()));
+                }
+                Ok(Breakpoint::Routine(argument.to_string()))
+            }
+            "label" => {
+                if !self
+                    .graph
+                    .node_labels
+                    .values()
+                    .any(|label| label == argument)
+                {
+                    return Err(Error::UnknownLabel(argument.to_string()));
+                }
+                Ok(Breakpoint::Label(argument.to_string()))
+            }
+            "line" => {
+                let (file, line) = match argument.rsplit_once(':') {
+                    Some((file, line)) => (Some(file.to_string()), line),
+                    None => (None, argument),
+                };
+                let line = line
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument(argument.to_string()))?;
+                Ok(Breakpoint::Line { file, line })
+            }
+            _ => Err(Error::InvalidArgument(kind.to_string())),
+        }
+    }
+    fn find_breakpoint(&self) -> std::option::Option<(usize, &Breakpoint)> {
+        let node = self.session.get_node();
+        let location = crate::graph::get_location(self.graph, node);
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .find_map(|(index, breakpoint)| {
+                let breakpoint = breakpoint.as_ref()?;
+                let is_hit = match breakpoint {
+                    Breakpoint::Routine(name) => self
+                        .graph
+                        .routinees
+                        .get(name)
+                        .is_some_and(|routine| routine.start == node),
+                    Breakpoint::Label(name) => self.graph.node_labels.get(&node) == Some(name),
+                    Breakpoint::Line { file, line } => location.is_some_and(|location| {
+                        location.line == *line
+                            && file.as_ref().is_none_or(|file| {
+                                location
+                                    .file
+                                    .as_deref()
+                                    .is_some_and(|other| other.ends_with(file.as_str()))
+                            })
+                    }),
+                };
+                is_hit.then_some((index + 1, breakpoint))
+            })
+    }
+    fn resume(&mut self, until: Until) -> std::result::Result<(), Error> {
+        if self.is_ended {
+            return Err(Error::Ended);
+        }
+        let mut steps = 0;
+        loop {
+            let (result, event) = self.session.step();
+            steps += 1;
+            let is_stopped = match (&until, &event) {
+                (Until::Steps(count), _) => steps >= *count,
+                (Until::Channel(channel), Some(event)) => {
+                    event.action.get_channel() == Some(channel.as_str())
+                }
+                _ => false,
+            };
+            if let Some(event) = &event
+                && (matches!(until, Until::Steps(_)) || is_stopped || !matches!(result, Ok(true)))
+            {
+                println!("{}", event);
+            }
+            match result {
+                Ok(true) => {}
+                Ok(false) => {
+                    println!("run ended with output {}", self.session.get_output());
+                    self.is_ended = true;
+                    return Ok(());
+                }
+                Err(error) => {
+                    println!("run failed: {}", error);
+                    self.is_ended = true;
+                    return Ok(());
+                }
+            }
+            if let Some((number, breakpoint)) = self.find_breakpoint() {
+                println!("breakpoint {}: {}", number, breakpoint);
+                self.show_where();
+                return Ok(());
+            }
+            if is_stopped {
+                self.show_where();
+                return Ok(());
+            }
+        }
+    }
+    fn reverse_step(&mut self, count: usize) -> std::result::Result<(), Error> {
+        let steps = self.session.get_statistics().steps;
+        self.seek(steps.saturating_sub(count))?;
+        self.show_where();
+        Ok(())
+    }
+    fn reverse_continue(&mut self) -> std::result::Result<(), Error> {
+        let checkpoints = self.session.get_checkpoints();
+        let mut end = self.session.get_statistics().steps;
+        for start in checkpoints.iter().copied().rev() {
+            if start >= end {
+                continue;
+            }
+            self.session.rewind(start);
+            let mut hit = None;
+            while self.session.get_statistics().steps < end {
+                if let Some((number, _)) = self.find_breakpoint() {
+                    hit = Some((self.session.get_statistics().steps, number));
+                }
+                if !matches!(self.session.step().0, Ok(true)) {
+                    break;
+                }
+            }
+            if let Some((step, number)) = hit {
+                self.seek(step)?;
+                if let Some(Some(breakpoint)) = self.breakpoints.get(number - 1) {
+                    println!("breakpoint {}: {}", number, breakpoint);
+                }
+                self.show_where();
+                return Ok(());
+            }
+            end = start;
+        }
+        match checkpoints.first() {
+            Some(start) => {
+                self.seek(*start)?;
+                println!("reached the start of the recording");
+                self.show_where();
+                Ok(())
+            }
+            None => Err(Error::NotRecording),
+        }
+    }
+    fn seek(&mut self, step: usize) -> std::result::Result<(), Error> {
+        if step < self.session.get_statistics().steps {
+            if !self.session.rewind(step) {
+                return Err(Error::NotRecording);
+            }
+            self.is_ended = false;
+        } else if self.is_ended && step > self.session.get_statistics().steps {
+            return Err(Error::Ended);
+        }
+        while self.session.get_statistics().steps < step {
+            let (result, event) = self.session.step();
+            match result {
+                Ok(true) => continue,
+                Ok(false) => {
+                    if let Some(event) = &event {
+                        println!("{}", event);
+                    }
+                    println!("run ended with output {}", self.session.get_output());
+                }
+                Err(error) => {
+                    if let Some(event) = &event {
+                        println!("{}", event);
+                    }
+                    println!("run failed: {}", error);
+                }
+            }
+            self.is_ended = true;
+            break;
+        }
+        Ok(())
+    }
+    fn show_where(&mut self) {
+        let node = self.session.get_node();
+        print!("next: ");
+        if let Some(routine) = self.session.get_routine() {
+            print!("{} ", routine);
+        }
+        print!("at node {}", node);
+        let location = crate::graph::get_location(self.graph, node).cloned();
+        if let Some(location) = &location {
+            print!(" ({})", location);
+        }
+        println!(" after {}", self.session.get_statistics());
+        if let Some(location) = location
+            && let Some(file) = &location.file
+        {
+            let lines =
+                self.sources.entry(file.to_string()).or_insert_with(
+                    || match std::fs::read_to_string(file.as_ref()) {
+                        Ok(source) => source.lines().map(str::to_string).collect(),
+                        Err(_) => std::vec::Vec::new(),
+                    },
+                );
+            if let Some(line) = (location.line as usize)
+                .checked_sub(1)
+                .and_then(|index| lines.get(index))
+            {
+                println!("{:>5} | {}", location.line, line);
+            }
+        }
+    }
+    fn show_child(&self, node: usize, child: &crate::interpret::Child, depth: usize) {
+        let r#type = match self.gammas[node]
+            .as_ref()
+            .and_then(|gamma| gamma.get(&child.name))
+        {
+            Some(r#type) => {
+                crate::peer::describe_named(&self.graph.typees, self.names, *r#type).to_string()
+            }
+            None => "?".to_string(),
+        };
+        print!(
+            "{:indent$}{}: {}, ",
+            "",
+            child.name,
+            r#type,
+            indent = depth * 2
+        );
+        print!("{}", self.describe_peer(&child.peer, child.messages));
+        println!();
+    }
+    fn show_tree(&self, node: usize, child: &crate::interpret::Child, depth: usize) {
+        self.show_child(node, child, depth);
+        if let crate::interpret::Peer::Process { node, children, .. } = &child.peer {
+            for child in children {
+                self.show_tree(*node, child, depth + 1);
+            }
+        }
+    }
+    fn describe_peer(&self, peer: &crate::interpret::Peer, messages: usize) -> std::string::String {
+        let mut description = match peer {
+            crate::interpret::Peer::Interaction => "the interaction".to_string(),
+            crate::interpret::Peer::InteractionEnd => "the interaction, ended".to_string(),
+            crate::interpret::Peer::Cancelled => "cancelled".to_string(),
+            crate::interpret::Peer::Shared { index } => format!("shared {}", index),
+            crate::interpret::Peer::Process {
+                routine,
+                node,
+                shared,
+                ..
+            } => {
+                let mut description = match routine {
+                    Some(routine) => format!("{} at node {}", routine, node),
+                    None => format!("node {}", node),
+                };
+                if let Some(location) = crate::graph::get_location(self.graph, *node) {
+                    description.push_str(&format!(" ({})", location));
+                }
+                if let Some(shared) = shared {
+                    description.push_str(&format!(", acquired from shared {}", shared));
+                }
+                description
+            }
+        };
+        if messages > 0 {
+            description.push_str(&format!(", {} buffered", messages));
+        }
+        description
+    }
+}
+
+fn read_number(text: &str) -> std::result::Result<usize, Error> {
+    text.parse()
+        .map_err(|_| Error::InvalidArgument(text.to_string()))
+}
@@ -0,0 +1,276 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    RunsFailed(usize),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::RunsFailed(count) => {
+                write!(f, "{} of the runs failed", count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub runs: usize,
+    pub budget: usize,
+    pub depth: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            runs: 1000,
+            budget: 10000,
+            depth: 32,
+        }
+    }
+}
+
+pub fn fuzz(
+    mut graph: crate::graph::Graph,
+    routine: &str,
+    options: Options,
+    interpret: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let formals = if let Some(routine) = graph.routinees.get(routine) {
+        routine.formals.clone()
+    } else {
+        return Err(std::boxed::Box::new(crate::peer::Error::UnknownRoutine(
+            routine.to_string(),
+        )));
+    };
+    let mut starts = std::vec::Vec::with_capacity(formals.len());
+    for formal in formals.iter() {
+        starts.push(crate::peer::synthesize(&mut graph, routine, &formal.name)?);
+    }
+    if starts.is_empty() {
+        return Ok(());
+    }
+    let distances = get_distances(&graph.typees);
+    let base = interpret.seed.unwrap_or(0);
+    let mut hungs = 0;
+    let mut faileds = 0;
+    let mut shrunks = std::collections::HashSet::new();
+    for run in 0..options.runs {
+        let seed = base.wrapping_add(run as u64);
+        let index = (seed % starts.len() as u64) as usize;
+        let mut runner = Runner {
+            graph: &graph,
+            distances: &distances,
+            start: starts[index],
+            options,
+            interpret: crate::interpret::Options {
+                seed: Some(seed),
+                limits: crate::interpret::Limits {
+                    steps: Some(options.budget),
+                    ..interpret.limits
+                },
+                ..interpret.clone()
+            },
+        };
+        let (outcome, choices) = runner.run(&[], Some(seed));
+        let message = match &outcome {
+            Outcome::Passed => continue,
+            Outcome::Hung => {
+                hungs += 1;
+                format!("hangs after {} steps", options.budget)
+            }
+            Outcome::Failed(message) => {
+                faileds += 1;
+                message.clone()
+            }
+        };
+        println!(
+            "seed {} driving {:?}: {}",
+            seed, formals[index].name, message
+        );
+        if shrunks.insert((index, outcome.clone())) {
+            let choices: std::vec::Vec<&str> = runner
+                .shrink(&outcome, choices)
+                .iter()
+                .map(|choice| if *choice { "accept" } else { "deny" })
+                .collect();
+            println!("  shrunk choices: {}", choices.join(" "));
+        }
+    }
+    println!("{} runs; {} hung; {} failed", options.runs, hungs, faileds);
+    if hungs + faileds > 0 {
+        Err(std::boxed::Box::new(Error::RunsFailed(hungs + faileds)))
+    } else {
+        Ok(())
+    }
+}
+
+fn get_distances(typees: &[crate::graph::TypeNode]) -> std::vec::Vec<usize> {
+    let mut distances = vec![usize::MAX; typees.len()];
+    let mut is_changed = true;
+    while is_changed {
+        is_changed = false;
+        for index in 0..typees.len() {
+            let distance = match typees[index] {
+                crate::graph::TypeNode::Variable { .. } => {
+                    distances[crate::peer::resolve(typees, index)]
+                }
+                crate::graph::TypeNode::Lollipop { value, next, .. }
+                | crate::graph::TypeNode::Times { value, next, .. } => distances[value]
+                    .saturating_add(distances[next])
+                    .saturating_add(1),
+                crate::graph::TypeNode::With { accept, deny, .. }
+                | crate::graph::TypeNode::Plus { accept, deny, .. } => {
+                    distances[accept].min(distances[deny]).saturating_add(1)
+                }
+                crate::graph::TypeNode::One { .. } | crate::graph::TypeNode::Bottom { .. } => 0,
+                _ => usize::MAX,
+            };
+            if distance < distances[index] {
+                distances[index] = distance;
+                is_changed = true;
+            }
+        }
+    }
+    distances
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Outcome {
+    Passed,
+    Hung,
+    Failed(std::string::String),
+}
+
+struct Runner<'a> {
+    graph: &'a crate::graph::Graph,
+    distances: &'a [usize],
+    start: usize,
+    options: Options,
+    interpret: crate::interpret::Options,
+}
+
+impl<'a> Runner<'a> {
+    fn run(
+        &mut self,
+        prefix: &[bool],
+        seed: std::option::Option<u64>,
+    ) -> (Outcome, std::vec::Vec<bool>) {
+        let mut fuzzer = Fuzzer {
+            typees: &self.graph.typees,
+            distances: self.distances,
+            prefix,
+            random: seed.map(crate::interpret::mix),
+            choices: std::vec::Vec::new(),
+            depth: self.options.depth,
+        };
+        let outcome = match crate::interpret::play(
+            self.graph,
+            self.start,
+            self.interpret.clone(),
+            &mut fuzzer,
+        ) {
+            Ok(()) => Outcome::Passed,
+            Err(error)
+                if matches!(
+                    error.downcast_ref(),
+                    Some(crate::interpret::Error::LimitExceeded {
+                        limit: crate::interpret::Limit::Steps,
+                        ..
+                    })
+                ) =>
+            {
+                Outcome::Hung
+            }
+            Err(error) => Outcome::Failed(error.to_string()),
+        };
+        (outcome, fuzzer.choices)
+    }
+    fn shrink(
+        &mut self,
+        outcome: &Outcome,
+        mut choices: std::vec::Vec<bool>,
+    ) -> std::vec::Vec<bool> {
+        let mut is_changed = true;
+        while is_changed {
+            is_changed = false;
+            let mut candidates = std::vec::Vec::new();
+            for length in 0..choices.len() {
+                candidates.push(choices[..length].to_vec());
+            }
+            for index in 0..choices.len() {
+                let mut candidate = choices.clone();
+                candidate.remove(index);
+                candidates.push(candidate);
+            }
+            for index in 0..choices.len() {
+                if choices[index] {
+                    let mut candidate = choices.clone();
+                    candidate[index] = false;
+                    candidates.push(candidate);
+                }
+            }
+            for candidate in candidates {
+                let (other, others) = self.run(&candidate, None);
+                if other == *outcome && is_smaller(&others, &choices) {
+                    choices = others;
+                    is_changed = true;
+                    break;
+                }
+            }
+        }
+        choices
+    }
+}
+
+fn is_smaller(left: &[bool], right: &[bool]) -> bool {
+    (left.len(), left.iter().filter(|choice| **choice).count())
+        < (right.len(), right.iter().filter(|choice| **choice).count())
+}
+
+struct Fuzzer<'a> {
+    typees: &'a [crate::graph::TypeNode],
+    distances: &'a [usize],
+    prefix: &'a [bool],
+    random: std::option::Option<u64>,
+    choices: std::vec::Vec<bool>,
+    depth: usize,
+}
+
+impl<'a> crate::interpret::Host for Fuzzer<'a> {
+    fn decide(
+        &mut self,
+        _: &str,
+        r#type: usize,
+    ) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        let (accept, deny) = match self.typees[crate::peer::resolve(self.typees, r#type)] {
+            crate::graph::TypeNode::With { accept, deny, .. } => (accept, deny),
+            _ => return Err(std::boxed::Box::new(crate::interpret::Error::TypeError)),
+        };
+        let index = self.choices.len();
+        let choice = match &mut self.random {
+            _ if index < self.prefix.len() => self.prefix[index],
+            Some(random) if index < self.depth => {
+                *random ^= *random << 13;
+                *random ^= *random >> 7;
+                *random ^= *random << 17;
+                *random >> 63 == 1
+            }
+            _ => self.distances[accept] <= self.distances[deny],
+        };
+        self.choices.push(choice);
+        Ok(choice)
+    }
+    fn report(
+        &mut self,
+        _: &str,
+        _: usize,
+        _: crate::graph::Event,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
@@ -3,6 +3,31 @@ pub struct Graph {
     pub typees: std::vec::Vec<TypeNode>,
     pub nodees: std::vec::Vec<Node>,
     pub routinees: std::collections::HashMap<std::string::String, Routine>,
+    pub forkes: std::vec::Vec<Routine>,
+    pub tryes: std::vec::Vec<Try>,
+    pub node_tryes: std::collections::HashMap<usize, usize>,
+    pub node_locations: std::collections::HashMap<usize, crate::location::Location>,
+    pub node_labels: std::collections::HashMap<usize, std::string::String>,
+    pub testes: std::vec::Vec<Test>,
+    pub literales: std::vec::Vec<Literal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Test {
+    pub name: std::string::String,
+    pub start: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Literal {
+    pub name: std::string::String,
+    pub r#type: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Try {
+    pub handler: usize,
+    pub names: std::vec::Vec<std::string::String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -44,7 +69,34 @@ pub enum TypeNode {
         deny: usize,
         dual: usize,
     },
-    One,
+    Shared {
+        value: usize,
+        dual: usize,
+    },
+    Service {
+        value: usize,
+        dual: usize,
+    },
+    Release {
+        value: usize,
+        dual: usize,
+    },
+    Detach {
+        value: usize,
+        dual: usize,
+    },
+    One {
+        dual: usize,
+    },
+    Bottom {
+        dual: usize,
+    },
+    Zero {
+        dual: usize,
+    },
+    Top {
+        dual: usize,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -89,15 +141,104 @@ pub enum Node {
         name: std::string::String,
         next: usize,
     },
+    Wait {
+        name: std::string::String,
+        next: usize,
+    },
+    Absurd {
+        name: std::string::String,
+    },
     Connect {
         left: std::string::String,
         right: std::string::String,
         next: usize,
     },
+    Acquire {
+        name: std::string::String,
+        next: usize,
+    },
+    Release {
+        name: std::string::String,
+        next: usize,
+    },
+    Share {
+        name: std::string::String,
+        copy: std::string::String,
+        next: usize,
+    },
+    Select {
+        arms: std::vec::Vec<Arm>,
+    },
+    Raise {
+        r#try: usize,
+    },
+    Fail {
+        location: crate::location::Location,
+    },
+    Decide {
+        name: std::string::String,
+        r#type: usize,
+        accepted: usize,
+        denied: usize,
+    },
+    Report {
+        name: std::string::String,
+        r#type: usize,
+        event: Event,
+        next: usize,
+    },
     End,
+    Drop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Event {
+    Start,
+    Accepted,
+    Denied,
+    Sent,
+    Received,
+    End,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Arm {
+    Offer {
+        client: std::string::String,
+        accepted: usize,
+        denied: usize,
+    },
+    Receive {
+        source: std::string::String,
+        variable: std::string::String,
+        next: usize,
+    },
 }
 
-pub type Expression = crate::tree::Expression;
+impl Arm {
+    pub fn get_name(&self) -> &std::string::String {
+        match self {
+            Arm::Offer { client, .. } => client,
+            Arm::Receive { source, .. } => source,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expression {
+    Variable {
+        name: std::string::String,
+    },
+    Call {
+        name: std::string::String,
+        before: std::vec::Vec<Expression>,
+        after: std::vec::Vec<Expression>,
+    },
+    Fork {
+        fork: usize,
+        names: std::vec::Vec<std::string::String>,
+    },
+}
 
 pub fn get_dual(typees: &[crate::graph::TypeNode], node: usize) -> usize {
     match &typees[node] {
@@ -106,6 +247,112 @@ pub fn get_dual(typees: &[crate::graph::TypeNode], node: usize) -> usize {
         crate::graph::TypeNode::Times { dual, .. } => *dual,
         crate::graph::TypeNode::With { dual, .. } => *dual,
         crate::graph::TypeNode::Plus { dual, .. } => *dual,
-        crate::graph::TypeNode::One => node,
+        crate::graph::TypeNode::Shared { dual, .. } => *dual,
+        crate::graph::TypeNode::Service { dual, .. } => *dual,
+        crate::graph::TypeNode::Release { dual, .. } => *dual,
+        crate::graph::TypeNode::Detach { dual, .. } => *dual,
+        crate::graph::TypeNode::One { dual } => *dual,
+        crate::graph::TypeNode::Bottom { dual } => *dual,
+        crate::graph::TypeNode::Zero { dual } => *dual,
+        crate::graph::TypeNode::Top { dual } => *dual,
+    }
+}
+
+pub fn get_principals(node: &crate::graph::Node) -> std::vec::Vec<&std::string::String> {
+    match node {
+        crate::graph::Node::Receive { source, .. } => vec![source],
+        crate::graph::Node::Send { destination, .. } => vec![destination],
+        crate::graph::Node::Offer { client, .. } => vec![client],
+        crate::graph::Node::Accept { server, .. } => vec![server],
+        crate::graph::Node::Deny { server, .. } => vec![server],
+        crate::graph::Node::Close { name, .. } => vec![name],
+        crate::graph::Node::Wait { name, .. } => vec![name],
+        crate::graph::Node::Absurd { name } => vec![name],
+        crate::graph::Node::Acquire { name, .. } => vec![name],
+        crate::graph::Node::Release { name, .. } => vec![name],
+        crate::graph::Node::Select { arms } => arms.iter().map(|arm| arm.get_name()).collect(),
+        _ => vec![],
+    }
+}
+
+pub fn get_nexts(node: &crate::graph::Node) -> std::vec::Vec<usize> {
+    match node {
+        crate::graph::Node::Branch { next }
+        | crate::graph::Node::Assign { next, .. }
+        | crate::graph::Node::Call { next, .. }
+        | crate::graph::Node::Receive { next, .. }
+        | crate::graph::Node::Send { next, .. }
+        | crate::graph::Node::Accept { next, .. }
+        | crate::graph::Node::Deny { next, .. }
+        | crate::graph::Node::Close { next, .. }
+        | crate::graph::Node::Wait { next, .. }
+        | crate::graph::Node::Connect { next, .. }
+        | crate::graph::Node::Acquire { next, .. }
+        | crate::graph::Node::Release { next, .. }
+        | crate::graph::Node::Share { next, .. }
+        | crate::graph::Node::Report { next, .. } => vec![*next],
+        crate::graph::Node::Offer {
+            accepted, denied, ..
+        }
+        | crate::graph::Node::Decide {
+            accepted, denied, ..
+        } => vec![*accepted, *denied],
+        crate::graph::Node::Select { arms } => arms
+            .iter()
+            .flat_map(|arm| match arm {
+                crate::graph::Arm::Offer {
+                    accepted, denied, ..
+                } => vec![*accepted, *denied],
+                crate::graph::Arm::Receive { next, .. } => vec![*next],
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+pub fn get_forks(node: &crate::graph::Node) -> std::vec::Vec<usize> {
+    let mut forks = vec![];
+    let mut expressions: std::vec::Vec<&crate::graph::Expression> = match node {
+        crate::graph::Node::Assign { value, .. } => vec![value],
+        crate::graph::Node::Call { actuals, .. } => actuals.iter().collect(),
+        _ => vec![],
+    };
+    while let Some(expression) = expressions.pop() {
+        match expression {
+            crate::graph::Expression::Variable { .. } => {}
+            crate::graph::Expression::Call { before, after, .. } => {
+                expressions.extend(before.iter().chain(after.iter()));
+            }
+            crate::graph::Expression::Fork { fork, .. } => forks.push(*fork),
+        }
+    }
+    forks
+}
+
+pub fn get_location(
+    graph: &crate::graph::Graph,
+    node: usize,
+) -> std::option::Option<&crate::location::Location> {
+    match &graph.nodees[node] {
+        crate::graph::Node::Fail { location } => Some(location),
+        _ => graph.node_locations.get(&node),
+    }
+}
+
+pub fn get_name(typees: &[crate::graph::TypeNode], node: usize) -> &'static str {
+    match &typees[node] {
+        crate::graph::TypeNode::Variable { .. } => "variable",
+        crate::graph::TypeNode::Lollipop { .. } => "lollipop",
+        crate::graph::TypeNode::Times { .. } => "times",
+        crate::graph::TypeNode::With { .. } => "with",
+        crate::graph::TypeNode::Plus { .. } => "plus",
+        crate::graph::TypeNode::Shared { .. } => "shared",
+        crate::graph::TypeNode::Service { .. } => "service",
+        crate::graph::TypeNode::Release { .. } => "release",
+        crate::graph::TypeNode::Detach { .. } => "detach",
+        crate::graph::TypeNode::One { .. } => "one",
+        crate::graph::TypeNode::Bottom { .. } => "bottom",
+        crate::graph::TypeNode::Zero { .. } => "zero",
+        crate::graph::TypeNode::Top { .. } => "top",
     }
 }
@@ -1,4 +1,4 @@
-#[allow(dead_code)]
+#[allow(dead_code, clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Error {
     NoMain,
@@ -6,6 +6,16 @@ pub enum Error {
     TypeError,
     Overwriting(std::string::String),
     UnboundVariable(std::string::String),
+    Absurd(std::string::String),
+    Deadlock(std::string::String),
+    Failed(crate::location::Location),
+    NoHost,
+    LimitExceeded {
+        limit: Limit,
+        statistics: Statistics,
+    },
+    Cancelled(Statistics),
+    InteractionCancelled,
 }
 
 impl std::fmt::Display for Error {
@@ -26,6 +36,27 @@ impl std::fmt::Display for Error {
             Error::UnboundVariable(name) => {
                 write!(f, "unbound variable {:?}", name)?;
             }
+            Error::Absurd(name) => {
+                write!(f, "reached absurd on {:?}", name)?;
+            }
+            Error::Deadlock(name) => {
+                write!(f, "deadlock acquiring {:?}", name)?;
+            }
+            Error::Failed(location) => {
+                write!(f, "assertion failed at {}", location)?;
+            }
+            Error::NoHost => {
+                write!(f, "no host to decide")?;
+            }
+            Error::LimitExceeded { limit, statistics } => {
+                write!(f, "{} limit exceeded after {}", limit, statistics)?;
+            }
+            Error::Cancelled(statistics) => {
+                write!(f, "cancelled after {}", statistics)?;
+            }
+            Error::InteractionCancelled => {
+                write!(f, "cancelled the interaction")?;
+            }
         }
         Ok(())
     }
@@ -36,36 +67,462 @@ impl std::error::Error for Error {}
 pub fn interpret(
     graph: crate::graph::Graph,
     input: u32,
+    options: Options,
+) -> std::result::Result<u32, std::boxed::Box<dyn std::error::Error>> {
+    let mut interpreter = Interpreter::create(&graph, input, options)?;
+    while interpreter.step()? {}
+    Ok(interpreter.interaction.get_output())
+}
+
+pub fn trace(
+    graph: crate::graph::Graph,
+    input: u32,
+    options: Options,
+    callback: &mut dyn FnMut(&Event),
 ) -> std::result::Result<u32, std::boxed::Box<dyn std::error::Error>> {
-    let mut state = InteractionState::Main(input, 0);
-    let mut interpreter = Interpreter::create(&graph, &mut state)?;
+    let mut interpreter = Interpreter::create(&graph, input, options)?;
+    interpreter.start_tracing();
+    interpreter.callback = Some(callback);
     while interpreter.step()? {}
-    Ok(state.get_output())
+    Ok(interpreter.interaction.get_output())
+}
+
+pub struct Session<'a> {
+    interpreter: Interpreter<'a>,
+    recording: std::option::Option<Recording>,
+}
+
+impl<'a> Session<'a> {
+    pub fn create(
+        graph: &'a crate::graph::Graph,
+        input: u32,
+        options: Options,
+    ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error>> {
+        let mut interpreter = Interpreter::create(graph, input, options)?;
+        interpreter.start_tracing();
+        Ok(Session {
+            interpreter,
+            recording: None,
+        })
+    }
+    pub fn step(
+        &mut self,
+    ) -> (
+        std::result::Result<bool, std::boxed::Box<dyn std::error::Error>>,
+        std::option::Option<Event>,
+    ) {
+        let (result, event) = self.interpreter.trace_step();
+        if let (Some(recording), Ok(true)) = (&mut self.recording, &result) {
+            let steps = self.interpreter.statistics.steps;
+            let last = recording
+                .checkpoints
+                .last()
+                .map_or(0, |last| last.statistics.steps);
+            if steps > last && (steps - recording.start).is_multiple_of(recording.interval) {
+                recording.checkpoints.push(self.interpreter.save());
+                if recording.checkpoints.len() > MAX_CHECKPOINTS {
+                    let checkpoints = std::mem::take(&mut recording.checkpoints);
+                    recording.checkpoints = checkpoints.into_iter().step_by(2).collect();
+                    recording.interval *= 2;
+                }
+            }
+        }
+        (result, event)
+    }
+    pub fn record(&mut self, interval: usize) {
+        self.recording = Some(Recording {
+            checkpoints: vec![self.interpreter.save()],
+            start: self.interpreter.statistics.steps,
+            interval: interval.max(1),
+        });
+    }
+    pub fn rewind(&mut self, step: usize) -> bool {
+        let checkpoint = self.recording.as_ref().and_then(|recording| {
+            recording
+                .checkpoints
+                .iter()
+                .rev()
+                .find(|checkpoint| checkpoint.statistics.steps <= step)
+        });
+        if let Some(checkpoint) = checkpoint {
+            self.interpreter.restore(checkpoint);
+            true
+        } else {
+            false
+        }
+    }
+    pub fn get_checkpoints(&self) -> std::vec::Vec<usize> {
+        match &self.recording {
+            Some(recording) => recording
+                .checkpoints
+                .iter()
+                .map(|checkpoint| checkpoint.statistics.steps)
+                .collect(),
+            None => std::vec::Vec::new(),
+        }
+    }
+    pub fn get_output(&self) -> u32 {
+        self.interpreter.interaction.get_output()
+    }
+    pub fn get_statistics(&self) -> Statistics {
+        self.interpreter.statistics
+    }
+    pub fn get_node(&self) -> usize {
+        self.interpreter.active.node
+    }
+    pub fn get_routine(&self) -> std::option::Option<std::string::String> {
+        self.interpreter.get_owner(self.interpreter.active.node)
+    }
+    pub fn get_children(&self) -> std::vec::Vec<Child> {
+        self.interpreter
+            .get_children(&self.interpreter.active.children)
+    }
+    pub fn get_shareds(&self) -> std::vec::Vec<(usize, Peer)> {
+        let mut shareds = std::vec::Vec::new();
+        for (index, shared) in self.interpreter.shareds.iter().enumerate() {
+            if let Some(routine) = &shared.routine {
+                shareds.push((index, self.interpreter.get_view(routine).0));
+            }
+        }
+        shareds
+    }
+}
+
+pub fn test(
+    graph: &crate::graph::Graph,
+    test: &crate::graph::Test,
+    options: Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let active = ActiveRoutine {
+        node: test.start,
+        children: std::collections::HashMap::new(),
+        to_interaction: std::string::String::new(),
+    };
+    let mut interpreter = Interpreter::with_active(graph, active, options);
+    while interpreter.step()? {}
+    Ok(())
+}
+
+pub fn play<'a>(
+    graph: &'a crate::graph::Graph,
+    start: usize,
+    options: Options,
+    host: &'a mut dyn Host,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let active = ActiveRoutine {
+        node: start,
+        children: std::collections::HashMap::new(),
+        to_interaction: std::string::String::new(),
+    };
+    let mut interpreter = Interpreter::with_active(graph, active, options);
+    interpreter.host = Some(host);
+    while interpreter.step()? {}
+    Ok(())
+}
+
+pub trait Host {
+    fn decide(
+        &mut self,
+        name: &str,
+        r#type: usize,
+    ) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>>;
+    fn report(
+        &mut self,
+        name: &str,
+        r#type: usize,
+        event: crate::graph::Event,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Synchronous,
+    Asynchronous {
+        capacity: std::option::Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub seed: std::option::Option<u64>,
+    pub mode: Mode,
+    pub limits: Limits,
+    pub cancel: std::option::Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    pub steps: std::option::Option<usize>,
+    pub processes: std::option::Option<usize>,
+    pub channels: std::option::Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps,
+    Processes,
+    Channels,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Steps => {
+                write!(f, "step")?;
+            }
+            Limit::Processes => {
+                write!(f, "process")?;
+            }
+            Limit::Channels => {
+                write!(f, "channel")?;
+            }
+        }
+        Ok(())
+    }
 }
 
-struct ActiveRoutine<'a> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    pub steps: usize,
+    pub processes: usize,
+    pub channels: usize,
+}
+
+impl std::fmt::Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} steps with {} processes and {} channels",
+            self.steps, self.processes, self.channels
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub step: usize,
+    pub routine: std::option::Option<std::string::String>,
+    pub node: usize,
+    pub location: std::option::Option<crate::location::Location>,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Action {
+    Entered {
+        routine: std::string::String,
+    },
+    #[default]
+    Executed,
+    Sent {
+        channel: std::string::String,
+        peer: std::option::Option<std::string::String>,
+    },
+    Received {
+        channel: std::string::String,
+        peer: std::option::Option<std::string::String>,
+    },
+    Chose {
+        channel: std::string::String,
+        peer: std::option::Option<std::string::String>,
+        accept: bool,
+    },
+    Offered {
+        channel: std::string::String,
+        peer: std::option::Option<std::string::String>,
+        accept: bool,
+    },
+    Closed {
+        channel: std::string::String,
+        peer: std::option::Option<std::string::String>,
+    },
+    Waited {
+        channel: std::string::String,
+        peer: std::option::Option<std::string::String>,
+    },
+    Flipped {
+        channel: std::string::String,
+        routine: std::option::Option<std::string::String>,
+    },
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "step {}: ", self.step)?;
+        if let Some(routine) = &self.routine {
+            write!(f, "{} ", routine)?;
+        }
+        write!(f, "at node {}", self.node)?;
+        if let Some(location) = &self.location {
+            write!(f, " ({})", location)?;
+        }
+        write!(f, " {}", self.action)?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let peer = match self {
+            Action::Entered { routine } => {
+                write!(f, "entered {}", routine)?;
+                return Ok(());
+            }
+            Action::Executed => {
+                write!(f, "executed")?;
+                return Ok(());
+            }
+            Action::Flipped { channel, routine } => {
+                write!(f, "switched over {:?}", channel)?;
+                if let Some(routine) = routine {
+                    write!(f, " to {}", routine)?;
+                }
+                return Ok(());
+            }
+            Action::Sent { channel, peer } => {
+                write!(f, "sent on {:?}", channel)?;
+                peer
+            }
+            Action::Received { channel, peer } => {
+                write!(f, "received on {:?}", channel)?;
+                peer
+            }
+            Action::Chose {
+                channel,
+                peer,
+                accept,
+            } => {
+                let choice = if *accept { "accepted" } else { "denied" };
+                write!(f, "{} on {:?}", choice, channel)?;
+                peer
+            }
+            Action::Offered {
+                channel,
+                peer,
+                accept,
+            } => {
+                let choice = if *accept { "accept" } else { "deny" };
+                write!(f, "offered on {:?} and got {}", channel, choice)?;
+                peer
+            }
+            Action::Closed { channel, peer } => {
+                write!(f, "closed {:?}", channel)?;
+                peer
+            }
+            Action::Waited { channel, peer } => {
+                write!(f, "waited on {:?}", channel)?;
+                peer
+            }
+        };
+        if let Some(peer) = peer {
+            write!(f, " with {}", peer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Action {
+    pub fn get_channel(&self) -> std::option::Option<&str> {
+        match self {
+            Action::Entered { .. } | Action::Executed | Action::Flipped { .. } => None,
+            Action::Sent { channel, .. }
+            | Action::Received { channel, .. }
+            | Action::Chose { channel, .. }
+            | Action::Offered { channel, .. }
+            | Action::Closed { channel, .. }
+            | Action::Waited { channel, .. } => Some(channel),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Child {
+    pub name: std::string::String,
+    pub peer: Peer,
+    pub messages: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Peer {
+    Interaction,
+    InteractionEnd,
+    Cancelled,
+    Shared {
+        index: usize,
+    },
+    Process {
+        routine: std::option::Option<std::string::String>,
+        node: usize,
+        parent: std::string::String,
+        shared: std::option::Option<usize>,
+        children: std::vec::Vec<Child>,
+    },
+}
+
+const MAX_CHECKPOINTS: usize = 64;
+
+struct Recording {
+    checkpoints: std::vec::Vec<Snapshot>,
+    start: usize,
+    interval: usize,
+}
+
+struct Snapshot {
+    active: ActiveRoutine,
+    suspendeds: std::vec::Vec<ActiveRoutine>,
+    interaction: InteractionState,
+    shareds: std::vec::Vec<Shared>,
+    random: std::option::Option<u64>,
+    statistics: Statistics,
+}
+
+#[derive(Clone)]
+struct ActiveRoutine {
     node: usize,
-    children: std::collections::HashMap<std::string::String, InactiveRoutine<'a>>,
+    children: std::collections::HashMap<std::string::String, InactiveRoutine>,
     to_interaction: std::string::String,
 }
 
-enum InactiveRoutine<'a> {
-    Interaction {
-        state: &'a mut InteractionState,
-    },
+#[derive(Clone)]
+enum InactiveRoutine {
+    Interaction,
     InteractionEnd,
+    Cancelled,
+    Shared {
+        index: usize,
+        ticket: std::option::Option<usize>,
+    },
+    Acquired {
+        index: usize,
+        routine: std::boxed::Box<InactiveRoutine>,
+    },
+    Buffered {
+        outgoing: std::collections::VecDeque<Message>,
+        incoming: std::collections::VecDeque<Message>,
+        routine: std::boxed::Box<InactiveRoutine>,
+    },
     Graph {
         node: usize,
-        children: std::collections::HashMap<std::string::String, InactiveRoutine<'a>>,
+        children: std::collections::HashMap<std::string::String, InactiveRoutine>,
         parent: std::string::String,
         to_interaction: std::string::String,
     },
 }
 
+#[derive(Clone)]
+enum Message {
+    Value(InactiveRoutine),
+    Choice(bool),
+}
+
+#[derive(Clone)]
 enum InteractionState {
     Main(u32, u32),
     Input(u32, u32),
     Output(u32, u32),
+    Cancelled(u32),
 }
 
 impl InteractionState {
@@ -74,19 +531,41 @@ impl InteractionState {
             InteractionState::Main(_, output) => *output,
             InteractionState::Input(_, output) => *output,
             InteractionState::Output(_, output) => *output,
+            InteractionState::Cancelled(output) => *output,
         }
     }
 }
 
+#[derive(Clone)]
+struct Shared {
+    routine: std::option::Option<InactiveRoutine>,
+    queue: std::collections::VecDeque<usize>,
+    next_ticket: usize,
+}
+
 struct Interpreter<'a> {
     graph: &'a crate::graph::Graph,
-    active: ActiveRoutine<'a>,
+    active: ActiveRoutine,
+    suspendeds: std::vec::Vec<ActiveRoutine>,
+    interaction: InteractionState,
+    shareds: std::vec::Vec<Shared>,
+    random: std::option::Option<u64>,
+    mode: Mode,
+    host: std::option::Option<&'a mut dyn Host>,
+    limits: Limits,
+    cancel: std::option::Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    statistics: Statistics,
+    callback: std::option::Option<&'a mut dyn FnMut(&Event)>,
+    is_tracing: bool,
+    owners: std::vec::Vec<std::option::Option<std::string::String>>,
+    action: Action,
 }
 
 impl<'a> Interpreter<'a> {
     fn create(
         graph: &'a crate::graph::Graph,
-        state: &'a mut InteractionState,
+        input: u32,
+        options: Options,
     ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error>> {
         let routine = if let Some(routine) = graph.routinees.get("main") {
             routine
@@ -99,17 +578,555 @@ impl<'a> Interpreter<'a> {
         }
         let formal = &routine.formals[0];
         let mut children = std::collections::HashMap::new();
-        children.insert(formal.name.clone(), InactiveRoutine::Interaction { state });
-        Ok(Interpreter {
+        children.insert(formal.name.clone(), InactiveRoutine::Interaction);
+        let active = ActiveRoutine {
+            node,
+            children,
+            to_interaction: formal.name.clone(),
+        };
+        let mut interpreter = Interpreter::with_active(graph, active, options);
+        interpreter.interaction = InteractionState::Main(input, 0);
+        Ok(interpreter)
+    }
+    fn save(&self) -> Snapshot {
+        Snapshot {
+            active: self.active.clone(),
+            suspendeds: self.suspendeds.clone(),
+            interaction: self.interaction.clone(),
+            shareds: self.shareds.clone(),
+            random: self.random,
+            statistics: self.statistics,
+        }
+    }
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.active = snapshot.active.clone();
+        self.suspendeds = snapshot.suspendeds.clone();
+        self.interaction = snapshot.interaction.clone();
+        self.shareds = snapshot.shareds.clone();
+        self.random = snapshot.random;
+        self.statistics = snapshot.statistics;
+        self.action = Action::Executed;
+    }
+    fn with_active(
+        graph: &'a crate::graph::Graph,
+        active: ActiveRoutine,
+        options: Options,
+    ) -> Self {
+        Interpreter {
             graph,
-            active: ActiveRoutine {
-                node,
-                children,
-                to_interaction: formal.name.clone(),
+            active,
+            suspendeds: std::vec::Vec::new(),
+            interaction: InteractionState::Main(0, 0),
+            shareds: std::vec::Vec::new(),
+            random: options.seed.map(mix),
+            mode: options.mode,
+            host: None,
+            limits: options.limits,
+            cancel: options.cancel,
+            statistics: Statistics {
+                processes: 1,
+                ..Statistics::default()
             },
-        })
+            callback: None,
+            is_tracing: false,
+            owners: std::vec::Vec::new(),
+            action: Action::Executed,
+        }
+    }
+    fn bind(
+        &mut self,
+        name: &str,
+        value: InactiveRoutine,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        if !value.is_parent_to_interaction() {
+            self.active.to_interaction = name.to_string();
+        }
+        if self
+            .active
+            .children
+            .insert(name.to_string(), value)
+            .is_some()
+        {
+            return Err(std::boxed::Box::new(Error::Overwriting(name.to_string())));
+        }
+        Ok(())
+    }
+    fn has_incoming(&self, name: &str) -> bool {
+        matches!(self.active.children.get(name), Some(InactiveRoutine::Buffered { incoming, .. }) if !incoming.is_empty())
+    }
+    fn pop_incoming(&mut self, name: &str) -> std::option::Option<Message> {
+        if let Some(InactiveRoutine::Buffered { incoming, .. }) = self.active.children.get_mut(name)
+        {
+            incoming.pop_front()
+        } else {
+            None
+        }
+    }
+    fn can_enqueue(&self, name: &str) -> bool {
+        let capacity = match self.mode {
+            Mode::Synchronous => return false,
+            Mode::Asynchronous { capacity } => capacity,
+        };
+        match self.active.children.get(name) {
+            Some(child) if !child.is_parent_to_interaction() => false,
+            Some(InactiveRoutine::Buffered { outgoing, .. }) => {
+                capacity.is_none_or(|capacity| outgoing.len() < capacity)
+            }
+            Some(InactiveRoutine::Graph { .. }) | Some(InactiveRoutine::Acquired { .. }) => {
+                capacity.is_none_or(|capacity| capacity > 0)
+            }
+            _ => false,
+        }
+    }
+    fn enqueue(&mut self, name: &str, message: Message) {
+        let child = self.active.children.get_mut(name).unwrap();
+        if !matches!(child, InactiveRoutine::Buffered { .. }) {
+            let routine = std::mem::replace(child, InactiveRoutine::Cancelled);
+            *child = InactiveRoutine::Buffered {
+                outgoing: std::collections::VecDeque::new(),
+                incoming: std::collections::VecDeque::new(),
+                routine: std::boxed::Box::new(routine),
+            };
+        }
+        if let InactiveRoutine::Buffered { outgoing, .. } = child {
+            outgoing.push_back(message);
+        }
+    }
+    fn pick(&mut self, count: usize) -> usize {
+        if let Some(random) = &mut self.random {
+            *random ^= *random << 13;
+            *random ^= *random >> 7;
+            *random ^= *random << 17;
+            (*random % count as u64) as usize
+        } else {
+            0
+        }
+    }
+    fn add_shared(&mut self, routine: InactiveRoutine) -> usize {
+        self.shareds.push(Shared {
+            routine: Some(routine),
+            queue: std::collections::VecDeque::new(),
+            next_ticket: 0,
+        });
+        self.shareds.len() - 1
+    }
+    fn acquire(
+        &mut self,
+        name: &str,
+        index: usize,
+        ticket: std::option::Option<usize>,
+    ) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        let shared = &mut self.shareds[index];
+        let is_turn = match ticket {
+            Some(ticket) => shared.queue.front() == Some(&ticket),
+            None => shared.queue.is_empty(),
+        };
+        if is_turn && let Some(routine) = shared.routine.take() {
+            if ticket.is_some() {
+                shared.queue.pop_front();
+            }
+            self.active.children.insert(
+                name.to_string(),
+                InactiveRoutine::Acquired {
+                    index,
+                    routine: std::boxed::Box::new(routine),
+                },
+            );
+            return Ok(true);
+        }
+        if ticket.is_none() {
+            let ticket = shared.next_ticket;
+            shared.next_ticket += 1;
+            shared.queue.push_back(ticket);
+            self.active.children.insert(
+                name.to_string(),
+                InactiveRoutine::Shared {
+                    index,
+                    ticket: Some(ticket),
+                },
+            );
+        }
+        let shared = &self.shareds[index];
+        let path = if shared.routine.is_none() {
+            find(
+                &self.active.children,
+                &|child| matches!(child, InactiveRoutine::Acquired { index: other, .. } if *other == index),
+            )
+        } else {
+            let front = shared.queue.front().copied();
+            find(
+                &self.active.children,
+                &|child| matches!(child, InactiveRoutine::Shared { index: other, ticket } if *other == index && *ticket == front),
+            )
+        };
+        match path {
+            Some(path) if !path.is_empty() => {
+                for name in path.iter() {
+                    self.flip(name)?;
+                }
+                Ok(true)
+            }
+            _ => Err(std::boxed::Box::new(Error::Deadlock(name.to_string()))),
+        }
+    }
+    fn cancel(&mut self) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        if let Some(InactiveRoutine::Buffered { outgoing, .. }) =
+            self.active.children.get(&self.active.to_interaction)
+            && !outgoing.is_empty()
+        {
+            let to_interaction = self.active.to_interaction.clone();
+            self.flip(&to_interaction)?;
+            return Ok(true);
+        }
+        if self.active.to_interaction.is_empty() && !self.suspendeds.is_empty() {
+            return self.finish();
+        }
+        match self
+            .active
+            .children
+            .remove(&self.active.to_interaction)
+            .map(InactiveRoutine::strip)
+        {
+            Some(InactiveRoutine::Graph {
+                node,
+                mut children,
+                parent,
+                to_interaction,
+            }) => {
+                children.insert(parent, InactiveRoutine::Cancelled);
+                self.end(ActiveRoutine {
+                    node,
+                    children,
+                    to_interaction,
+                });
+                Ok(true)
+            }
+            Some(InactiveRoutine::Interaction) | Some(InactiveRoutine::InteractionEnd) => {
+                Err(std::boxed::Box::new(Error::InteractionCancelled))
+            }
+            Some(_) => Ok(false),
+            None => Err(std::boxed::Box::new(Error::UnboundVariable(
+                self.active.to_interaction.clone(),
+            ))),
+        }
+    }
+    fn detach(&mut self) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        let to_interaction = std::mem::take(&mut self.active.to_interaction);
+        match self
+            .active
+            .children
+            .remove(&to_interaction)
+            .map(InactiveRoutine::strip)
+        {
+            Some(InactiveRoutine::Graph {
+                node,
+                mut children,
+                parent,
+                to_interaction,
+            }) => {
+                children.insert(parent, InactiveRoutine::Cancelled);
+                self.suspendeds.push(ActiveRoutine {
+                    node,
+                    children,
+                    to_interaction,
+                });
+            }
+            Some(InactiveRoutine::Interaction) | Some(InactiveRoutine::InteractionEnd) => {
+                self.interaction = InteractionState::Cancelled(self.interaction.get_output());
+            }
+            Some(_) => {}
+            None => {
+                return Err(std::boxed::Box::new(Error::UnboundVariable(to_interaction)));
+            }
+        }
+        Ok(true)
+    }
+    fn finish(&mut self) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        if let Some(active) = self.suspendeds.pop() {
+            self.end(active);
+            return Ok(true);
+        }
+        if let InteractionState::Cancelled(_) = self.interaction {
+            return Err(std::boxed::Box::new(Error::InteractionCancelled));
+        }
+        Ok(false)
+    }
+    fn end(&mut self, active: ActiveRoutine) {
+        self.statistics.processes = self.statistics.processes.saturating_sub(1);
+        for child in std::mem::replace(&mut self.active, active)
+            .children
+            .into_values()
+        {
+            self.discard(child);
+        }
+    }
+    fn raise(
+        &mut self,
+        r#try: usize,
+    ) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        let r#try = &self.graph.tryes[r#try];
+        let to_interaction = self.active.to_interaction.clone();
+        let is_cancelling = r#try.names.contains(&to_interaction)
+            && self.active.children.contains_key(&to_interaction);
+        if is_cancelling
+            && let Some(InactiveRoutine::Buffered { outgoing, .. }) =
+                self.active.children.get(&to_interaction)
+            && !outgoing.is_empty()
+        {
+            self.flip(&to_interaction)?;
+            return Ok(true);
+        }
+        self.active.node = r#try.handler;
+        for name in r#try.names.iter() {
+            if *name != to_interaction
+                && let Some(child) = self.active.children.remove(name)
+            {
+                self.discard(child);
+            }
+        }
+        if is_cancelling {
+            return self.detach();
+        }
+        Ok(true)
+    }
+    fn discard(&mut self, routine: InactiveRoutine) {
+        match routine {
+            InactiveRoutine::Shared {
+                index,
+                ticket: Some(ticket),
+            } => self.shareds[index].queue.retain(|other| *other != ticket),
+            InactiveRoutine::Acquired { routine, .. } => self.discard(*routine),
+            InactiveRoutine::Buffered {
+                outgoing,
+                incoming,
+                routine,
+            } => {
+                for message in outgoing.into_iter().chain(incoming) {
+                    if let Message::Value(value) = message {
+                        self.discard(value);
+                    }
+                }
+                self.discard(*routine);
+            }
+            InactiveRoutine::Graph { children, .. } => {
+                self.statistics.processes = self.statistics.processes.saturating_sub(1);
+                for child in children.into_values() {
+                    self.discard(child);
+                }
+            }
+            _ => {}
+        }
+    }
+    fn check_limits(&mut self) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(std::sync::atomic::Ordering::Relaxed))
+        {
+            return Err(std::boxed::Box::new(Error::Cancelled(self.statistics)));
+        }
+        if self
+            .limits
+            .steps
+            .is_some_and(|limit| self.statistics.steps >= limit)
+        {
+            return Err(std::boxed::Box::new(Error::LimitExceeded {
+                limit: Limit::Steps,
+                statistics: self.statistics,
+            }));
+        }
+        self.statistics.steps += 1;
+        Ok(())
     }
     fn step(&mut self) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        if !self.is_tracing {
+            self.check_limits()?;
+            return self.execute();
+        }
+        let (result, event) = self.trace_step();
+        if let (Some(callback), Some(event)) = (&mut self.callback, &event) {
+            callback(event);
+        }
+        result
+    }
+    fn trace_step(
+        &mut self,
+    ) -> (
+        std::result::Result<bool, std::boxed::Box<dyn std::error::Error>>,
+        std::option::Option<Event>,
+    ) {
+        if let Err(error) = self.check_limits() {
+            return (Err(error), None);
+        }
+        let node = self.active.node;
+        self.action = Action::Executed;
+        let result = self.execute();
+        let event = Event {
+            step: self.statistics.steps,
+            routine: self.get_owner(node),
+            node,
+            location: crate::graph::get_location(self.graph, node).cloned(),
+            action: std::mem::take(&mut self.action),
+        };
+        (result, Some(event))
+    }
+    fn start_tracing(&mut self) {
+        self.owners = get_owners(self.graph);
+        self.is_tracing = true;
+    }
+    fn get_children(
+        &self,
+        children: &std::collections::HashMap<std::string::String, InactiveRoutine>,
+    ) -> std::vec::Vec<Child> {
+        let mut views: std::vec::Vec<Child> = children
+            .iter()
+            .map(|(name, child)| {
+                let (peer, messages) = self.get_view(child);
+                Child {
+                    name: name.clone(),
+                    peer,
+                    messages,
+                }
+            })
+            .collect();
+        views.sort_by(|left, right| left.name.cmp(&right.name));
+        views
+    }
+    fn get_view(&self, routine: &InactiveRoutine) -> (Peer, usize) {
+        match routine {
+            InactiveRoutine::Interaction => (Peer::Interaction, 0),
+            InactiveRoutine::InteractionEnd => (Peer::InteractionEnd, 0),
+            InactiveRoutine::Cancelled => (Peer::Cancelled, 0),
+            InactiveRoutine::Shared { index, .. } => (Peer::Shared { index: *index }, 0),
+            InactiveRoutine::Acquired { index, routine } => {
+                let (mut peer, messages) = self.get_view(routine);
+                if let Peer::Process { shared, .. } = &mut peer {
+                    *shared = Some(*index);
+                }
+                (peer, messages)
+            }
+            InactiveRoutine::Buffered {
+                outgoing,
+                incoming,
+                routine,
+            } => {
+                let (peer, messages) = self.get_view(routine);
+                (peer, messages + outgoing.len() + incoming.len())
+            }
+            InactiveRoutine::Graph {
+                node,
+                children,
+                parent,
+                ..
+            } => (
+                Peer::Process {
+                    routine: self.get_owner(*node),
+                    node: *node,
+                    parent: parent.clone(),
+                    shared: None,
+                    children: self.get_children(children),
+                },
+                0,
+            ),
+        }
+    }
+    fn get_owner(&self, node: usize) -> std::option::Option<std::string::String> {
+        self.owners.get(node).cloned().flatten()
+    }
+    fn get_peer(&self, name: &str) -> std::option::Option<std::string::String> {
+        let mut child = self.active.children.get(name)?;
+        loop {
+            match child {
+                InactiveRoutine::Acquired { routine, .. }
+                | InactiveRoutine::Buffered { routine, .. } => child = routine,
+                InactiveRoutine::Graph { node, .. } => return self.get_owner(*node),
+                _ => return None,
+            }
+        }
+    }
+    fn record(&mut self, action: impl FnOnce(&Self) -> Action) {
+        if self.is_tracing {
+            self.action = action(self);
+        }
+    }
+    fn record_sent(&mut self, channel: &str) {
+        self.record(|interpreter| Action::Sent {
+            channel: channel.to_string(),
+            peer: interpreter.get_peer(channel),
+        });
+    }
+    fn record_received(&mut self, channel: &str) {
+        self.record(|interpreter| Action::Received {
+            channel: channel.to_string(),
+            peer: interpreter.get_peer(channel),
+        });
+    }
+    fn record_chose(&mut self, channel: &str, accept: bool) {
+        self.record(|interpreter| Action::Chose {
+            channel: channel.to_string(),
+            peer: interpreter.get_peer(channel),
+            accept,
+        });
+    }
+    fn record_offered(&mut self, channel: &str, accept: bool) {
+        self.record(|interpreter| Action::Offered {
+            channel: channel.to_string(),
+            peer: interpreter.get_peer(channel),
+            accept,
+        });
+    }
+    fn flip(
+        &mut self,
+        name: &std::string::String,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        if let Some(InactiveRoutine::Buffered { routine, .. }) = self.active.children.get(name)
+            && !routine.is_process()
+        {
+            return self.deliver(name);
+        }
+        self.active.flip(name);
+        self.record(|interpreter| Action::Flipped {
+            channel: name.clone(),
+            routine: interpreter.get_owner(interpreter.active.node),
+        });
+        Ok(())
+    }
+    fn deliver(
+        &mut self,
+        name: &std::string::String,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        let (outgoing, incoming, mut routine) = split(self.active.children.remove(name).unwrap());
+        for message in outgoing {
+            match (&mut routine, message) {
+                (InactiveRoutine::Cancelled, Message::Value(value)) => self.discard(value),
+                (InactiveRoutine::Cancelled, Message::Choice(_)) => {}
+                (routine, Message::Choice(accept)) => {
+                    routine.choose(self.graph, &mut self.interaction, accept)?
+                }
+                (_, Message::Value(_)) => return Err(std::boxed::Box::new(Error::TypeError)),
+            }
+        }
+        self.active.children.insert(
+            name.clone(),
+            join(std::collections::VecDeque::new(), incoming, routine),
+        );
+        Ok(())
+    }
+    fn execute(&mut self) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        if crate::graph::get_principals(&self.graph.nodees[self.active.node])
+            .into_iter()
+            .any(|principal| {
+                matches!(
+                    self.active.children.get(principal),
+                    Some(InactiveRoutine::Cancelled)
+                )
+            })
+        {
+            if let Some(r#try) = self.graph.node_tryes.get(&self.active.node) {
+                return self.raise(*r#try);
+            }
+            return self.cancel();
+        }
         match &self.graph.nodees[self.active.node] {
             crate::graph::Node::Branch { next } => self.active.node = *next,
             crate::graph::Node::Assign {
@@ -118,7 +1135,12 @@ impl<'a> Interpreter<'a> {
                 next,
             } => {
                 self.active.node = *next;
-                let value = self.active.evaluate(self.graph, value_expression)?;
+                let value = self.active.evaluate(
+                    self.graph,
+                    value_expression,
+                    &self.limits,
+                    &mut self.statistics,
+                )?;
                 if !value.is_parent_to_interaction() {
                     self.active.to_interaction = name.clone();
                 }
@@ -137,19 +1159,38 @@ impl<'a> Interpreter<'a> {
                 for (formal, actual_expression) in
                     routine.formals.iter().zip(actual_expressions.iter())
                 {
-                    let actual = self.active.evaluate(self.graph, actual_expression)?;
+                    let actual = self.active.evaluate(
+                        self.graph,
+                        actual_expression,
+                        &self.limits,
+                        &mut self.statistics,
+                    )?;
                     if !actual.is_parent_to_interaction() {
                         to_interaction = Some(formal.name.clone());
                     }
                     children.insert(formal.name.clone(), actual);
                 }
                 if let Some(to_interaction) = to_interaction {
-                    self.active = ActiveRoutine {
-                        node: routine.start,
-                        children,
-                        to_interaction,
-                    };
+                    self.record(|_| Action::Entered {
+                        routine: name.clone(),
+                    });
+                    for child in std::mem::replace(
+                        &mut self.active,
+                        ActiveRoutine {
+                            node: routine.start,
+                            children,
+                            to_interaction,
+                        },
+                    )
+                    .children
+                    .into_values()
+                    {
+                        self.discard(child);
+                    }
                 } else {
+                    for child in children.into_values() {
+                        self.discard(child);
+                    }
                     self.active.node = *next;
                 }
             }
@@ -161,7 +1202,15 @@ impl<'a> Interpreter<'a> {
                 if !self.active.children.contains_key(source) {
                     return Err(std::boxed::Box::new(Error::UnboundVariable(source.clone())));
                 }
-                if *source == self.active.to_interaction
+                if let Some(message) = self.pop_incoming(source) {
+                    let value = match message {
+                        Message::Value(value) => value,
+                        Message::Choice(_) => return Err(std::boxed::Box::new(Error::TypeError)),
+                    };
+                    self.record_received(source);
+                    self.active.node = *next;
+                    self.bind(variable, value)?;
+                } else if *source == self.active.to_interaction
                     && self
                         .active
                         .children
@@ -175,6 +1224,7 @@ impl<'a> Interpreter<'a> {
                         .get_mut(source)
                         .unwrap()
                         .receive(self.graph)?;
+                    self.record_received(source);
                     self.active.node = *next;
                     if !value.is_parent_to_interaction() {
                         self.active.to_interaction = variable.clone();
@@ -188,7 +1238,7 @@ impl<'a> Interpreter<'a> {
                         return Err(std::boxed::Box::new(Error::Overwriting(variable.clone())));
                     }
                 } else {
-                    self.active.flip(source);
+                    self.flip(source)?;
                 }
             }
             crate::graph::Node::Send {
@@ -225,8 +1275,23 @@ impl<'a> Interpreter<'a> {
                         .get_mut(destination)
                         .unwrap()
                         .send(self.graph, value)?;
+                    self.record_sent(destination);
+                } else if self.can_enqueue(destination) {
+                    let value = if let Some(value) = self.active.children.remove(variable) {
+                        value
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnboundVariable(
+                            variable.clone(),
+                        )));
+                    };
+                    self.active.node = *next;
+                    if !value.is_parent_to_interaction() {
+                        self.active.to_interaction = destination.clone();
+                    }
+                    self.enqueue(destination, Message::Value(value));
+                    self.record_sent(destination);
                 } else {
-                    self.active.flip(destination);
+                    self.flip(destination)?;
                 }
             }
             crate::graph::Node::Offer {
@@ -237,7 +1302,14 @@ impl<'a> Interpreter<'a> {
                 if !self.active.children.contains_key(client) {
                     return Err(std::boxed::Box::new(Error::UnboundVariable(client.clone())));
                 }
-                if *client == self.active.to_interaction
+                if let Some(message) = self.pop_incoming(client) {
+                    let accept = match message {
+                        Message::Choice(accept) => accept,
+                        Message::Value(_) => return Err(std::boxed::Box::new(Error::TypeError)),
+                    };
+                    self.record_offered(client, accept);
+                    self.active.node = if accept { *accepted } else { *denied };
+                } else if *client == self.active.to_interaction
                     && self
                         .active
                         .children
@@ -245,19 +1317,80 @@ impl<'a> Interpreter<'a> {
                         .unwrap()
                         .is_parent_principal(self.graph)
                 {
-                    self.active.node = if self
+                    let accept = self
                         .active
                         .children
                         .get_mut(client)
                         .unwrap()
-                        .offer(self.graph)?
-                    {
-                        *accepted
+                        .offer(self.graph, &mut self.interaction)?;
+                    self.record_offered(client, accept);
+                    self.active.node = if accept { *accepted } else { *denied };
+                } else {
+                    self.flip(client)?;
+                }
+            }
+            crate::graph::Node::Select { arms } => {
+                let mut readys = std::vec::Vec::new();
+                for arm in arms.iter() {
+                    let child = if let Some(child) = self.active.children.get(arm.get_name()) {
+                        child
                     } else {
-                        *denied
+                        return Err(std::boxed::Box::new(Error::UnboundVariable(
+                            arm.get_name().clone(),
+                        )));
                     };
-                } else {
-                    self.active.flip(client);
+                    if child.is_parent_principal(self.graph) || self.has_incoming(arm.get_name()) {
+                        readys.push(arm);
+                    }
+                }
+                if readys.is_empty() {
+                    let index = self.pick(arms.len());
+                    self.flip(arms[index].get_name())?;
+                    return Ok(true);
+                }
+                let index = self.pick(readys.len());
+                match readys[index] {
+                    crate::graph::Arm::Offer {
+                        client,
+                        accepted,
+                        denied,
+                    } => {
+                        let accept = match self.pop_incoming(client) {
+                            Some(Message::Choice(accept)) => accept,
+                            Some(Message::Value(_)) => {
+                                return Err(std::boxed::Box::new(Error::TypeError));
+                            }
+                            None => self
+                                .active
+                                .children
+                                .get_mut(client)
+                                .unwrap()
+                                .offer(self.graph, &mut self.interaction)?,
+                        };
+                        self.record_offered(client, accept);
+                        self.active.node = if accept { *accepted } else { *denied };
+                    }
+                    crate::graph::Arm::Receive {
+                        source,
+                        variable,
+                        next,
+                    } => {
+                        let value = match self.pop_incoming(source) {
+                            Some(Message::Value(value)) => value,
+                            Some(Message::Choice(_)) => {
+                                return Err(std::boxed::Box::new(Error::TypeError));
+                            }
+                            None => self
+                                .active
+                                .children
+                                .get_mut(source)
+                                .unwrap()
+                                .receive(self.graph)?,
+                        };
+                        self.record_received(source);
+                        self.active.node = *next;
+                        self.bind(variable, value)?;
+                    }
                 }
             }
             crate::graph::Node::Accept { server, next } => {
@@ -272,14 +1405,19 @@ impl<'a> Interpreter<'a> {
                         .unwrap()
                         .is_parent_principal(self.graph)
                 {
-                    self.active
-                        .children
-                        .get_mut(server)
-                        .unwrap()
-                        .choose(self.graph, true)?;
+                    self.active.children.get_mut(server).unwrap().choose(
+                        self.graph,
+                        &mut self.interaction,
+                        true,
+                    )?;
+                    self.record_chose(server, true);
+                    self.active.node = *next;
+                } else if self.can_enqueue(server) {
+                    self.enqueue(server, Message::Choice(true));
+                    self.record_chose(server, true);
                     self.active.node = *next;
                 } else {
-                    self.active.flip(server);
+                    self.flip(server)?;
                 }
             }
             crate::graph::Node::Deny { server, next } => {
@@ -294,14 +1432,19 @@ impl<'a> Interpreter<'a> {
                         .unwrap()
                         .is_parent_principal(self.graph)
                 {
-                    self.active
-                        .children
-                        .get_mut(server)
-                        .unwrap()
-                        .choose(self.graph, false)?;
+                    self.active.children.get_mut(server).unwrap().choose(
+                        self.graph,
+                        &mut self.interaction,
+                        false,
+                    )?;
+                    self.record_chose(server, false);
+                    self.active.node = *next;
+                } else if self.can_enqueue(server) {
+                    self.enqueue(server, Message::Choice(false));
+                    self.record_chose(server, false);
                     self.active.node = *next;
                 } else {
-                    self.active.flip(server);
+                    self.flip(server)?;
                 }
             }
             crate::graph::Node::Close { name, .. } => {
@@ -316,11 +1459,74 @@ impl<'a> Interpreter<'a> {
                         .unwrap()
                         .is_parent_principal(self.graph)
                 {
-                    match self.active.children.remove(name).unwrap() {
-                        InactiveRoutine::Interaction { .. } => {
+                    self.record(|interpreter| Action::Closed {
+                        channel: name.clone(),
+                        peer: interpreter.get_peer(name),
+                    });
+                    match self.active.children.remove(name).unwrap().strip() {
+                        InactiveRoutine::Interaction => {
+                            return Err(std::boxed::Box::new(Error::TypeError));
+                        }
+                        InactiveRoutine::Cancelled => {
+                            return Err(std::boxed::Box::new(Error::TypeError));
+                        }
+                        InactiveRoutine::Shared { .. }
+                        | InactiveRoutine::Acquired { .. }
+                        | InactiveRoutine::Buffered { .. } => {
                             return Err(std::boxed::Box::new(Error::TypeError));
                         }
                         InactiveRoutine::InteractionEnd => return Ok(false),
+                        InactiveRoutine::Graph {
+                            node,
+                            children,
+                            to_interaction,
+                            ..
+                        } => match self.graph.nodees[node] {
+                            crate::graph::Node::Wait { next, .. } => {
+                                self.end(ActiveRoutine {
+                                    node: next,
+                                    children,
+                                    to_interaction,
+                                });
+                            }
+                            _ => return Err(std::boxed::Box::new(Error::TypeError)),
+                        },
+                    }
+                } else {
+                    self.flip(name)?;
+                }
+            }
+            crate::graph::Node::Wait { name, .. } => {
+                if !self.active.children.contains_key(name) {
+                    return Err(std::boxed::Box::new(Error::UnboundVariable(name.clone())));
+                }
+                if *name == self.active.to_interaction
+                    && self
+                        .active
+                        .children
+                        .get(name)
+                        .unwrap()
+                        .is_parent_principal(self.graph)
+                {
+                    self.record(|interpreter| Action::Waited {
+                        channel: name.clone(),
+                        peer: interpreter.get_peer(name),
+                    });
+                    match self.active.children.remove(name).unwrap().strip() {
+                        InactiveRoutine::Interaction => {
+                            return Err(std::boxed::Box::new(Error::TypeError));
+                        }
+                        InactiveRoutine::Cancelled => {
+                            return Err(std::boxed::Box::new(Error::TypeError));
+                        }
+                        InactiveRoutine::Shared { .. }
+                        | InactiveRoutine::Acquired { .. }
+                        | InactiveRoutine::Buffered { .. } => {
+                            return Err(std::boxed::Box::new(Error::TypeError));
+                        }
+                        InactiveRoutine::InteractionEnd => {
+                            return Err(std::boxed::Box::new(Error::TypeError));
+                        }
                         InactiveRoutine::Graph {
                             node,
                             children,
@@ -328,16 +1534,168 @@ impl<'a> Interpreter<'a> {
                             ..
                         } => match self.graph.nodees[node] {
                             crate::graph::Node::Close { next, .. } => {
-                                self.active.node = next;
-                                self.active.children = children;
-                                self.active.to_interaction = to_interaction;
+                                self.end(ActiveRoutine {
+                                    node: next,
+                                    children,
+                                    to_interaction,
+                                });
                             }
                             _ => return Err(std::boxed::Box::new(Error::TypeError)),
                         },
                     }
                 } else {
-                    self.active.flip(name);
+                    self.flip(name)?;
+                }
+            }
+            crate::graph::Node::Absurd { name } => match self.active.children.get(name) {
+                Some(child) if child.is_process() => self.flip(name)?,
+                _ => return Err(std::boxed::Box::new(Error::Absurd(name.clone()))),
+            },
+            crate::graph::Node::Acquire { name, next } => {
+                let child = if let Some(child) = self.active.children.get(name) {
+                    child
+                } else {
+                    return Err(std::boxed::Box::new(Error::UnboundVariable(name.clone())));
+                };
+                if let InactiveRoutine::Shared { index, ticket } = child {
+                    return self.acquire(name, *index, *ticket);
+                }
+                if child.is_parent_principal(self.graph) {
+                    self.active
+                        .children
+                        .get_mut(name)
+                        .unwrap()
+                        .advance(self.graph)?;
+                    self.active.node = *next;
+                } else {
+                    self.flip(name)?;
+                }
+            }
+            crate::graph::Node::Release { name, next } => {
+                if !self.active.children.contains_key(name) {
+                    return Err(std::boxed::Box::new(Error::UnboundVariable(name.clone())));
+                }
+                if !self
+                    .active
+                    .children
+                    .get(name)
+                    .unwrap()
+                    .is_parent_principal(self.graph)
+                {
+                    self.flip(name)?;
+                    return Ok(true);
+                }
+                let (index, peer) = match self.active.children.remove(name).unwrap().strip() {
+                    InactiveRoutine::Acquired { index, routine } => (Some(index), *routine),
+                    peer => (None, peer),
+                };
+                let (node, mut children, parent, to_interaction) = match peer {
+                    InactiveRoutine::Graph {
+                        node,
+                        children,
+                        parent,
+                        to_interaction,
+                    } => (node, children, parent, to_interaction),
+                    _ => return Err(std::boxed::Box::new(Error::TypeError)),
+                };
+                let peer_next = match self.graph.nodees[node] {
+                    crate::graph::Node::Release { next, .. } => next,
+                    _ => return Err(std::boxed::Box::new(Error::TypeError)),
+                };
+                if *name == self.active.to_interaction {
+                    let server = InactiveRoutine::Graph {
+                        node: *next,
+                        children: std::mem::take(&mut self.active.children),
+                        parent: name.clone(),
+                        to_interaction: name.clone(),
+                    };
+                    let index = match index {
+                        Some(index) => {
+                            self.shareds[index].routine = Some(server);
+                            index
+                        }
+                        None => self.add_shared(server),
+                    };
+                    children.insert(
+                        parent,
+                        InactiveRoutine::Shared {
+                            index,
+                            ticket: None,
+                        },
+                    );
+                    self.active = ActiveRoutine {
+                        node: peer_next,
+                        children,
+                        to_interaction,
+                    };
+                } else {
+                    let server = InactiveRoutine::Graph {
+                        node: peer_next,
+                        children,
+                        parent,
+                        to_interaction,
+                    };
+                    let index = match index {
+                        Some(index) => {
+                            self.shareds[index].routine = Some(server);
+                            index
+                        }
+                        None => self.add_shared(server),
+                    };
+                    self.active.children.insert(
+                        name.clone(),
+                        InactiveRoutine::Shared {
+                            index,
+                            ticket: None,
+                        },
+                    );
+                    self.active.node = *next;
+                }
+            }
+            crate::graph::Node::Share { name, copy, next } => {
+                let index = match self
+                    .active
+                    .children
+                    .remove(name)
+                    .map(InactiveRoutine::strip)
+                {
+                    Some(InactiveRoutine::Shared { index, ticket }) => {
+                        self.active
+                            .children
+                            .insert(name.clone(), InactiveRoutine::Shared { index, ticket });
+                        index
+                    }
+                    Some(routine @ InactiveRoutine::Graph { .. }) => {
+                        let index = self.add_shared(routine);
+                        self.active.children.insert(
+                            name.clone(),
+                            InactiveRoutine::Shared {
+                                index,
+                                ticket: None,
+                            },
+                        );
+                        index
+                    }
+                    Some(_) => return Err(std::boxed::Box::new(Error::TypeError)),
+                    None => {
+                        return Err(std::boxed::Box::new(Error::UnboundVariable(name.clone())));
+                    }
+                };
+                if self
+                    .active
+                    .children
+                    .insert(
+                        copy.clone(),
+                        InactiveRoutine::Shared {
+                            index,
+                            ticket: None,
+                        },
+                    )
+                    .is_some()
+                {
+                    return Err(std::boxed::Box::new(Error::Overwriting(copy.clone())));
                 }
+                self.active.node = *next;
             }
             crate::graph::Node::Connect { left, right, next } => {
                 let left_child = if let Some(left_child) = self.active.children.remove(left) {
@@ -350,74 +1708,157 @@ impl<'a> Interpreter<'a> {
                 } else {
                     return Err(std::boxed::Box::new(Error::TypeError));
                 };
+                let (left_outgoing, left_incoming, left_routine) = split(left_child);
+                let (right_outgoing, right_incoming, right_routine) = split(right_child);
+                let mut to_left = left_outgoing;
+                to_left.extend(right_incoming);
+                let mut to_right = right_outgoing;
+                to_right.extend(left_incoming);
                 if self.active.to_interaction == *left {
-                    match right_child {
+                    let left_child = join(to_left, to_right, left_routine);
+                    let (index, right_routine) = match right_routine {
+                        InactiveRoutine::Acquired { index, routine } => (Some(index), *routine),
+                        routine => (None, routine),
+                    };
+                    match right_routine {
                         InactiveRoutine::Graph {
                             node,
                             mut children,
                             parent,
                             to_interaction,
                         } => {
+                            let left_child = match index {
+                                Some(index) => InactiveRoutine::Acquired {
+                                    index,
+                                    routine: std::boxed::Box::new(left_child),
+                                },
+                                None => left_child,
+                            };
                             children.insert(parent, left_child);
-                            self.active = ActiveRoutine {
+                            self.end(ActiveRoutine {
                                 node,
                                 children,
                                 to_interaction,
-                            };
+                            });
+                        }
+                        InactiveRoutine::Cancelled => {
+                            self.active.children.insert(left.clone(), left_child);
+                            return self.cancel();
                         }
                         _ => panic!(),
                     }
                 } else if self.active.to_interaction == *right {
-                    match left_child {
+                    let right_child = join(to_right, to_left, right_routine);
+                    let (index, left_routine) = match left_routine {
+                        InactiveRoutine::Acquired { index, routine } => (Some(index), *routine),
+                        routine => (None, routine),
+                    };
+                    match left_routine {
                         InactiveRoutine::Graph {
                             node,
                             mut children,
                             parent,
                             to_interaction,
                         } => {
+                            let right_child = match index {
+                                Some(index) => InactiveRoutine::Acquired {
+                                    index,
+                                    routine: std::boxed::Box::new(right_child),
+                                },
+                                None => right_child,
+                            };
                             children.insert(parent, right_child);
-                            self.active = ActiveRoutine {
+                            self.end(ActiveRoutine {
                                 node,
                                 children,
                                 to_interaction,
-                            };
+                            });
+                        }
+                        InactiveRoutine::Cancelled => {
+                            self.active.children.insert(right.clone(), right_child);
+                            return self.cancel();
                         }
                         _ => panic!(),
                     }
                 } else {
+                    self.discard(join(to_left, to_right, left_routine));
+                    self.discard(right_routine);
                     self.active.node = *next;
                 }
             }
+            crate::graph::Node::End if self.active.to_interaction.is_empty() => {
+                return self.finish();
+            }
             crate::graph::Node::End => return Err(std::boxed::Box::new(Error::TypeError)),
+            crate::graph::Node::Fail { location } => {
+                return Err(std::boxed::Box::new(Error::Failed(location.clone())));
+            }
+            crate::graph::Node::Decide {
+                name,
+                r#type,
+                accepted,
+                denied,
+            } => {
+                let host = if let Some(host) = &mut self.host {
+                    host
+                } else {
+                    return Err(std::boxed::Box::new(Error::NoHost));
+                };
+                self.active.node = if host.decide(name, *r#type)? {
+                    *accepted
+                } else {
+                    *denied
+                };
+            }
+            crate::graph::Node::Report {
+                name,
+                r#type,
+                event,
+                next,
+            } => {
+                if let Some(host) = &mut self.host {
+                    host.report(name, *r#type, *event)?;
+                }
+                self.active.node = *next;
+            }
+            crate::graph::Node::Raise { r#try } => return self.raise(*r#try),
+            crate::graph::Node::Drop => return self.cancel(),
         }
         Ok(true)
     }
 }
 
-impl<'a> ActiveRoutine<'a> {
+impl ActiveRoutine {
     fn flip(&mut self, name: &std::string::String) {
-        let (name, node, mut children, parent, to_interaction) =
-            match self.children.remove_entry(name).unwrap() {
-                (
-                    name,
-                    InactiveRoutine::Graph {
-                        node,
-                        children,
-                        parent,
-                        to_interaction,
-                    },
-                ) => (name, node, children, parent, to_interaction),
-                _ => panic!(),
-            };
-        children.insert(
-            parent,
+        let (name, child) = self.children.remove_entry(name).unwrap();
+        let (outgoing, incoming, child) = split(child);
+        let (index, child) = match child {
+            InactiveRoutine::Acquired { index, routine } => (Some(index), *routine),
+            child => (None, child),
+        };
+        let (node, mut children, parent, to_interaction) = match child {
             InactiveRoutine::Graph {
-                node: std::mem::take(&mut self.node),
-                children: std::mem::take(&mut self.children),
-                parent: name,
-                to_interaction: std::mem::take(&mut self.to_interaction),
+                node,
+                children,
+                parent,
+                to_interaction,
+            } => (node, children, parent, to_interaction),
+            _ => panic!(),
+        };
+        let routine = InactiveRoutine::Graph {
+            node: std::mem::take(&mut self.node),
+            children: std::mem::take(&mut self.children),
+            parent: name,
+            to_interaction: std::mem::take(&mut self.to_interaction),
+        };
+        let routine = match index {
+            Some(index) => InactiveRoutine::Acquired {
+                index,
+                routine: std::boxed::Box::new(routine),
             },
-        );
+            None => routine,
+        };
+        children.insert(parent, join(incoming, outgoing, routine));
         self.node = node;
         self.children = children;
         self.to_interaction = to_interaction;
@@ -426,7 +1867,32 @@ impl<'a> ActiveRoutine<'a> {
         &mut self,
         graph: &crate::graph::Graph,
         expression: &crate::graph::Expression,
-    ) -> std::result::Result<InactiveRoutine<'a>, std::boxed::Box<dyn std::error::Error>> {
+        limits: &Limits,
+        statistics: &mut Statistics,
+    ) -> std::result::Result<InactiveRoutine, std::boxed::Box<dyn std::error::Error>> {
+        if !matches!(expression, crate::graph::Expression::Variable { .. }) {
+            statistics.processes += 1;
+            statistics.channels += 1;
+            let limit = if limits
+                .processes
+                .is_some_and(|limit| statistics.processes > limit)
+            {
+                Some(Limit::Processes)
+            } else if limits
+                .channels
+                .is_some_and(|limit| statistics.channels > limit)
+            {
+                Some(Limit::Channels)
+            } else {
+                None
+            };
+            if let Some(limit) = limit {
+                return Err(std::boxed::Box::new(Error::LimitExceeded {
+                    limit,
+                    statistics: *statistics,
+                }));
+            }
+        }
         match expression {
             crate::graph::Expression::Variable { name } => {
                 let value = if let Some(value) = self.children.remove(name) {
@@ -445,14 +1911,14 @@ impl<'a> ActiveRoutine<'a> {
                 let mut children = std::collections::HashMap::new();
                 let mut to_interaction_index = before.len();
                 for (index, actual_expression) in (0..).zip(before.iter()) {
-                    let actual = self.evaluate(graph, actual_expression)?;
+                    let actual = self.evaluate(graph, actual_expression, limits, statistics)?;
                     if !actual.is_parent_to_interaction() {
                         to_interaction_index = index;
                     }
                     children.insert(routine.formals[index].name.clone(), actual);
                 }
                 for (index, actual_expression) in ((before.len() + 1)..).zip(after.iter()) {
-                    let actual = self.evaluate(graph, actual_expression)?;
+                    let actual = self.evaluate(graph, actual_expression, limits, statistics)?;
                     if !actual.is_parent_to_interaction() {
                         to_interaction_index = index;
                     }
@@ -465,33 +1931,75 @@ impl<'a> ActiveRoutine<'a> {
                     to_interaction: routine.formals[to_interaction_index].name.clone(),
                 })
             }
+            crate::graph::Expression::Fork { fork, names } => {
+                let fork = &graph.forkes[*fork];
+                let parent = fork.formals[0].name.clone();
+                let mut children = std::collections::HashMap::new();
+                let mut to_interaction = parent.clone();
+                for name in names {
+                    if let Some(actual) = self.children.remove(name) {
+                        if !actual.is_parent_to_interaction() {
+                            to_interaction = name.clone();
+                        }
+                        children.insert(name.clone(), actual);
+                    }
+                }
+                Ok(InactiveRoutine::Graph {
+                    node: fork.start,
+                    children,
+                    parent,
+                    to_interaction,
+                })
+            }
         }
     }
 }
 
-impl<'a> InactiveRoutine<'a> {
+impl InactiveRoutine {
+    fn is_process(&self) -> bool {
+        match self {
+            InactiveRoutine::Acquired { routine, .. }
+            | InactiveRoutine::Buffered { routine, .. } => routine.is_process(),
+            InactiveRoutine::Graph { .. } => true,
+            _ => false,
+        }
+    }
     fn is_parent_principal(&self, graph: &crate::graph::Graph) -> bool {
         match self {
-            InactiveRoutine::Interaction { .. } => true,
+            InactiveRoutine::Interaction => true,
             InactiveRoutine::InteractionEnd => true,
+            InactiveRoutine::Cancelled => true,
+            InactiveRoutine::Shared { .. } => false,
+            InactiveRoutine::Acquired { routine, .. } => routine.is_parent_principal(graph),
+            InactiveRoutine::Buffered {
+                outgoing, routine, ..
+            } => outgoing.is_empty() && routine.is_parent_principal(graph),
             InactiveRoutine::Graph { node, parent, .. } => {
-                let principal = match &graph.nodees[*node] {
-                    crate::graph::Node::Receive { source, .. } => source,
-                    crate::graph::Node::Send { destination, .. } => destination,
-                    crate::graph::Node::Offer { client, .. } => client,
-                    crate::graph::Node::Accept { server, .. } => server,
-                    crate::graph::Node::Deny { server, .. } => server,
-                    crate::graph::Node::Close { name, .. } => name,
-                    _ => return false,
-                };
-                *parent == *principal
+                crate::graph::get_principals(&graph.nodees[*node]).contains(&parent)
             }
         }
     }
     fn is_parent_to_interaction(&self) -> bool {
         match self {
-            InactiveRoutine::Interaction { .. } => false,
+            InactiveRoutine::Interaction => false,
             InactiveRoutine::InteractionEnd => false,
+            InactiveRoutine::Cancelled => false,
+            InactiveRoutine::Shared { .. } => true,
+            InactiveRoutine::Acquired { routine, .. } => routine.is_parent_to_interaction(),
+            InactiveRoutine::Buffered {
+                outgoing,
+                incoming,
+                routine,
+            } => {
+                routine.is_parent_to_interaction()
+                    && outgoing
+                        .iter()
+                        .chain(incoming.iter())
+                        .all(|message| match message {
+                            Message::Value(value) => value.is_parent_to_interaction(),
+                            Message::Choice(_) => true,
+                        })
+            }
             InactiveRoutine::Graph {
                 parent,
                 to_interaction,
@@ -502,38 +2010,53 @@ impl<'a> InactiveRoutine<'a> {
     fn send(
         &mut self,
         graph: &crate::graph::Graph,
-        value: InactiveRoutine<'a>,
+        value: InactiveRoutine,
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
         match self {
-            InactiveRoutine::Interaction { .. } => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Interaction => Err(std::boxed::Box::new(Error::TypeError)),
             InactiveRoutine::InteractionEnd => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Cancelled => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Shared { .. } => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Acquired { routine, .. } => routine.send(graph, value),
+            InactiveRoutine::Buffered { routine, .. } => routine.send(graph, value),
             InactiveRoutine::Graph {
                 node,
                 children,
+                parent,
                 to_interaction,
-                ..
-            } => match &graph.nodees[*node] {
-                crate::graph::Node::Receive { variable, next, .. } => {
-                    *node = *next;
-                    if !value.is_parent_to_interaction() {
-                        *to_interaction = variable.clone();
-                    }
-                    if children.insert(variable.clone(), value).is_some() {
-                        return Err(std::boxed::Box::new(Error::Overwriting(variable.clone())));
-                    }
-                    Ok(())
+            } => {
+                let (variable, next) = match &graph.nodees[*node] {
+                    crate::graph::Node::Receive { variable, next, .. } => (variable, *next),
+                    crate::graph::Node::Select { arms } => match get_arm(arms, parent) {
+                        Some(crate::graph::Arm::Receive { variable, next, .. }) => {
+                            (variable, *next)
+                        }
+                        _ => return Err(std::boxed::Box::new(Error::TypeError)),
+                    },
+                    _ => return Err(std::boxed::Box::new(Error::TypeError)),
+                };
+                *node = next;
+                if !value.is_parent_to_interaction() {
+                    *to_interaction = variable.clone();
                 }
-                _ => Err(std::boxed::Box::new(Error::TypeError)),
-            },
+                if children.insert(variable.clone(), value).is_some() {
+                    return Err(std::boxed::Box::new(Error::Overwriting(variable.clone())));
+                }
+                Ok(())
+            }
         }
     }
     fn receive(
         &mut self,
         graph: &crate::graph::Graph,
-    ) -> std::result::Result<InactiveRoutine<'a>, std::boxed::Box<dyn std::error::Error>> {
+    ) -> std::result::Result<InactiveRoutine, std::boxed::Box<dyn std::error::Error>> {
         match self {
-            InactiveRoutine::Interaction { .. } => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Interaction => Err(std::boxed::Box::new(Error::TypeError)),
             InactiveRoutine::InteractionEnd => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Cancelled => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Shared { .. } => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Acquired { routine, .. } => routine.receive(graph),
+            InactiveRoutine::Buffered { routine, .. } => routine.receive(graph),
             InactiveRoutine::Graph {
                 node,
                 children,
@@ -561,21 +2084,22 @@ impl<'a> InactiveRoutine<'a> {
     fn choose(
         &mut self,
         graph: &crate::graph::Graph,
+        interaction: &mut InteractionState,
         accept: bool,
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
         match self {
-            InactiveRoutine::Interaction { state } => match state {
+            InactiveRoutine::Interaction => match interaction {
                 InteractionState::Main(input, output) => {
                     if accept {
-                        **state = InteractionState::Input(*input, *output);
+                        *interaction = InteractionState::Input(*input, *output);
                     } else {
-                        **state = InteractionState::Output(*input, *output);
+                        *interaction = InteractionState::Output(*input, *output);
                     }
                     Ok(())
                 }
                 InteractionState::Output(input, output) => {
                     if accept {
-                        **state = InteractionState::Main(*input, *output + 1);
+                        *interaction = InteractionState::Main(*input, *output + 1);
                     } else {
                         *self = InactiveRoutine::InteractionEnd;
                     }
@@ -584,35 +2108,76 @@ impl<'a> InactiveRoutine<'a> {
                 _ => Err(std::boxed::Box::new(Error::TypeError)),
             },
             InactiveRoutine::InteractionEnd => Err(std::boxed::Box::new(Error::TypeError)),
-            InactiveRoutine::Graph { node, .. } => match &graph.nodees[*node] {
+            InactiveRoutine::Cancelled => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Shared { .. } => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Acquired { routine, .. } => routine.choose(graph, interaction, accept),
+            InactiveRoutine::Buffered { routine, .. } => routine.choose(graph, interaction, accept),
+            InactiveRoutine::Graph { node, parent, .. } => match &graph.nodees[*node] {
                 crate::graph::Node::Offer {
                     accepted, denied, ..
                 } => {
                     *node = if accept { *accepted } else { *denied };
                     Ok(())
                 }
+                crate::graph::Node::Select { arms } => match get_arm(arms, parent) {
+                    Some(crate::graph::Arm::Offer {
+                        accepted, denied, ..
+                    }) => {
+                        *node = if accept { *accepted } else { *denied };
+                        Ok(())
+                    }
+                    _ => Err(std::boxed::Box::new(Error::TypeError)),
+                },
+                _ => Err(std::boxed::Box::new(Error::TypeError)),
+            },
+        }
+    }
+    fn strip(self) -> Self {
+        match self {
+            InactiveRoutine::Buffered { routine, .. } => *routine,
+            routine => routine,
+        }
+    }
+    fn advance(
+        &mut self,
+        graph: &crate::graph::Graph,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        match self {
+            InactiveRoutine::Acquired { routine, .. } => routine.advance(graph),
+            InactiveRoutine::Buffered { routine, .. } => routine.advance(graph),
+            InactiveRoutine::Graph { node, .. } => match &graph.nodees[*node] {
+                crate::graph::Node::Acquire { next, .. } => {
+                    *node = *next;
+                    Ok(())
+                }
                 _ => Err(std::boxed::Box::new(Error::TypeError)),
             },
+            _ => Err(std::boxed::Box::new(Error::TypeError)),
         }
     }
     fn offer(
         &mut self,
         graph: &crate::graph::Graph,
+        interaction: &mut InteractionState,
     ) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
         match self {
-            InactiveRoutine::Interaction { state } => match state {
+            InactiveRoutine::Interaction => match interaction {
                 InteractionState::Input(input, output) => {
                     if *input != 0 {
-                        **state = InteractionState::Main(*input - 1, *output);
+                        *interaction = InteractionState::Main(*input - 1, *output);
                         Ok(true)
                     } else {
-                        **state = InteractionState::Main(*input, *output);
+                        *interaction = InteractionState::Main(*input, *output);
                         Ok(false)
                     }
                 }
                 _ => Err(std::boxed::Box::new(Error::TypeError)),
             },
             InactiveRoutine::InteractionEnd => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Cancelled => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Shared { .. } => Err(std::boxed::Box::new(Error::TypeError)),
+            InactiveRoutine::Acquired { routine, .. } => routine.offer(graph, interaction),
+            InactiveRoutine::Buffered { routine, .. } => routine.offer(graph, interaction),
             InactiveRoutine::Graph { node, .. } => match &graph.nodees[*node] {
                 crate::graph::Node::Accept { next, .. } => {
                     *node = *next;
@@ -627,3 +2192,119 @@ impl<'a> InactiveRoutine<'a> {
         }
     }
 }
+
+fn get_owners(
+    graph: &crate::graph::Graph,
+) -> std::vec::Vec<std::option::Option<std::string::String>> {
+    let mut owners = vec![None; graph.nodees.len()];
+    let mut starts: std::vec::Vec<(usize, std::string::String)> = graph
+        .testes
+        .iter()
+        .map(|test| (test.start, format!("test {:?}", test.name)))
+        .collect();
+    let mut routines: std::vec::Vec<_> = graph.routinees.iter().collect();
+    routines.sort_by(|left, right| right.0.cmp(left.0));
+    starts.extend(
+        routines
+            .into_iter()
+            .map(|(name, routine)| (routine.start, name.clone())),
+    );
+    while let Some((start, owner)) = starts.pop() {
+        let mut indexs = vec![start];
+        while let Some(index) = indexs.pop() {
+            if owners[index].is_some() {
+                continue;
+            }
+            owners[index] = Some(owner.clone());
+            let node = &graph.nodees[index];
+            indexs.extend(crate::graph::get_nexts(node));
+            if let Some(r#try) = graph.node_tryes.get(&index) {
+                indexs.push(graph.tryes[*r#try].handler);
+            }
+            if let crate::graph::Node::Raise { r#try } = node {
+                indexs.push(graph.tryes[*r#try].handler);
+            }
+            for fork in crate::graph::get_forks(node) {
+                starts.push((graph.forkes[fork].start, format!("{} fork {}", owner, fork)));
+            }
+        }
+    }
+    owners
+}
+
+fn find(
+    children: &std::collections::HashMap<std::string::String, InactiveRoutine>,
+    predicate: &dyn Fn(&InactiveRoutine) -> bool,
+) -> std::option::Option<std::vec::Vec<std::string::String>> {
+    for (name, child) in children.iter() {
+        if predicate(child) {
+            return Some(vec![]);
+        }
+        let child = match child {
+            InactiveRoutine::Buffered { routine, .. } => routine,
+            child => child,
+        };
+        let child = match child {
+            InactiveRoutine::Acquired { routine, .. } => routine,
+            child => child,
+        };
+        if let InactiveRoutine::Graph { children, .. } = child
+            && let Some(mut path) = find(children, predicate)
+        {
+            path.insert(0, name.clone());
+            return Some(path);
+        }
+    }
+    None
+}
+
+fn get_arm<'a>(
+    arms: &'a [crate::graph::Arm],
+    name: &std::string::String,
+) -> std::option::Option<&'a crate::graph::Arm> {
+    arms.iter().find(|arm| arm.get_name() == name)
+}
+
+fn split(
+    routine: InactiveRoutine,
+) -> (
+    std::collections::VecDeque<Message>,
+    std::collections::VecDeque<Message>,
+    InactiveRoutine,
+) {
+    match routine {
+        InactiveRoutine::Buffered {
+            outgoing,
+            incoming,
+            routine,
+        } => (outgoing, incoming, *routine),
+        routine => (
+            std::collections::VecDeque::new(),
+            std::collections::VecDeque::new(),
+            routine,
+        ),
+    }
+}
+
+fn join(
+    outgoing: std::collections::VecDeque<Message>,
+    incoming: std::collections::VecDeque<Message>,
+    routine: InactiveRoutine,
+) -> InactiveRoutine {
+    if outgoing.is_empty() && incoming.is_empty() {
+        routine
+    } else {
+        InactiveRoutine::Buffered {
+            outgoing,
+            incoming,
+            routine: std::boxed::Box::new(routine),
+        }
+    }
+}
+
+pub fn mix(seed: u64) -> u64 {
+    let mut random = seed.wrapping_add(0x9e3779b97f4a7c15);
+    random = (random ^ (random >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    random = (random ^ (random >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (random ^ (random >> 31)) | 1
+}
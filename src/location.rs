@@ -1,12 +1,24 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Location {
+    pub file: std::option::Option<std::rc::Rc<str>>,
     pub line: u32,
     pub column: u32,
 }
 
 impl Location {
     pub fn from_indexs(line: u32, column: u32) -> Self {
-        Location { line, column }
+        Location {
+            file: None,
+            line,
+            column,
+        }
+    }
+    pub fn from_file(file: std::rc::Rc<str>) -> Self {
+        Location {
+            file: Some(file),
+            line: 1,
+            column: 1,
+        }
     }
     pub fn next_line(&mut self) {
         self.line += 1;
@@ -19,6 +31,9 @@ impl Location {
 
 impl std::fmt::Display for Location {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
         write!(f, "{}:{}", self.line, self.column)?;
         Ok(())
     }
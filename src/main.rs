@@ -1,17 +1,29 @@
 mod anal;
 mod check;
+mod compat;
+mod debug;
+mod fuzz;
 mod graph;
 mod interpret;
 mod location;
+mod module;
 mod parse;
+mod peer;
+mod play;
+mod project;
 mod scan;
 mod token;
+mod trace;
+mod transcript;
 mod tree;
 
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum Error {
     WrongArgumentCount,
+    UnknownOption(std::string::String),
+    InapplicableOption(std::string::String),
+    TestsFailed(usize),
 }
 
 impl std::fmt::Display for Error {
@@ -20,6 +32,15 @@ impl std::fmt::Display for Error {
             Error::WrongArgumentCount => {
                 write!(f, "wrong argument count")?;
             }
+            Error::UnknownOption(option) => {
+                write!(f, "unknown option {:?}", option)?;
+            }
+            Error::InapplicableOption(option) => {
+                write!(f, "option {:?} does not apply to this command", option)?;
+            }
+            Error::TestsFailed(count) => {
+                write!(f, "{} of the tests failed", count)?;
+            }
         }
         Ok(())
     }
@@ -27,36 +48,518 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+fn read_tree(
+    path: &str,
+    prelude: bool,
+) -> std::result::Result<crate::tree::Tree, std::boxed::Box<dyn std::error::Error>> {
+    crate::module::load(std::path::Path::new(path), prelude)
+}
+
+fn read_unprojected_tree(
+    path: &str,
+) -> std::result::Result<crate::tree::Tree, std::boxed::Box<dyn std::error::Error>> {
+    crate::module::read(std::path::Path::new(path))
+}
+
+fn read_type_expression(
+    text: &str,
+) -> std::result::Result<crate::tree::TypeExpression, std::boxed::Box<dyn std::error::Error>> {
+    let scanner = crate::scan::scan(text.chars().map(Ok))?;
+    crate::parse::parse_type_expression(scanner)
+}
+
+fn subtype(
+    tree: crate::tree::Tree,
+    sub: &str,
+    sup: &str,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let expressions = vec![read_type_expression(sub)?, read_type_expression(sup)?];
+    let (graph, indexs) = crate::anal::anal_with_type_expressions(tree, expressions)?;
+    println!("{}", crate::check::is_subtype(&graph, indexs[0], indexs[1]));
+    Ok(())
+}
+
+fn compat(
+    old: crate::tree::Tree,
+    new: crate::tree::Tree,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let report = crate::compat::compat(old, new)?;
+    let mut is_breaking = false;
+    for (declaration, changes) in report {
+        for change in changes {
+            is_breaking |= change.is_breaking();
+            println!("{}: {}", declaration, change);
+        }
+    }
+    if is_breaking {
+        Err(std::boxed::Box::new(crate::compat::Error::Incompatible))
+    } else {
+        Ok(())
+    }
+}
+
+fn project(
+    tree: crate::tree::Tree,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    crate::project::check_protocols(&tree.protocoles)?;
+    for protocol in tree.protocoles.iter() {
+        for r#type in crate::project::project_protocol(protocol)? {
+            println!("{}", r#type);
+        }
+    }
+    Ok(())
+}
+
+const OPTIONS: &[&str] = &[
+    "--no-prelude",
+    "--trace",
+    "--seed",
+    "--buffer",
+    "--runs",
+    "--budget",
+    "--depth",
+    "--max-steps",
+    "--max-processes",
+    "--max-channels",
+    "--record",
+    "--timeout",
+];
+
+const RUN_OPTIONS: &[&str] = &[
+    "--no-prelude",
+    "--trace",
+    "--seed",
+    "--buffer",
+    "--max-steps",
+    "--max-processes",
+    "--max-channels",
+    "--timeout",
+];
+
+const TEST_OPTIONS: &[&str] = &[
+    "--no-prelude",
+    "--seed",
+    "--buffer",
+    "--max-steps",
+    "--max-processes",
+    "--max-channels",
+    "--timeout",
+];
+
+const PLAY_OPTIONS: &[&str] = &[
+    "--no-prelude",
+    "--seed",
+    "--buffer",
+    "--max-steps",
+    "--max-processes",
+    "--max-channels",
+];
+
+const DEBUG_OPTIONS: &[&str] = &[
+    "--no-prelude",
+    "--seed",
+    "--buffer",
+    "--max-steps",
+    "--max-processes",
+    "--max-channels",
+    "--record",
+];
+
+const FUZZ_OPTIONS: &[&str] = &[
+    "--no-prelude",
+    "--seed",
+    "--buffer",
+    "--runs",
+    "--budget",
+    "--depth",
+    "--max-processes",
+    "--max-channels",
+];
+
+const TREE_OPTIONS: &[&str] = &["--no-prelude"];
+
+struct Options {
+    prelude: bool,
+    trace: bool,
+    record: std::option::Option<usize>,
+    timeout: std::option::Option<std::time::Duration>,
+    interpret: crate::interpret::Options,
+    fuzz: crate::fuzz::Options,
+}
+
+fn read_options(
+    args: &[std::string::String],
+    applicables: &[&str],
+) -> std::result::Result<Options, std::boxed::Box<dyn std::error::Error>> {
+    let mut options = Options {
+        prelude: true,
+        trace: false,
+        record: None,
+        timeout: None,
+        interpret: crate::interpret::Options::default(),
+        fuzz: crate::fuzz::Options::default(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !applicables.contains(&arg.as_str()) {
+            if OPTIONS.contains(&arg.as_str()) {
+                return Err(std::boxed::Box::new(Error::InapplicableOption(arg.clone())));
+            }
+            return Err(std::boxed::Box::new(Error::UnknownOption(arg.clone())));
+        }
+        if arg == "--no-prelude" {
+            options.prelude = false;
+            continue;
+        }
+        if arg == "--trace" {
+            options.trace = true;
+            continue;
+        }
+        let value = if let Some(value) = args.next() {
+            value
+        } else {
+            return Err(std::boxed::Box::new(Error::WrongArgumentCount));
+        };
+        match arg.as_str() {
+            "--seed" => options.interpret.seed = Some(value.parse().map_err(std::boxed::Box::new)?),
+            "--buffer" => {
+                options.interpret.mode = crate::interpret::Mode::Asynchronous {
+                    capacity: if value == "unbounded" {
+                        None
+                    } else {
+                        Some(value.parse().map_err(std::boxed::Box::new)?)
+                    },
+                }
+            }
+            "--runs" => options.fuzz.runs = value.parse().map_err(std::boxed::Box::new)?,
+            "--budget" => options.fuzz.budget = value.parse().map_err(std::boxed::Box::new)?,
+            "--depth" => options.fuzz.depth = value.parse().map_err(std::boxed::Box::new)?,
+            "--max-steps" => {
+                options.interpret.limits.steps = Some(value.parse().map_err(std::boxed::Box::new)?)
+            }
+            "--max-processes" => {
+                options.interpret.limits.processes =
+                    Some(value.parse().map_err(std::boxed::Box::new)?)
+            }
+            "--max-channels" => {
+                options.interpret.limits.channels =
+                    Some(value.parse().map_err(std::boxed::Box::new)?)
+            }
+            "--record" => options.record = Some(value.parse().map_err(std::boxed::Box::new)?),
+            "--timeout" => {
+                let seconds = value.parse().map_err(std::boxed::Box::new)?;
+                options.timeout = Some(
+                    std::time::Duration::try_from_secs_f64(seconds)
+                        .map_err(std::boxed::Box::new)?,
+                );
+            }
+            _ => return Err(std::boxed::Box::new(Error::UnknownOption(arg.clone()))),
+        }
+    }
+    Ok(options)
+}
+
+fn start_timer(
+    timeout: std::option::Option<std::time::Duration>,
+    options: &mut crate::interpret::Options,
+) {
+    if let Some(timeout) = timeout {
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            flag.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        options.cancel = Some(cancel);
+    }
+}
+
 fn run(
-    reader: impl std::io::Read,
+    tree: crate::tree::Tree,
+    trace: bool,
+    timeout: std::option::Option<std::time::Duration>,
+    mut options: crate::interpret::Options,
 ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
-    let characters = utf8_decode::UnsafeDecoder::new(reader.bytes()).map(|character| {
-        character
-            .map_err(|error| std::boxed::Box::new(error) as std::boxed::Box<dyn std::error::Error>)
-    });
-    let scanner = crate::scan::scan(characters)?;
-    let tree = crate::parse::parse(scanner)?;
     let graph = crate::anal::anal(tree)?;
     crate::check::check(&graph)?;
     let mut line = std::string::String::new();
     std::io::stdin().read_line(&mut line)?;
     let input = line.trim().parse().map_err(std::boxed::Box::new)?;
-    let value = crate::interpret::interpret(graph, input)?;
+    start_timer(timeout, &mut options);
+    let value = if trace {
+        crate::interpret::trace(graph, input, options, &mut |event| {
+            eprintln!("{}", crate::trace::to_json(event))
+        })?
+    } else {
+        crate::interpret::interpret(graph, input, options)?
+    };
     println!("{:?}", value);
     Ok(())
 }
 
+fn test(
+    tree: crate::tree::Tree,
+    filter: std::option::Option<&str>,
+    timeout: std::option::Option<std::time::Duration>,
+    mut options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let graph = crate::anal::anal(tree)?;
+    crate::check::check(&graph)?;
+    start_timer(timeout, &mut options);
+    let mut passed = 0;
+    let mut failed = 0;
+    let mut filtered = 0;
+    for test in graph.testes.iter() {
+        if filter.is_some_and(|filter| !test.name.contains(filter)) {
+            filtered += 1;
+            continue;
+        }
+        match crate::interpret::test(&graph, test, options.clone()) {
+            Ok(()) => {
+                println!("test {:?} ... ok", test.name);
+                passed += 1;
+            }
+            Err(error) => {
+                println!("test {:?} ... FAILED: {}", test.name, error);
+                failed += 1;
+            }
+        }
+    }
+    println!(
+        "{} passed; {} failed; {} filtered out",
+        passed, failed, filtered
+    );
+    if failed > 0 {
+        Err(std::boxed::Box::new(Error::TestsFailed(failed)))
+    } else {
+        Ok(())
+    }
+}
+
+fn transcript(
+    tree: crate::tree::Tree,
+    routine: &str,
+    transcripts: std::vec::Vec<crate::transcript::Transcript>,
+    timeout: std::option::Option<std::time::Duration>,
+    mut options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let graph = crate::anal::anal(tree)?;
+    crate::check::check(&graph)?;
+    start_timer(timeout, &mut options);
+    crate::transcript::replay(&graph, routine, &transcripts, options)?;
+    println!("ok");
+    Ok(())
+}
+
+fn anal_with_names(
+    tree: crate::tree::Tree,
+) -> std::result::Result<
+    (
+        crate::graph::Graph,
+        std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    ),
+    std::boxed::Box<dyn std::error::Error>,
+> {
+    let names: std::vec::Vec<std::string::String> = tree
+        .typees
+        .iter()
+        .map(|r#type| r#type.name.clone())
+        .chain(tree.dataes.iter().map(|data| data.name.clone()))
+        .collect();
+    let expressions = names
+        .iter()
+        .map(|name| crate::tree::TypeExpression::Variable {
+            name: name.clone(),
+            is_dual: false,
+        })
+        .collect();
+    let (graph, indexs) = crate::anal::anal_with_type_expressions(tree, expressions)?;
+    let names = crate::peer::get_names(&graph, &names, &indexs);
+    Ok((graph, names))
+}
+
+fn play(
+    tree: crate::tree::Tree,
+    routine: &str,
+    formal: &str,
+    options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let (graph, names) = anal_with_names(tree)?;
+    crate::check::check(&graph)?;
+    crate::play::play(graph, &names, routine, formal, options)
+}
+
+fn debug(
+    tree: crate::tree::Tree,
+    record: std::option::Option<usize>,
+    options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let (graph, names) = anal_with_names(tree)?;
+    crate::debug::debug(graph, &names, record, options)
+}
+
+fn fuzz(
+    tree: crate::tree::Tree,
+    routine: &str,
+    options: crate::fuzz::Options,
+    interpret: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let graph = crate::anal::anal(tree)?;
+    crate::check::check(&graph)?;
+    crate::fuzz::fuzz(graph, routine, options, interpret)
+}
+
 fn main() -> std::process::ExitCode {
     fn inner(
         args: &[std::string::String],
     ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
-        if args.len() == 2 {
-            let file = std::fs::File::open(&args[1]).map_err(std::boxed::Box::new)?;
-            let reader = std::io::BufReader::new(file);
-            run(reader)?;
-            Ok(())
-        } else {
-            Err(std::boxed::Box::new(Error::WrongArgumentCount))
+        match args.get(1).map(|arg| arg.as_str()) {
+            Some("subtype") => {
+                if args.len() == 4 {
+                    let tree = crate::tree::Tree {
+                        typees: vec![],
+                        routinees: vec![],
+                        protocoles: vec![],
+                        dataes: vec![],
+                        importes: vec![],
+                        publics: std::collections::BTreeSet::new(),
+                        preludees: std::collections::BTreeSet::new(),
+                        testes: vec![],
+                    };
+                    subtype(tree, &args[2], &args[3])
+                } else if args.len() >= 5 {
+                    let options = read_options(&args[5..], TREE_OPTIONS)?;
+                    subtype(read_tree(&args[2], options.prelude)?, &args[3], &args[4])
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("project") => {
+                if args.len() == 3 {
+                    project(read_unprojected_tree(&args[2])?)
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("test") => {
+                if args.len() >= 3 {
+                    let (filter, rest) = match args.get(3) {
+                        Some(filter) if !filter.starts_with("--") => {
+                            (Some(filter.as_str()), &args[4..])
+                        }
+                        _ => (None, &args[3..]),
+                    };
+                    let options = read_options(rest, TEST_OPTIONS)?;
+                    test(
+                        read_tree(&args[2], options.prelude)?,
+                        filter,
+                        options.timeout,
+                        options.interpret,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("transcript") => {
+                let mut transcripts = std::vec::Vec::new();
+                let mut rest = args.get(4..).unwrap_or_default();
+                while let [formal, path, tail @ ..] = rest {
+                    if formal.starts_with("--") {
+                        break;
+                    }
+                    transcripts.push(crate::transcript::Transcript {
+                        formal: formal.clone(),
+                        steps: crate::transcript::read(std::path::Path::new(path))?,
+                    });
+                    rest = tail;
+                }
+                if transcripts.is_empty() {
+                    return Err(std::boxed::Box::new(Error::WrongArgumentCount));
+                }
+                let options = read_options(rest, TEST_OPTIONS)?;
+                transcript(
+                    read_tree(&args[2], options.prelude)?,
+                    &args[3],
+                    transcripts,
+                    options.timeout,
+                    options.interpret,
+                )
+            }
+            Some("play") => {
+                if args.len() >= 5 {
+                    let options = read_options(&args[5..], PLAY_OPTIONS)?;
+                    play(
+                        read_tree(&args[2], options.prelude)?,
+                        &args[3],
+                        &args[4],
+                        options.interpret,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("fuzz") => {
+                if args.len() >= 4 {
+                    let options = read_options(&args[4..], FUZZ_OPTIONS)?;
+                    fuzz(
+                        read_tree(&args[2], options.prelude)?,
+                        &args[3],
+                        options.fuzz,
+                        options.interpret,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("run") => {
+                if args.len() >= 3 {
+                    let options = read_options(&args[3..], RUN_OPTIONS)?;
+                    run(
+                        read_tree(&args[2], options.prelude)?,
+                        options.trace,
+                        options.timeout,
+                        options.interpret,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("debug") => {
+                if args.len() >= 3 {
+                    let options = read_options(&args[3..], DEBUG_OPTIONS)?;
+                    debug(
+                        read_tree(&args[2], options.prelude)?,
+                        options.record,
+                        options.interpret,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            Some("compat") => {
+                if args.len() >= 4 {
+                    let options = read_options(&args[4..], TREE_OPTIONS)?;
+                    compat(
+                        read_tree(&args[2], options.prelude)?,
+                        read_tree(&args[3], options.prelude)?,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
+            _ => {
+                if args.len() >= 2 {
+                    let options = read_options(&args[2..], RUN_OPTIONS)?;
+                    run(
+                        read_tree(&args[1], options.prelude)?,
+                        options.trace,
+                        options.timeout,
+                        options.interpret,
+                    )
+                } else {
+                    Err(std::boxed::Box::new(Error::WrongArgumentCount))
+                }
+            }
         }
     }
     let args: std::vec::Vec<std::string::String> = std::env::args().collect();
@@ -0,0 +1,656 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    Unreadable {
+        path: std::string::String,
+        location: std::option::Option<crate::location::Location>,
+        error: std::io::Error,
+    },
+    ImportCycle(std::vec::Vec<std::string::String>),
+    DuplicateModule {
+        name: std::string::String,
+        location: crate::location::Location,
+    },
+    UnknownModule {
+        name: std::string::String,
+        file: std::rc::Rc<str>,
+    },
+    UnknownName {
+        name: std::string::String,
+        file: std::rc::Rc<str>,
+    },
+    NotPublic {
+        name: std::string::String,
+        file: std::rc::Rc<str>,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Unreadable {
+                path,
+                location,
+                error,
+            } => {
+                write!(f, "cannot read {}", path)?;
+                if let Some(location) = location {
+                    write!(f, " imported at {}", location)?;
+                }
+                write!(f, ": {}", error)?;
+            }
+            Error::ImportCycle(paths) => {
+                write!(f, "import cycle {}", paths.join(" -> "))?;
+            }
+            Error::DuplicateModule { name, location } => {
+                write!(
+                    f,
+                    "module {:?} imported at {} conflicts with another module of the same name",
+                    name, location
+                )?;
+            }
+            Error::UnknownModule { name, file } => {
+                write!(f, "unknown module {:?} in {}", name, file)?;
+            }
+            Error::UnknownName { name, file } => {
+                write!(f, "unknown name {:?} in {}", name, file)?;
+            }
+            Error::NotPublic { name, file } => {
+                write!(f, "{:?} is not public in {}", name, file)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn read(
+    path: &std::path::Path,
+) -> std::result::Result<crate::tree::Tree, std::boxed::Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path).map_err(std::boxed::Box::new)?;
+    let reader = std::io::BufReader::new(file);
+    let characters =
+        utf8_decode::UnsafeDecoder::new(std::io::Read::bytes(reader)).map(|character| {
+            character.map_err(|error| {
+                std::boxed::Box::new(error) as std::boxed::Box<dyn std::error::Error>
+            })
+        });
+    let scanner = crate::scan::scan_file(characters, path.display().to_string().into())?;
+    crate::parse::parse(scanner)
+}
+
+const PRELUDE: &str = include_str!("prelude.cr");
+
+pub fn load(
+    path: &std::path::Path,
+    prelude: bool,
+) -> std::result::Result<crate::tree::Tree, std::boxed::Box<dyn std::error::Error>> {
+    let mut loader = Loader {
+        modules: std::vec::Vec::new(),
+        indexs: std::collections::HashMap::new(),
+        stack: std::vec::Vec::new(),
+        names: std::collections::HashMap::new(),
+        prelude: None,
+    };
+    if prelude {
+        let name = std::string::String::from("prelude");
+        let file: std::rc::Rc<str> = "<prelude>".into();
+        let scanner = crate::scan::scan_file(PRELUDE.chars().map(Ok), file.clone())?;
+        let tree = crate::parse::parse(scanner)?;
+        loader
+            .names
+            .insert(name.clone(), std::path::PathBuf::from(&*file));
+        let index = loader.add_module(file, format!("{}::", name), tree)?;
+        loader.prelude = Some(index);
+    }
+    loader.load_module(path, None)?;
+    loader.into_tree()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Kind {
+    Type,
+    Routine,
+    Constructor,
+}
+
+struct Module {
+    file: std::rc::Rc<str>,
+    prefix: std::string::String,
+    tree: crate::tree::Tree,
+    modules: std::collections::HashMap<std::string::String, usize>,
+    uses: std::collections::HashMap<std::string::String, usize>,
+}
+
+impl Module {
+    fn declares(&self, kind: Kind, name: &str) -> bool {
+        match kind {
+            Kind::Type => {
+                self.tree.typees.iter().any(|r#type| r#type.name == name)
+                    || self.tree.dataes.iter().any(|data| data.name == name)
+            }
+            Kind::Routine => self
+                .tree
+                .routinees
+                .iter()
+                .any(|routine| routine.name == name),
+            Kind::Constructor => self.tree.dataes.iter().any(|data| {
+                data.constructors
+                    .iter()
+                    .any(|constructor| constructor.name == name)
+            }),
+        }
+    }
+}
+
+struct Loader {
+    modules: std::vec::Vec<Module>,
+    indexs: std::collections::HashMap<std::path::PathBuf, usize>,
+    stack: std::vec::Vec<(std::path::PathBuf, std::rc::Rc<str>)>,
+    names: std::collections::HashMap<std::string::String, std::path::PathBuf>,
+    prelude: std::option::Option<usize>,
+}
+
+impl Loader {
+    fn add_module(
+        &mut self,
+        file: std::rc::Rc<str>,
+        prefix: std::string::String,
+        mut tree: crate::tree::Tree,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        crate::project::check_protocols(&tree.protocoles)?;
+        for protocol in std::mem::take(&mut tree.protocoles) {
+            let typees = crate::project::project_protocol(&protocol)?;
+            if tree.publics.contains(&protocol.name) {
+                tree.publics
+                    .extend(typees.iter().map(|r#type| r#type.name.clone()));
+            }
+            tree.typees.extend(typees);
+        }
+        for data in tree.dataes.iter() {
+            if tree.publics.contains(&data.name) {
+                tree.publics.extend(
+                    data.constructors
+                        .iter()
+                        .map(|constructor| constructor.name.clone()),
+                );
+            }
+        }
+        let mut modules = std::collections::HashMap::new();
+        if let Some(prelude) = self.prelude {
+            modules.insert(std::string::String::from("prelude"), prelude);
+        }
+        self.modules.push(Module {
+            file,
+            prefix,
+            tree,
+            modules,
+            uses: std::collections::HashMap::new(),
+        });
+        Ok(self.modules.len() - 1)
+    }
+    fn load_module(
+        &mut self,
+        path: &std::path::Path,
+        location: std::option::Option<&crate::location::Location>,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let file: std::rc::Rc<str> = path.display().to_string().into();
+        let canonical = std::fs::canonicalize(path).map_err(|error| Error::Unreadable {
+            path: file.to_string(),
+            location: location.cloned(),
+            error,
+        })?;
+        if let Some(position) = self
+            .stack
+            .iter()
+            .position(|(stacked, _)| *stacked == canonical)
+        {
+            let mut files: std::vec::Vec<_> = self.stack[position..]
+                .iter()
+                .map(|(_, file)| file.to_string())
+                .collect();
+            files.push(file.to_string());
+            return Err(std::boxed::Box::new(Error::ImportCycle(files)));
+        }
+        if let Some(index) = self.indexs.get(&canonical) {
+            return Ok(*index);
+        }
+        let prefix = if let Some(location) = location {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if let Some(other) = self.names.get(&name)
+                && *other != canonical
+            {
+                return Err(std::boxed::Box::new(Error::DuplicateModule {
+                    name,
+                    location: location.clone(),
+                }));
+            }
+            self.names.insert(name.clone(), canonical.clone());
+            format!("{}::", name)
+        } else {
+            std::string::String::new()
+        };
+        let mut tree = read(path)?;
+        let importes = std::mem::take(&mut tree.importes);
+        let index = self.add_module(file.clone(), prefix, tree)?;
+        self.indexs.insert(canonical.clone(), index);
+        self.stack.push((canonical, file.clone()));
+        let directory = path.parent().unwrap_or(std::path::Path::new(""));
+        for import in importes {
+            match import {
+                crate::tree::Import::File { path, location } => {
+                    let path = directory.join(path);
+                    let module = self.load_module(&path, Some(&location))?;
+                    let name = self.modules[module]
+                        .prefix
+                        .trim_end_matches("::")
+                        .to_string();
+                    self.modules[index].modules.insert(name, module);
+                }
+                crate::tree::Import::Use {
+                    module,
+                    name,
+                    location,
+                } => {
+                    let module = if let Some(module) = self.modules[index].modules.get(&module) {
+                        *module
+                    } else {
+                        let path = directory.join(format!("{}.cr", module));
+                        let imported = self.load_module(&path, Some(&location))?;
+                        self.modules[index].modules.insert(module, imported);
+                        imported
+                    };
+                    let imported = &self.modules[module];
+                    if ![Kind::Type, Kind::Routine, Kind::Constructor]
+                        .into_iter()
+                        .any(|kind| imported.declares(kind, &name))
+                    {
+                        return Err(std::boxed::Box::new(Error::UnknownName { name, file }));
+                    }
+                    if !imported.tree.publics.contains(&name) {
+                        return Err(std::boxed::Box::new(Error::NotPublic { name, file }));
+                    }
+                    self.modules[index].uses.insert(name, module);
+                }
+            }
+        }
+        self.stack.pop();
+        Ok(index)
+    }
+    fn into_tree(
+        self,
+    ) -> std::result::Result<crate::tree::Tree, std::boxed::Box<dyn std::error::Error>> {
+        let mut typees = std::vec::Vec::new();
+        let mut routinees = std::vec::Vec::new();
+        let mut dataes = std::vec::Vec::new();
+        let mut testes = std::vec::Vec::new();
+        let mut preludees = std::collections::BTreeSet::new();
+        for index in 0..self.modules.len() {
+            let resolver = Resolver {
+                loader: &self,
+                index,
+            };
+            let lows = (typees.len(), dataes.len(), routinees.len());
+            let tree = &self.modules[index].tree;
+            for r#type in tree.typees.iter() {
+                typees.push(crate::tree::Type {
+                    name: resolver.resolve(Kind::Type, &r#type.name)?,
+                    value: std::boxed::Box::new(
+                        resolver.rename_type_expression(&r#type.value, &mut vec![])?,
+                    ),
+                });
+            }
+            for data in tree.dataes.iter() {
+                let mut constructors = std::vec::Vec::with_capacity(data.constructors.len());
+                for constructor in data.constructors.iter() {
+                    let mut fields = std::vec::Vec::with_capacity(constructor.fields.len());
+                    for field in constructor.fields.iter() {
+                        fields.push(resolver.rename_type_expression(field, &mut vec![])?);
+                    }
+                    constructors.push(crate::tree::Constructor {
+                        name: resolver.resolve(Kind::Constructor, &constructor.name)?,
+                        fields,
+                    });
+                }
+                dataes.push(crate::tree::Data {
+                    name: resolver.resolve(Kind::Type, &data.name)?,
+                    constructors,
+                });
+            }
+            for routine in tree.routinees.iter() {
+                let mut formals = std::vec::Vec::with_capacity(routine.formals.len());
+                for formal in routine.formals.iter() {
+                    formals.push(resolver.rename_formal(formal)?);
+                }
+                routinees.push(crate::tree::Routine {
+                    name: resolver.resolve(Kind::Routine, &routine.name)?,
+                    formals,
+                    body: resolver.rename_statements(&routine.body)?,
+                    is_affine: routine.is_affine,
+                });
+            }
+            if self.prelude == Some(index) {
+                preludees.extend(
+                    typees[lows.0..]
+                        .iter()
+                        .map(|r#type| r#type.name.clone())
+                        .chain(dataes[lows.1..].iter().map(|data| data.name.clone()))
+                        .chain(
+                            routinees[lows.2..]
+                                .iter()
+                                .map(|routine| routine.name.clone()),
+                        ),
+                );
+            }
+            if self.modules[index].prefix.is_empty() {
+                for test in tree.testes.iter() {
+                    testes.push(crate::tree::Test {
+                        name: test.name.clone(),
+                        body: resolver.rename_statements(&test.body)?,
+                        location: test.location.clone(),
+                    });
+                }
+            }
+        }
+        Ok(crate::tree::Tree {
+            typees,
+            routinees,
+            protocoles: vec![],
+            dataes,
+            importes: vec![],
+            publics: std::collections::BTreeSet::new(),
+            preludees,
+            testes,
+        })
+    }
+}
+
+struct Resolver<'a> {
+    loader: &'a Loader,
+    index: usize,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve(
+        &self,
+        kind: Kind,
+        name: &str,
+    ) -> std::result::Result<std::string::String, std::boxed::Box<dyn std::error::Error>> {
+        let module = &self.loader.modules[self.index];
+        if let Some((prefix, rest)) = name.split_once("::") {
+            let imported = if let Some(imported) = module.modules.get(prefix) {
+                &self.loader.modules[*imported]
+            } else {
+                return Err(std::boxed::Box::new(Error::UnknownModule {
+                    name: prefix.to_string(),
+                    file: module.file.clone(),
+                }));
+            };
+            if !imported.declares(kind, rest) {
+                return Err(std::boxed::Box::new(Error::UnknownName {
+                    name: name.to_string(),
+                    file: module.file.clone(),
+                }));
+            }
+            if !imported.tree.publics.contains(rest) {
+                return Err(std::boxed::Box::new(Error::NotPublic {
+                    name: name.to_string(),
+                    file: module.file.clone(),
+                }));
+            }
+            return Ok(format!("{}{}", imported.prefix, rest));
+        }
+        if module.declares(kind, name) {
+            return Ok(format!("{}{}", module.prefix, name));
+        }
+        if let Some(imported) = module.uses.get(name) {
+            let imported = &self.loader.modules[*imported];
+            if imported.declares(kind, name) {
+                return Ok(format!("{}{}", imported.prefix, name));
+            }
+        }
+        if let Some(prelude) = module.modules.get("prelude") {
+            let prelude = &self.loader.modules[*prelude];
+            if prelude.declares(kind, name) && prelude.tree.publics.contains(name) {
+                return Ok(format!("{}{}", prelude.prefix, name));
+            }
+        }
+        Ok(name.to_string())
+    }
+    fn rename_formal(
+        &self,
+        formal: &crate::tree::Formal,
+    ) -> std::result::Result<crate::tree::Formal, std::boxed::Box<dyn std::error::Error>> {
+        Ok(crate::tree::Formal {
+            name: formal.name.clone(),
+            r#type: self.rename_type_expression(&formal.r#type, &mut vec![])?,
+        })
+    }
+    fn rename_type_expression(
+        &self,
+        expression: &crate::tree::TypeExpression,
+        bound: &mut std::vec::Vec<std::string::String>,
+    ) -> std::result::Result<crate::tree::TypeExpression, std::boxed::Box<dyn std::error::Error>>
+    {
+        let mut rename = |expression: &crate::tree::TypeExpression| {
+            self.rename_type_expression(expression, bound)
+                .map(std::boxed::Box::new)
+        };
+        Ok(match expression {
+            crate::tree::TypeExpression::Variable { name, is_dual } => {
+                crate::tree::TypeExpression::Variable {
+                    name: if bound.contains(name) {
+                        name.clone()
+                    } else {
+                        self.resolve(Kind::Type, name)?
+                    },
+                    is_dual: *is_dual,
+                }
+            }
+            crate::tree::TypeExpression::Lollipop { value, next } => {
+                crate::tree::TypeExpression::Lollipop {
+                    value: rename(value)?,
+                    next: rename(next)?,
+                }
+            }
+            crate::tree::TypeExpression::Times { value, next } => {
+                crate::tree::TypeExpression::Times {
+                    value: rename(value)?,
+                    next: rename(next)?,
+                }
+            }
+            crate::tree::TypeExpression::With { accept, deny } => {
+                crate::tree::TypeExpression::With {
+                    accept: rename(accept)?,
+                    deny: rename(deny)?,
+                }
+            }
+            crate::tree::TypeExpression::Plus { accept, deny } => {
+                crate::tree::TypeExpression::Plus {
+                    accept: rename(accept)?,
+                    deny: rename(deny)?,
+                }
+            }
+            crate::tree::TypeExpression::Dual { value } => crate::tree::TypeExpression::Dual {
+                value: rename(value)?,
+            },
+            crate::tree::TypeExpression::Shared { value } => crate::tree::TypeExpression::Shared {
+                value: rename(value)?,
+            },
+            crate::tree::TypeExpression::Release { value } => {
+                crate::tree::TypeExpression::Release {
+                    value: rename(value)?,
+                }
+            }
+            crate::tree::TypeExpression::Recursive { name, value } => {
+                bound.push(name.clone());
+                let value = self.rename_type_expression(value, bound);
+                bound.pop();
+                crate::tree::TypeExpression::Recursive {
+                    name: name.clone(),
+                    value: std::boxed::Box::new(value?),
+                }
+            }
+            crate::tree::TypeExpression::One => crate::tree::TypeExpression::One,
+            crate::tree::TypeExpression::Bottom => crate::tree::TypeExpression::Bottom,
+            crate::tree::TypeExpression::Zero => crate::tree::TypeExpression::Zero,
+            crate::tree::TypeExpression::Top => crate::tree::TypeExpression::Top,
+        })
+    }
+    fn rename_expression(
+        &self,
+        expression: &crate::tree::Expression,
+    ) -> std::result::Result<crate::tree::Expression, std::boxed::Box<dyn std::error::Error>> {
+        Ok(match expression {
+            crate::tree::Expression::Variable { name } => {
+                crate::tree::Expression::Variable { name: name.clone() }
+            }
+            crate::tree::Expression::Call {
+                name,
+                before,
+                after,
+            } => crate::tree::Expression::Call {
+                name: self.resolve(Kind::Routine, name)?,
+                before: before
+                    .iter()
+                    .map(|actual| self.rename_expression(actual))
+                    .collect::<std::result::Result<_, _>>()?,
+                after: after
+                    .iter()
+                    .map(|actual| self.rename_expression(actual))
+                    .collect::<std::result::Result<_, _>>()?,
+            },
+            crate::tree::Expression::Literal { value, r#type } => {
+                crate::tree::Expression::Literal {
+                    value: *value,
+                    r#type: self.rename_type_expression(r#type, &mut vec![])?,
+                }
+            }
+            crate::tree::Expression::Fork { formal, body } => crate::tree::Expression::Fork {
+                formal: self.rename_formal(formal)?,
+                body: self.rename_statements(body)?,
+            },
+        })
+    }
+    fn rename_statements(
+        &self,
+        statements: &[crate::tree::Statement],
+    ) -> std::result::Result<
+        std::vec::Vec<crate::tree::Statement>,
+        std::boxed::Box<dyn std::error::Error>,
+    > {
+        let mut renamed = std::vec::Vec::with_capacity(statements.len());
+        for statement in statements {
+            renamed.push(match statement {
+                crate::tree::Statement::Assign { name, value } => crate::tree::Statement::Assign {
+                    name: name.clone(),
+                    value: std::boxed::Box::new(self.rename_expression(value)?),
+                },
+                crate::tree::Statement::Call { name, actuals } => crate::tree::Statement::Call {
+                    name: self.resolve(Kind::Routine, name)?,
+                    actuals: actuals
+                        .iter()
+                        .map(|actual| self.rename_expression(actual))
+                        .collect::<std::result::Result<_, _>>()?,
+                },
+                crate::tree::Statement::Offer {
+                    client,
+                    accepted,
+                    denied,
+                } => crate::tree::Statement::Offer {
+                    client: client.clone(),
+                    accepted: self.rename_statements(accepted)?,
+                    denied: self.rename_statements(denied)?,
+                },
+                crate::tree::Statement::Select { arms } => {
+                    let mut renamed_arms = std::vec::Vec::with_capacity(arms.len());
+                    for arm in arms {
+                        renamed_arms.push(match arm {
+                            crate::tree::Arm::Offer {
+                                client,
+                                accepted,
+                                denied,
+                            } => crate::tree::Arm::Offer {
+                                client: client.clone(),
+                                accepted: self.rename_statements(accepted)?,
+                                denied: self.rename_statements(denied)?,
+                            },
+                            crate::tree::Arm::Receive {
+                                source,
+                                variable,
+                                body,
+                            } => crate::tree::Arm::Receive {
+                                source: source.clone(),
+                                variable: variable.clone(),
+                                body: self.rename_statements(body)?,
+                            },
+                        });
+                    }
+                    crate::tree::Statement::Select { arms: renamed_arms }
+                }
+                crate::tree::Statement::Loop { body } => crate::tree::Statement::Loop {
+                    body: self.rename_statements(body)?,
+                },
+                crate::tree::Statement::While { client, body } => crate::tree::Statement::While {
+                    client: client.clone(),
+                    body: self.rename_statements(body)?,
+                },
+                crate::tree::Statement::Construct {
+                    name,
+                    constructor,
+                    fields,
+                } => crate::tree::Statement::Construct {
+                    name: name.clone(),
+                    constructor: self.resolve(Kind::Constructor, constructor)?,
+                    fields: fields.clone(),
+                },
+                crate::tree::Statement::Match { name, cases } => {
+                    let mut renamed_cases = std::vec::Vec::with_capacity(cases.len());
+                    for case in cases {
+                        renamed_cases.push(crate::tree::Case {
+                            constructor: self.resolve(Kind::Constructor, &case.constructor)?,
+                            variables: case.variables.clone(),
+                            body: self.rename_statements(&case.body)?,
+                        });
+                    }
+                    crate::tree::Statement::Match {
+                        name: name.clone(),
+                        cases: renamed_cases,
+                    }
+                }
+                crate::tree::Statement::Try { body, handler } => crate::tree::Statement::Try {
+                    body: self.rename_statements(body)?,
+                    handler: self.rename_statements(handler)?,
+                },
+                crate::tree::Statement::Assert { value, location } => {
+                    crate::tree::Statement::Assert {
+                        value: std::boxed::Box::new(self.rename_expression(value)?),
+                        location: location.clone(),
+                    }
+                }
+                crate::tree::Statement::Branch { .. }
+                | crate::tree::Statement::Label { .. }
+                | crate::tree::Statement::Mark { .. }
+                | crate::tree::Statement::Receive { .. }
+                | crate::tree::Statement::Send { .. }
+                | crate::tree::Statement::Accept { .. }
+                | crate::tree::Statement::Deny { .. }
+                | crate::tree::Statement::Close { .. }
+                | crate::tree::Statement::Wait { .. }
+                | crate::tree::Statement::Absurd { .. }
+                | crate::tree::Statement::Connect { .. }
+                | crate::tree::Statement::Acquire { .. }
+                | crate::tree::Statement::Release { .. }
+                | crate::tree::Statement::Share { .. }
+                | crate::tree::Statement::Break
+                | crate::tree::Statement::Continue
+                | crate::tree::Statement::Raise => statement.clone(),
+            });
+        }
+        Ok(renamed)
+    }
+}
@@ -39,6 +39,34 @@ pub fn parse(
     }
 }
 
+pub fn parse_type_expression(
+    tokens: impl std::iter::Iterator<
+        Item = std::result::Result<crate::token::Token, std::boxed::Box<dyn std::error::Error>>,
+    >,
+) -> std::result::Result<crate::tree::TypeExpression, std::boxed::Box<dyn std::error::Error>> {
+    let mut parser = Parser::from_tokens(tokens)?;
+    let expression = parser.parse_multiplicative()?;
+    if let Some(token) = parser.peek() {
+        Err(std::boxed::Box::new(Error::UnexpectedToken(token)))
+    } else {
+        Ok(expression)
+    }
+}
+
+pub fn parse_transcript(
+    tokens: impl std::iter::Iterator<
+        Item = std::result::Result<crate::token::Token, std::boxed::Box<dyn std::error::Error>>,
+    >,
+) -> std::result::Result<std::vec::Vec<crate::tree::Step>, std::boxed::Box<dyn std::error::Error>> {
+    let mut parser = Parser::from_tokens(tokens)?;
+    let steps = parser.parse_steps()?;
+    if let Some(token) = parser.peek() {
+        Err(std::boxed::Box::new(Error::UnexpectedToken(token)))
+    } else {
+        Ok(steps)
+    }
+}
+
 struct Parser<I> {
     tokens: I,
     lookahead: std::option::Option<crate::token::Token>,
@@ -87,8 +115,17 @@ impl<
         } else {
             return Err(std::boxed::Box::new(Error::UnexpectedEnd));
         };
-        if let crate::token::TokenValue::Identifier(name) = token.value {
+        if let crate::token::TokenValue::Identifier(mut name) = token.value {
             self.advance()?;
+            while let Some(crate::token::Token {
+                value: crate::token::TokenValue::DoubleColon,
+                ..
+            }) = self.peek()
+            {
+                self.advance()?;
+                name.push_str("::");
+                name.push_str(&self.parse_identifier()?);
+            }
             Ok(name)
         } else {
             Err(std::boxed::Box::new(Error::UnexpectedToken(token)))
@@ -99,14 +136,149 @@ impl<
     ) -> std::result::Result<crate::tree::Tree, std::boxed::Box<dyn std::error::Error>> {
         let mut typees = vec![];
         let mut routinees = vec![];
-        while let Some(token) = self.peek() {
-            match token.value {
-                crate::token::TokenValue::Type => typees.push(self.parse_type()?),
-                crate::token::TokenValue::Routine => routinees.push(self.parse_routine()?),
+        let mut protocoles = vec![];
+        let mut dataes = vec![];
+        let mut importes = vec![];
+        let mut publics = std::collections::BTreeSet::new();
+        let mut testes = vec![];
+        while let Some(mut token) = self.peek() {
+            let is_public = token.value == crate::token::TokenValue::Pub;
+            if is_public {
+                self.advance()?;
+                token = if let Some(token) = self.peek() {
+                    token
+                } else {
+                    return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+                };
+            }
+            let name = match token.value {
+                crate::token::TokenValue::Type => {
+                    typees.push(self.parse_type()?);
+                    typees.last().unwrap().name.clone()
+                }
+                crate::token::TokenValue::Routine => {
+                    routinees.push(self.parse_routine()?);
+                    routinees.last().unwrap().name.clone()
+                }
+                crate::token::TokenValue::Affine => {
+                    self.advance()?;
+                    let mut routine = self.parse_routine()?;
+                    routine.is_affine = true;
+                    routinees.push(routine);
+                    routinees.last().unwrap().name.clone()
+                }
+                crate::token::TokenValue::Protocol => {
+                    protocoles.push(self.parse_protocol()?);
+                    protocoles.last().unwrap().name.clone()
+                }
+                crate::token::TokenValue::Data => {
+                    dataes.push(self.parse_data()?);
+                    dataes.last().unwrap().name.clone()
+                }
+                crate::token::TokenValue::Import if !is_public => {
+                    self.advance()?;
+                    let token = if let Some(token) = self.peek() {
+                        token
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+                    };
+                    if let crate::token::TokenValue::String(path) = token.value {
+                        self.advance()?;
+                        importes.push(crate::tree::Import::File {
+                            path,
+                            location: token.location,
+                        });
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnexpectedToken(token)));
+                    }
+                    continue;
+                }
+                crate::token::TokenValue::Test if !is_public => {
+                    self.advance()?;
+                    let token = if let Some(token) = self.peek() {
+                        token
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+                    };
+                    if let crate::token::TokenValue::String(name) = token.value {
+                        self.advance()?;
+                        let body = self.parse_block()?;
+                        testes.push(crate::tree::Test {
+                            name,
+                            body,
+                            location: token.location,
+                        });
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnexpectedToken(token)));
+                    }
+                    continue;
+                }
+                crate::token::TokenValue::Use if !is_public => {
+                    self.advance()?;
+                    let module = self.parse_identifier()?;
+                    let (module, name) = if let Some((module, name)) = module.rsplit_once("::") {
+                        (module.to_string(), name.to_string())
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnexpectedToken(token)));
+                    };
+                    importes.push(crate::tree::Import::Use {
+                        module,
+                        name,
+                        location: token.location,
+                    });
+                    continue;
+                }
                 _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+            };
+            if is_public {
+                publics.insert(name);
             }
         }
-        Ok(crate::tree::Tree { typees, routinees })
+        Ok(crate::tree::Tree {
+            typees,
+            routinees,
+            protocoles,
+            dataes,
+            importes,
+            publics,
+            preludees: std::collections::BTreeSet::new(),
+            testes,
+        })
+    }
+    fn parse_data(
+        &mut self,
+    ) -> std::result::Result<crate::tree::Data, std::boxed::Box<dyn std::error::Error>> {
+        self.expect(crate::token::TokenValue::Data)?;
+        let name = self.parse_identifier()?;
+        self.expect(crate::token::TokenValue::Equals)?;
+        let mut constructors = vec![];
+        loop {
+            let name = self.parse_identifier()?;
+            let mut fields = vec![];
+            while let Some(token) = self.peek() {
+                match token.value {
+                    crate::token::TokenValue::Identifier(_)
+                    | crate::token::TokenValue::LeftParenthesis
+                    | crate::token::TokenValue::Times
+                    | crate::token::TokenValue::Rec
+                    | crate::token::TokenValue::Shared
+                    | crate::token::TokenValue::Release
+                    | crate::token::TokenValue::Integer(0 | 1)
+                    | crate::token::TokenValue::Bottom
+                    | crate::token::TokenValue::Top => fields.push(self.parse_primary()?),
+                    _ => break,
+                }
+            }
+            constructors.push(crate::tree::Constructor { name, fields });
+            match self.peek() {
+                Some(crate::token::Token {
+                    value: crate::token::TokenValue::Bar,
+                    ..
+                }) => self.advance()?,
+                _ => break,
+            }
+        }
+        Ok(crate::tree::Data { name, constructors })
     }
     fn parse_type(
         &mut self,
@@ -196,26 +368,165 @@ impl<
                 self.expect(crate::token::TokenValue::RightParenthesis)?;
                 Ok(expression)
             }
-            crate::token::TokenValue::Identifier(name) => {
-                self.advance()?;
+            crate::token::TokenValue::Identifier(_) => {
+                let name = self.parse_identifier()?;
                 Ok(crate::tree::TypeExpression::Variable {
                     name,
                     is_dual: false,
                 })
             }
             crate::token::TokenValue::Times => {
+                self.advance()?;
+                match self.parse_primary()? {
+                    crate::tree::TypeExpression::Variable { name, is_dual } => {
+                        Ok(crate::tree::TypeExpression::Variable {
+                            name,
+                            is_dual: !is_dual,
+                        })
+                    }
+                    value => Ok(crate::tree::TypeExpression::Dual {
+                        value: std::boxed::Box::new(value),
+                    }),
+                }
+            }
+            crate::token::TokenValue::Rec => {
                 self.advance()?;
                 let name = self.parse_identifier()?;
-                Ok(crate::tree::TypeExpression::Variable {
+                self.expect(crate::token::TokenValue::Dot)?;
+                let value = self.parse_multiplicative()?;
+                Ok(crate::tree::TypeExpression::Recursive {
                     name,
-                    is_dual: true,
+                    value: std::boxed::Box::new(value),
                 })
             }
-            crate::token::TokenValue::One => {
+            crate::token::TokenValue::Shared => {
+                self.advance()?;
+                let value = self.parse_primary()?;
+                Ok(crate::tree::TypeExpression::Shared {
+                    value: std::boxed::Box::new(value),
+                })
+            }
+            crate::token::TokenValue::Release => {
+                self.advance()?;
+                let value = self.parse_primary()?;
+                Ok(crate::tree::TypeExpression::Release {
+                    value: std::boxed::Box::new(value),
+                })
+            }
+            crate::token::TokenValue::Integer(1) => {
                 self.advance()?;
                 Ok(crate::tree::TypeExpression::One)
             }
-            _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+            crate::token::TokenValue::Bottom => {
+                self.advance()?;
+                Ok(crate::tree::TypeExpression::Bottom)
+            }
+            crate::token::TokenValue::Integer(0) => {
+                self.advance()?;
+                Ok(crate::tree::TypeExpression::Zero)
+            }
+            crate::token::TokenValue::Top => {
+                self.advance()?;
+                Ok(crate::tree::TypeExpression::Top)
+            }
+            _ => Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+        }
+    }
+    fn parse_protocol(
+        &mut self,
+    ) -> std::result::Result<crate::tree::Protocol, std::boxed::Box<dyn std::error::Error>> {
+        self.expect(crate::token::TokenValue::Protocol)?;
+        let name = self.parse_identifier()?;
+        self.expect(crate::token::TokenValue::LeftParenthesis)?;
+        let mut roles = vec![self.parse_identifier()?];
+        loop {
+            let token = if let Some(token) = self.peek() {
+                token
+            } else {
+                return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+            };
+            match token.value {
+                crate::token::TokenValue::Comma => self.advance()?,
+                crate::token::TokenValue::RightParenthesis => break,
+                _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+            }
+            roles.push(self.parse_identifier()?);
+        }
+        self.expect(crate::token::TokenValue::RightParenthesis)?;
+        let body = self.parse_interactions()?;
+        Ok(crate::tree::Protocol { name, roles, body })
+    }
+    fn parse_interactions(
+        &mut self,
+    ) -> std::result::Result<
+        std::vec::Vec<crate::tree::Interaction>,
+        std::boxed::Box<dyn std::error::Error>,
+    > {
+        self.expect(crate::token::TokenValue::LeftBrace)?;
+        let mut interactions = vec![];
+        loop {
+            let token = if let Some(token) = self.peek() {
+                token
+            } else {
+                return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+            };
+            if let crate::token::TokenValue::RightBrace = token.value {
+                break;
+            }
+            interactions.push(self.parse_interaction()?);
+        }
+        self.expect(crate::token::TokenValue::RightBrace)?;
+        Ok(interactions)
+    }
+    fn parse_interaction(
+        &mut self,
+    ) -> std::result::Result<crate::tree::Interaction, std::boxed::Box<dyn std::error::Error>> {
+        let token = if let Some(token) = self.peek() {
+            token
+        } else {
+            return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+        };
+        if let crate::token::TokenValue::Rec = token.value {
+            self.advance()?;
+            let name = self.parse_identifier()?;
+            let body = self.parse_interactions()?;
+            return Ok(crate::tree::Interaction::Recursive { name, body });
+        }
+        let sender = self.parse_identifier()?;
+        let token = if let Some(token) = self.peek() {
+            token
+        } else {
+            return Ok(crate::tree::Interaction::Continue { name: sender });
+        };
+        if token.value != crate::token::TokenValue::Arrow {
+            return Ok(crate::tree::Interaction::Continue { name: sender });
+        }
+        self.advance()?;
+        let receiver = self.parse_identifier()?;
+        self.expect(crate::token::TokenValue::Colon)?;
+        let token = if let Some(token) = self.peek() {
+            token
+        } else {
+            return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+        };
+        if let crate::token::TokenValue::Choice = token.value {
+            self.advance()?;
+            let accepted = self.parse_interactions()?;
+            self.expect(crate::token::TokenValue::Else)?;
+            let denied = self.parse_interactions()?;
+            Ok(crate::tree::Interaction::Choice {
+                sender,
+                receiver,
+                accepted,
+                denied,
+            })
+        } else {
+            let r#type = self.parse_multiplicative()?;
+            Ok(crate::tree::Interaction::Message {
+                sender,
+                receiver,
+                r#type,
+            })
         }
     }
     fn parse_routine(
@@ -250,6 +561,9 @@ impl<
             if let crate::token::TokenValue::RightBrace = token.value {
                 break;
             }
+            body.push(crate::tree::Statement::Mark {
+                location: token.location,
+            });
             body.push(self.parse_statement()?);
         }
         self.expect(crate::token::TokenValue::RightBrace)?;
@@ -257,6 +571,7 @@ impl<
             name,
             formals,
             body,
+            is_affine: false,
         })
     }
     fn parse_formal(
@@ -270,6 +585,61 @@ impl<
     fn parse_statement(
         &mut self,
     ) -> std::result::Result<crate::tree::Statement, std::boxed::Box<dyn std::error::Error>> {
+        let token = if let Some(token) = self.peek() {
+            token
+        } else {
+            return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+        };
+        match token.value {
+            crate::token::TokenValue::Try => {
+                self.advance()?;
+                let body = self.parse_block()?;
+                self.expect(crate::token::TokenValue::Catch)?;
+                let handler = self.parse_block()?;
+                return Ok(crate::tree::Statement::Try { body, handler });
+            }
+            crate::token::TokenValue::Raise => {
+                self.advance()?;
+                return Ok(crate::tree::Statement::Raise);
+            }
+            crate::token::TokenValue::Assert => {
+                self.advance()?;
+                let value = self.parse_expression()?;
+                return Ok(crate::tree::Statement::Assert {
+                    value: std::boxed::Box::new(value),
+                    location: token.location,
+                });
+            }
+            crate::token::TokenValue::Select => {
+                self.advance()?;
+                return self.parse_select();
+            }
+            crate::token::TokenValue::Match => {
+                self.advance()?;
+                return self.parse_match();
+            }
+            crate::token::TokenValue::Loop => {
+                self.advance()?;
+                let body = self.parse_block()?;
+                return Ok(crate::tree::Statement::Loop { body });
+            }
+            crate::token::TokenValue::While => {
+                self.advance()?;
+                let client = self.parse_identifier()?;
+                self.expect(crate::token::TokenValue::Offer)?;
+                let body = self.parse_block()?;
+                return Ok(crate::tree::Statement::While { client, body });
+            }
+            crate::token::TokenValue::Break => {
+                self.advance()?;
+                return Ok(crate::tree::Statement::Break);
+            }
+            crate::token::TokenValue::Continue => {
+                self.advance()?;
+                return Ok(crate::tree::Statement::Continue);
+            }
+            _ => {}
+        }
         let identifier = self.parse_identifier()?;
         let token = if let Some(token) = self.peek() {
             token
@@ -311,6 +681,38 @@ impl<
                     actuals,
                 })
             }
+            crate::token::TokenValue::LeftArrow => {
+                self.advance()?;
+                let constructor = self.parse_identifier()?;
+                let mut fields = vec![];
+                if let Some(crate::token::Token {
+                    value: crate::token::TokenValue::LeftParenthesis,
+                    ..
+                }) = self.peek()
+                {
+                    self.advance()?;
+                    loop {
+                        let token = if let Some(token) = self.peek() {
+                            token
+                        } else {
+                            return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+                        };
+                        if token.value == crate::token::TokenValue::RightParenthesis {
+                            break;
+                        }
+                        if !fields.is_empty() {
+                            self.expect(crate::token::TokenValue::Comma)?;
+                        }
+                        fields.push(self.parse_identifier()?);
+                    }
+                    self.expect(crate::token::TokenValue::RightParenthesis)?;
+                }
+                Ok(crate::tree::Statement::Construct {
+                    name: identifier,
+                    constructor,
+                    fields,
+                })
+            }
             crate::token::TokenValue::Receive => {
                 self.advance()?;
                 let variable = self.parse_identifier()?;
@@ -342,6 +744,9 @@ impl<
                         if token.value == crate::token::TokenValue::RightBrace {
                             break;
                         }
+                        accepted.push(crate::tree::Statement::Mark {
+                            location: token.location,
+                        });
                         accepted.push(self.parse_statement()?);
                     }
                     self.expect(crate::token::TokenValue::RightBrace)?;
@@ -369,6 +774,9 @@ impl<
                     if token.value == crate::token::TokenValue::RightBrace {
                         break;
                     }
+                    denied.push(crate::tree::Statement::Mark {
+                        location: token.location,
+                    });
                     denied.push(self.parse_statement()?);
                 }
                 self.expect(crate::token::TokenValue::RightBrace)?;
@@ -399,6 +807,30 @@ impl<
                 self.advance()?;
                 Ok(crate::tree::Statement::Close { name: identifier })
             }
+            crate::token::TokenValue::Wait => {
+                self.advance()?;
+                Ok(crate::tree::Statement::Wait { name: identifier })
+            }
+            crate::token::TokenValue::Absurd => {
+                self.advance()?;
+                Ok(crate::tree::Statement::Absurd { name: identifier })
+            }
+            crate::token::TokenValue::Acquire => {
+                self.advance()?;
+                Ok(crate::tree::Statement::Acquire { name: identifier })
+            }
+            crate::token::TokenValue::Release => {
+                self.advance()?;
+                Ok(crate::tree::Statement::Release { name: identifier })
+            }
+            crate::token::TokenValue::Share => {
+                self.advance()?;
+                let copy = self.parse_identifier()?;
+                Ok(crate::tree::Statement::Share {
+                    name: identifier,
+                    copy,
+                })
+            }
             crate::token::TokenValue::Connect => {
                 self.advance()?;
                 let variable = self.parse_identifier()?;
@@ -410,30 +842,219 @@ impl<
             _ => Ok(crate::tree::Statement::Branch { name: identifier }),
         }
     }
+    fn parse_match(
+        &mut self,
+    ) -> std::result::Result<crate::tree::Statement, std::boxed::Box<dyn std::error::Error>> {
+        let name = self.parse_identifier()?;
+        self.expect(crate::token::TokenValue::LeftBrace)?;
+        let mut cases = vec![];
+        loop {
+            let token = if let Some(token) = self.peek() {
+                token
+            } else {
+                return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+            };
+            if token.value == crate::token::TokenValue::RightBrace {
+                break;
+            }
+            let constructor = self.parse_identifier()?;
+            let mut variables = vec![];
+            while let Some(crate::token::Token {
+                value: crate::token::TokenValue::Identifier(_),
+                ..
+            }) = self.peek()
+            {
+                variables.push(self.parse_identifier()?);
+            }
+            let body = self.parse_block()?;
+            cases.push(crate::tree::Case {
+                constructor,
+                variables,
+                body,
+            });
+        }
+        self.expect(crate::token::TokenValue::RightBrace)?;
+        Ok(crate::tree::Statement::Match { name, cases })
+    }
+    fn parse_select(
+        &mut self,
+    ) -> std::result::Result<crate::tree::Statement, std::boxed::Box<dyn std::error::Error>> {
+        self.expect(crate::token::TokenValue::LeftBrace)?;
+        let mut arms = vec![];
+        loop {
+            let token = if let Some(token) = self.peek() {
+                token
+            } else {
+                return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+            };
+            if token.value == crate::token::TokenValue::RightBrace {
+                break;
+            }
+            let identifier = self.parse_identifier()?;
+            let token = if let Some(token) = self.peek() {
+                token
+            } else {
+                return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+            };
+            match token.value {
+                crate::token::TokenValue::Offer => {
+                    self.advance()?;
+                    let accepted = self.parse_block()?;
+                    self.expect(crate::token::TokenValue::Else)?;
+                    let denied = self.parse_block()?;
+                    arms.push(crate::tree::Arm::Offer {
+                        client: identifier,
+                        accepted,
+                        denied,
+                    });
+                }
+                crate::token::TokenValue::Receive => {
+                    self.advance()?;
+                    let variable = self.parse_identifier()?;
+                    let body = self.parse_block()?;
+                    arms.push(crate::tree::Arm::Receive {
+                        source: identifier,
+                        variable,
+                        body,
+                    });
+                }
+                _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+            }
+        }
+        self.expect(crate::token::TokenValue::RightBrace)?;
+        Ok(crate::tree::Statement::Select { arms })
+    }
+    fn parse_block(
+        &mut self,
+    ) -> std::result::Result<
+        std::vec::Vec<crate::tree::Statement>,
+        std::boxed::Box<dyn std::error::Error>,
+    > {
+        self.expect(crate::token::TokenValue::LeftBrace)?;
+        let mut statements = vec![];
+        loop {
+            let token = if let Some(token) = self.peek() {
+                token
+            } else {
+                return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+            };
+            if token.value == crate::token::TokenValue::RightBrace {
+                break;
+            }
+            statements.push(crate::tree::Statement::Mark {
+                location: token.location,
+            });
+            statements.push(self.parse_statement()?);
+        }
+        self.expect(crate::token::TokenValue::RightBrace)?;
+        Ok(statements)
+    }
+    fn parse_steps(
+        &mut self,
+    ) -> std::result::Result<std::vec::Vec<crate::tree::Step>, std::boxed::Box<dyn std::error::Error>>
+    {
+        let mut steps = vec![];
+        while let Some(token) = self.peek() {
+            let action = match token.value {
+                crate::token::TokenValue::RightBrace => break,
+                crate::token::TokenValue::Accept => {
+                    self.advance()?;
+                    crate::tree::Action::Accept
+                }
+                crate::token::TokenValue::Deny => {
+                    self.advance()?;
+                    crate::tree::Action::Deny
+                }
+                crate::token::TokenValue::Identifier(ref name) if name == "expect" => {
+                    self.advance()?;
+                    let token = if let Some(token) = self.peek() {
+                        token
+                    } else {
+                        return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+                    };
+                    match token.value {
+                        crate::token::TokenValue::Accept => {
+                            self.advance()?;
+                            crate::tree::Action::ExpectAccept
+                        }
+                        crate::token::TokenValue::Deny => {
+                            self.advance()?;
+                            crate::tree::Action::ExpectDeny
+                        }
+                        _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+                    }
+                }
+                crate::token::TokenValue::Send => {
+                    self.advance()?;
+                    self.expect(crate::token::TokenValue::LeftBrace)?;
+                    let body = self.parse_steps()?;
+                    self.expect(crate::token::TokenValue::RightBrace)?;
+                    crate::tree::Action::Send { body }
+                }
+                crate::token::TokenValue::Receive => {
+                    self.advance()?;
+                    self.expect(crate::token::TokenValue::LeftBrace)?;
+                    let body = self.parse_steps()?;
+                    self.expect(crate::token::TokenValue::RightBrace)?;
+                    crate::tree::Action::Receive { body }
+                }
+                crate::token::TokenValue::Close => {
+                    self.advance()?;
+                    crate::tree::Action::Close
+                }
+                crate::token::TokenValue::Wait => {
+                    self.advance()?;
+                    crate::tree::Action::Wait
+                }
+                _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+            };
+            steps.push(crate::tree::Step {
+                action,
+                location: token.location,
+            });
+            if let Some(crate::token::Token {
+                value: crate::token::TokenValue::Semicolon,
+                ..
+            }) = self.peek()
+            {
+                self.advance()?;
+            }
+        }
+        Ok(steps)
+    }
     fn parse_expression(
         &mut self,
     ) -> std::result::Result<crate::tree::Expression, std::boxed::Box<dyn std::error::Error>> {
+        if let Some(crate::token::Token {
+            value: crate::token::TokenValue::Integer(value),
+            ..
+        }) = self.peek()
+        {
+            self.advance()?;
+            self.expect(crate::token::TokenValue::Colon)?;
+            let r#type = self.parse_primary()?;
+            return Ok(crate::tree::Expression::Literal { value, r#type });
+        }
+        if let Some(crate::token::Token {
+            value: crate::token::TokenValue::Fork,
+            ..
+        }) = self.peek()
+        {
+            self.advance()?;
+            self.expect(crate::token::TokenValue::LeftParenthesis)?;
+            let formal = self.parse_formal()?;
+            self.expect(crate::token::TokenValue::RightParenthesis)?;
+            let body = self.parse_block()?;
+            return Ok(crate::tree::Expression::Fork { formal, body });
+        }
         let identifier = self.parse_identifier()?;
         let token = if let Some(token) = self.peek() {
             token
         } else {
             return Ok(crate::tree::Expression::Variable { name: identifier });
         };
-        match token.value {
-            crate::token::TokenValue::Comma => {
-                return Ok(crate::tree::Expression::Variable { name: identifier });
-            }
-            crate::token::TokenValue::RightParenthesis => {
-                return Ok(crate::tree::Expression::Variable { name: identifier });
-            }
-            crate::token::TokenValue::Identifier(_) => {
-                return Ok(crate::tree::Expression::Variable { name: identifier });
-            }
-            crate::token::TokenValue::RightBrace => {
-                return Ok(crate::tree::Expression::Variable { name: identifier });
-            }
-            crate::token::TokenValue::LeftParenthesis => {}
-            _ => return Err(std::boxed::Box::new(Error::UnexpectedToken(token))),
+        if token.value != crate::token::TokenValue::LeftParenthesis {
+            return Ok(crate::tree::Expression::Variable { name: identifier });
         }
         self.expect(crate::token::TokenValue::LeftParenthesis)?;
         let mut before = vec![];
@@ -0,0 +1,445 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    UnknownRoutine(std::string::String),
+    UnknownFormal(std::string::String),
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownRoutine(name) => {
+                write!(f, "unknown routine {:?}", name)?;
+            }
+            Error::UnknownFormal(name) => {
+                write!(f, "unknown formal {:?}", name)?;
+            }
+            Error::Unsupported(name) => {
+                write!(f, "peers cannot play a {} type", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+const RECEIVED: &str = "received";
+const SENT: &str = "sent";
+const VALUE: &str = "peer value";
+const DONE: &str = "peer done";
+const PARENT: &str = "peer parent";
+
+pub fn synthesize(
+    graph: &mut crate::graph::Graph,
+    routine: &str,
+    formal: &str,
+) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+    let formals = if let Some(routine) = graph.routinees.get(routine) {
+        routine.formals.clone()
+    } else {
+        return Err(std::boxed::Box::new(Error::UnknownRoutine(
+            routine.to_string(),
+        )));
+    };
+    let parent = if let Some(parent) = formals.iter().position(|other| other.name == formal) {
+        parent
+    } else {
+        return Err(std::boxed::Box::new(Error::UnknownFormal(
+            formal.to_string(),
+        )));
+    };
+    let end = graph.nodees.len();
+    graph.nodees.push(crate::graph::Node::End);
+    let one = graph
+        .typees
+        .iter()
+        .position(|r#type| matches!(r#type, crate::graph::TypeNode::One { .. }))
+        .unwrap();
+    let mut synthesizer = Synthesizer { graph, end, one };
+    let mut before = std::vec::Vec::new();
+    let mut after = std::vec::Vec::new();
+    for (index, other) in formals.iter().enumerate() {
+        if index == parent {
+            continue;
+        }
+        let actual = crate::graph::Expression::Call {
+            name: synthesizer.add_peer(&other.name, other.r#type)?,
+            before: vec![],
+            after: vec![],
+        };
+        if index < parent {
+            before.push(actual);
+        } else {
+            after.push(actual);
+        }
+    }
+    let start = synthesizer.add_session(formal, formals[parent].r#type, end)?;
+    Ok(synthesizer.add_node(crate::graph::Node::Assign {
+        name: formal.to_string(),
+        value: std::boxed::Box::new(crate::graph::Expression::Call {
+            name: routine.to_string(),
+            before,
+            after,
+        }),
+        next: start,
+    }))
+}
+
+pub fn get_names(
+    graph: &crate::graph::Graph,
+    names: &[std::string::String],
+    indexs: &[usize],
+) -> std::collections::HashMap<usize, crate::tree::TypeExpression> {
+    let mut expressions = std::collections::HashMap::new();
+    for (name, index) in names.iter().zip(indexs.iter()) {
+        let index = resolve(&graph.typees, *index);
+        expressions
+            .entry(index)
+            .or_insert_with(|| crate::tree::TypeExpression::Variable {
+                name: name.clone(),
+                is_dual: false,
+            });
+        expressions
+            .entry(crate::graph::get_dual(&graph.typees, index))
+            .or_insert_with(|| crate::tree::TypeExpression::Variable {
+                name: name.clone(),
+                is_dual: true,
+            });
+    }
+    expressions
+}
+
+pub fn describe(
+    typees: &[crate::graph::TypeNode],
+    names: &std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    index: usize,
+) -> crate::tree::TypeExpression {
+    let mut describer = Describer {
+        typees,
+        names,
+        visitings: std::vec::Vec::new(),
+        recursives: std::collections::HashSet::new(),
+    };
+    describer.describe_node(resolve(typees, index))
+}
+
+pub fn describe_named(
+    typees: &[crate::graph::TypeNode],
+    names: &std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    index: usize,
+) -> crate::tree::TypeExpression {
+    let mut describer = Describer {
+        typees,
+        names,
+        visitings: std::vec::Vec::new(),
+        recursives: std::collections::HashSet::new(),
+    };
+    *describer.describe_child(index)
+}
+
+pub fn resolve(typees: &[crate::graph::TypeNode], mut index: usize) -> usize {
+    while let crate::graph::TypeNode::Variable { node, is_dual, .. } = typees[index] {
+        index = if is_dual {
+            crate::graph::get_dual(typees, node)
+        } else {
+            node
+        };
+    }
+    index
+}
+
+struct Describer<'a> {
+    typees: &'a [crate::graph::TypeNode],
+    names: &'a std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    visitings: std::vec::Vec<usize>,
+    recursives: std::collections::HashSet<usize>,
+}
+
+impl<'a> Describer<'a> {
+    fn describe_child(&mut self, index: usize) -> std::boxed::Box<crate::tree::TypeExpression> {
+        let index = resolve(self.typees, index);
+        if let Some(name) = self.names.get(&index) {
+            return std::boxed::Box::new(name.clone());
+        }
+        if self.visitings.contains(&index) {
+            self.recursives.insert(index);
+            return std::boxed::Box::new(crate::tree::TypeExpression::Variable {
+                name: format!("t{}", index),
+                is_dual: false,
+            });
+        }
+        std::boxed::Box::new(self.describe_node(index))
+    }
+    fn describe_node(&mut self, index: usize) -> crate::tree::TypeExpression {
+        self.visitings.push(index);
+        let expression = match self.typees[index] {
+            crate::graph::TypeNode::Variable { .. } => unreachable!(),
+            crate::graph::TypeNode::Lollipop { value, next, .. } => {
+                crate::tree::TypeExpression::Lollipop {
+                    value: self.describe_child(value),
+                    next: self.describe_child(next),
+                }
+            }
+            crate::graph::TypeNode::Times { value, next, .. } => {
+                crate::tree::TypeExpression::Times {
+                    value: self.describe_child(value),
+                    next: self.describe_child(next),
+                }
+            }
+            crate::graph::TypeNode::With { accept, deny, .. } => {
+                crate::tree::TypeExpression::With {
+                    accept: self.describe_child(accept),
+                    deny: self.describe_child(deny),
+                }
+            }
+            crate::graph::TypeNode::Plus { accept, deny, .. } => {
+                crate::tree::TypeExpression::Plus {
+                    accept: self.describe_child(accept),
+                    deny: self.describe_child(deny),
+                }
+            }
+            crate::graph::TypeNode::Shared { value, .. } => crate::tree::TypeExpression::Shared {
+                value: self.describe_child(value),
+            },
+            crate::graph::TypeNode::Service { value, .. } => crate::tree::TypeExpression::Dual {
+                value: std::boxed::Box::new(crate::tree::TypeExpression::Shared {
+                    value: self.describe_child(crate::graph::get_dual(self.typees, value)),
+                }),
+            },
+            crate::graph::TypeNode::Release { value, .. } => crate::tree::TypeExpression::Release {
+                value: self.describe_child(value),
+            },
+            crate::graph::TypeNode::Detach { value, .. } => crate::tree::TypeExpression::Dual {
+                value: std::boxed::Box::new(crate::tree::TypeExpression::Release {
+                    value: self.describe_child(crate::graph::get_dual(self.typees, value)),
+                }),
+            },
+            crate::graph::TypeNode::One { .. } => crate::tree::TypeExpression::One,
+            crate::graph::TypeNode::Bottom { .. } => crate::tree::TypeExpression::Bottom,
+            crate::graph::TypeNode::Zero { .. } => crate::tree::TypeExpression::Zero,
+            crate::graph::TypeNode::Top { .. } => crate::tree::TypeExpression::Top,
+        };
+        self.visitings.pop();
+        if self.recursives.remove(&index) {
+            crate::tree::TypeExpression::Recursive {
+                name: format!("t{}", index),
+                value: std::boxed::Box::new(expression),
+            }
+        } else {
+            expression
+        }
+    }
+}
+
+struct Synthesizer<'a> {
+    graph: &'a mut crate::graph::Graph,
+    end: usize,
+    one: usize,
+}
+
+impl<'a> Synthesizer<'a> {
+    fn add_node(&mut self, node: crate::graph::Node) -> usize {
+        let index = self.graph.nodees.len();
+        self.graph.nodees.push(node);
+        index
+    }
+    fn get_dual(&self, index: usize) -> usize {
+        crate::graph::get_dual(&self.graph.typees, resolve(&self.graph.typees, index))
+    }
+    fn add_peer(
+        &mut self,
+        name: &str,
+        r#type: usize,
+    ) -> std::result::Result<std::string::String, std::boxed::Box<dyn std::error::Error>> {
+        let r#type = resolve(&self.graph.typees, r#type);
+        let routine = format!("{} {}", name, r#type);
+        if self.graph.routinees.contains_key(&routine) {
+            return Ok(routine);
+        }
+        let formals = vec![crate::graph::Formal {
+            name: name.to_string(),
+            r#type: self.get_dual(r#type),
+        }];
+        self.graph
+            .routinees
+            .insert(routine.clone(), crate::graph::Routine { start: 0, formals });
+        let start = self.add_session(name, r#type, self.end)?;
+        self.graph.routinees.get_mut(&routine).unwrap().start = start;
+        Ok(routine)
+    }
+    fn add_driver(
+        &mut self,
+        r#type: usize,
+    ) -> std::result::Result<std::string::String, std::boxed::Box<dyn std::error::Error>> {
+        let r#type = resolve(&self.graph.typees, r#type);
+        let routine = format!("{} {}", RECEIVED, r#type);
+        if self.graph.routinees.contains_key(&routine) {
+            return Ok(routine);
+        }
+        let formals = vec![
+            crate::graph::Formal {
+                name: RECEIVED.to_string(),
+                r#type: self.get_dual(r#type),
+            },
+            crate::graph::Formal {
+                name: PARENT.to_string(),
+                r#type: self.one,
+            },
+        ];
+        self.graph
+            .routinees
+            .insert(routine.clone(), crate::graph::Routine { start: 0, formals });
+        let last = self.add_node(crate::graph::Node::Close {
+            name: PARENT.to_string(),
+            next: self.end,
+        });
+        let start = self.add_session(RECEIVED, r#type, last)?;
+        self.graph.routinees.get_mut(&routine).unwrap().start = start;
+        Ok(routine)
+    }
+    fn add_session(
+        &mut self,
+        name: &str,
+        r#type: usize,
+        last: usize,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let mut states = std::collections::HashMap::new();
+        let next = self.add_state(name, r#type, last, &mut states)?;
+        Ok(self.add_node(crate::graph::Node::Report {
+            name: name.to_string(),
+            r#type: self.get_dual(r#type),
+            event: crate::graph::Event::Start,
+            next,
+        }))
+    }
+    fn add_state(
+        &mut self,
+        name: &str,
+        r#type: usize,
+        last: usize,
+        states: &mut std::collections::HashMap<usize, usize>,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let r#type = resolve(&self.graph.typees, r#type);
+        if let Some(index) = states.get(&r#type) {
+            return Ok(*index);
+        }
+        let index = self.add_node(crate::graph::Node::Branch { next: 0 });
+        states.insert(r#type, index);
+        let report =
+            |next: usize, r#type: usize, event: crate::graph::Event| crate::graph::Node::Report {
+                name: name.to_string(),
+                r#type,
+                event,
+                next,
+            };
+        let node = match self.graph.typees[r#type] {
+            crate::graph::TypeNode::Plus { accept, deny, .. } => {
+                let accepted = self.add_state(name, accept, last, states)?;
+                let accepted = self.add_node(crate::graph::Node::Accept {
+                    server: name.to_string(),
+                    next: accepted,
+                });
+                let denied = self.add_state(name, deny, last, states)?;
+                let denied = self.add_node(crate::graph::Node::Deny {
+                    server: name.to_string(),
+                    next: denied,
+                });
+                crate::graph::Node::Decide {
+                    name: name.to_string(),
+                    r#type: self.get_dual(r#type),
+                    accepted,
+                    denied,
+                }
+            }
+            crate::graph::TypeNode::With { accept, deny, .. } => {
+                let accepted = self.add_state(name, accept, last, states)?;
+                let accepted = self.add_node(report(
+                    accepted,
+                    self.get_dual(accept),
+                    crate::graph::Event::Accepted,
+                ));
+                let denied = self.add_state(name, deny, last, states)?;
+                let denied = self.add_node(report(
+                    denied,
+                    self.get_dual(deny),
+                    crate::graph::Event::Denied,
+                ));
+                crate::graph::Node::Offer {
+                    client: name.to_string(),
+                    accepted,
+                    denied,
+                }
+            }
+            crate::graph::TypeNode::Lollipop { value, next, .. } => {
+                let next_type = self.get_dual(next);
+                let next = self.add_state(name, next, last, states)?;
+                let next = self.add_node(crate::graph::Node::Wait {
+                    name: DONE.to_string(),
+                    next,
+                });
+                let driver = self.add_driver(self.get_dual(value))?;
+                let next = self.add_node(crate::graph::Node::Assign {
+                    name: DONE.to_string(),
+                    value: std::boxed::Box::new(crate::graph::Expression::Call {
+                        name: driver,
+                        before: vec![crate::graph::Expression::Variable {
+                            name: VALUE.to_string(),
+                        }],
+                        after: vec![],
+                    }),
+                    next,
+                });
+                let next = self.add_node(report(next, next_type, crate::graph::Event::Received));
+                crate::graph::Node::Receive {
+                    source: name.to_string(),
+                    variable: VALUE.to_string(),
+                    next,
+                }
+            }
+            crate::graph::TypeNode::Times { value, next, .. } => {
+                let next_type = self.get_dual(next);
+                let next = self.add_state(name, next, last, states)?;
+                let next = self.add_node(report(next, next_type, crate::graph::Event::Sent));
+                let next = self.add_node(crate::graph::Node::Send {
+                    destination: name.to_string(),
+                    variable: VALUE.to_string(),
+                    next,
+                });
+                let peer = self.add_peer(SENT, value)?;
+                crate::graph::Node::Assign {
+                    name: VALUE.to_string(),
+                    value: std::boxed::Box::new(crate::graph::Expression::Call {
+                        name: peer,
+                        before: vec![],
+                        after: vec![],
+                    }),
+                    next,
+                }
+            }
+            crate::graph::TypeNode::One { dual } => {
+                let next = self.add_node(crate::graph::Node::Wait {
+                    name: name.to_string(),
+                    next: last,
+                });
+                report(next, dual, crate::graph::Event::End)
+            }
+            crate::graph::TypeNode::Bottom { dual } => {
+                let next = self.add_node(crate::graph::Node::Close {
+                    name: name.to_string(),
+                    next: last,
+                });
+                report(next, dual, crate::graph::Event::End)
+            }
+            _ => {
+                return Err(std::boxed::Box::new(Error::Unsupported(
+                    crate::graph::get_name(&self.graph.typees, self.get_dual(r#type)),
+                )));
+            }
+        };
+        let next = self.add_node(node);
+        self.graph.nodees[index] = crate::graph::Node::Branch { next };
+        Ok(index)
+    }
+}
@@ -0,0 +1,91 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    InputEnded,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InputEnded => {
+                write!(f, "input ended before the session did")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn play(
+    mut graph: crate::graph::Graph,
+    names: &std::collections::HashMap<usize, crate::tree::TypeExpression>,
+    routine: &str,
+    formal: &str,
+    options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let start = crate::peer::synthesize(&mut graph, routine, formal)?;
+    let mut terminal = Terminal {
+        typees: &graph.typees,
+        names,
+    };
+    crate::interpret::play(&graph, start, options, &mut terminal)
+}
+
+struct Terminal<'a> {
+    typees: &'a [crate::graph::TypeNode],
+    names: &'a std::collections::HashMap<usize, crate::tree::TypeExpression>,
+}
+
+impl<'a> Terminal<'a> {
+    fn describe(&self, index: usize) -> crate::tree::TypeExpression {
+        crate::peer::describe(self.typees, self.names, index)
+    }
+}
+
+impl<'a> crate::interpret::Host for Terminal<'a> {
+    fn decide(
+        &mut self,
+        name: &str,
+        r#type: usize,
+    ) -> std::result::Result<bool, std::boxed::Box<dyn std::error::Error>> {
+        let (accept, deny) = match self.describe(r#type) {
+            crate::tree::TypeExpression::With { accept, deny } => (accept, deny),
+            _ => return Err(std::boxed::Box::new(crate::interpret::Error::TypeError)),
+        };
+        loop {
+            print!("{}: accept ({}) or deny ({})? ", name, accept, deny);
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut line = std::string::String::new();
+            if std::io::stdin().read_line(&mut line)? == 0 {
+                return Err(std::boxed::Box::new(Error::InputEnded));
+            }
+            match line.trim() {
+                "accept" | "a" => return Ok(true),
+                "deny" | "d" => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+    fn report(
+        &mut self,
+        name: &str,
+        r#type: usize,
+        event: crate::graph::Event,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        let r#type = self.describe(r#type);
+        match event {
+            crate::graph::Event::Start => println!("{}: {}", name, r#type),
+            crate::graph::Event::Accepted => {
+                println!("{}: routine accepts, now {}", name, r#type)
+            }
+            crate::graph::Event::Denied => println!("{}: routine denies, now {}", name, r#type),
+            crate::graph::Event::Sent => println!("{}: you send a channel, now {}", name, r#type),
+            crate::graph::Event::Received => {
+                println!("{}: routine sends a channel, now {}", name, r#type)
+            }
+            crate::graph::Event::End => println!("{}: session ends", name),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    DuplicateRole(std::string::String),
+    UnknownRole(std::string::String),
+    SelfInteraction(std::string::String),
+    UnknownRecursion(std::string::String),
+    DuplicateRecursion(std::string::String),
+    NotProjectable {
+        protocol: std::string::String,
+        left: std::string::String,
+        right: std::string::String,
+    },
+    DuplicateProtocol(std::string::String),
+    AmbiguousProjection(std::string::String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DuplicateRole(name) => {
+                write!(f, "duplicate role {:?}", name)?;
+            }
+            Error::UnknownRole(name) => {
+                write!(f, "unknown role {:?}", name)?;
+            }
+            Error::SelfInteraction(name) => {
+                write!(f, "role {:?} interacts with itself", name)?;
+            }
+            Error::UnknownRecursion(name) => {
+                write!(f, "unknown recursion {:?}", name)?;
+            }
+            Error::DuplicateRecursion(name) => {
+                write!(f, "duplicate recursion {:?}", name)?;
+            }
+            Error::NotProjectable {
+                protocol,
+                left,
+                right,
+            } => {
+                write!(
+                    f,
+                    "protocol {:?} is not projectable onto {:?} and {:?}",
+                    protocol, left, right
+                )?;
+            }
+            Error::DuplicateProtocol(name) => {
+                write!(f, "duplicate protocol {:?}", name)?;
+            }
+            Error::AmbiguousProjection(name) => {
+                write!(
+                    f,
+                    "projected type {:?} names more than one pair of roles",
+                    name
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn check_protocols(
+    protocols: &[crate::tree::Protocol],
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let mut names = std::collections::HashSet::new();
+    for (index, protocol) in protocols.iter().enumerate() {
+        if protocols[..index]
+            .iter()
+            .any(|other| other.name == protocol.name)
+        {
+            return Err(std::boxed::Box::new(Error::DuplicateProtocol(
+                protocol.name.clone(),
+            )));
+        }
+        for (index, role) in protocol.roles.iter().enumerate() {
+            if protocol.roles[..index].contains(role) {
+                return Err(std::boxed::Box::new(Error::DuplicateRole(role.clone())));
+            }
+        }
+        for (index, left) in protocol.roles.iter().enumerate() {
+            for right in protocol.roles[index + 1..].iter() {
+                for name in [
+                    get_name(&protocol.name, left, right),
+                    get_name(&protocol.name, right, left),
+                ] {
+                    if !names.insert(name.clone()) {
+                        return Err(std::boxed::Box::new(Error::AmbiguousProjection(name)));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn project_protocol(
+    protocol: &crate::tree::Protocol,
+) -> std::result::Result<std::vec::Vec<crate::tree::Type>, std::boxed::Box<dyn std::error::Error>> {
+    let mut recursions = std::vec::Vec::new();
+    check_interactions(protocol, &protocol.body, &mut recursions)?;
+    let mut typees = std::vec::Vec::new();
+    for (index, left) in protocol.roles.iter().enumerate() {
+        for right in protocol.roles[index + 1..].iter() {
+            let projection = Projection {
+                protocol,
+                left,
+                right,
+            };
+            let value = projection.project(&protocol.body, &[])?;
+            let name = get_name(&protocol.name, left, right);
+            typees.push(crate::tree::Type {
+                name: get_name(&protocol.name, right, left),
+                value: std::boxed::Box::new(crate::tree::TypeExpression::Variable {
+                    name: name.clone(),
+                    is_dual: true,
+                }),
+            });
+            typees.push(crate::tree::Type {
+                name,
+                value: std::boxed::Box::new(value),
+            });
+        }
+    }
+    Ok(typees)
+}
+
+fn get_name(protocol: &str, left: &str, right: &str) -> std::string::String {
+    format!("{}_{}_{}", protocol, left, right)
+}
+
+fn check_interactions(
+    protocol: &crate::tree::Protocol,
+    interactions: &[crate::tree::Interaction],
+    recursions: &mut std::vec::Vec<std::string::String>,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    for interaction in interactions {
+        match interaction {
+            crate::tree::Interaction::Message {
+                sender, receiver, ..
+            } => check_roles(protocol, sender, receiver)?,
+            crate::tree::Interaction::Choice {
+                sender,
+                receiver,
+                accepted,
+                denied,
+            } => {
+                check_roles(protocol, sender, receiver)?;
+                check_interactions(protocol, accepted, recursions)?;
+                check_interactions(protocol, denied, recursions)?;
+            }
+            crate::tree::Interaction::Recursive { name, body } => {
+                if recursions.contains(name) {
+                    return Err(std::boxed::Box::new(Error::DuplicateRecursion(
+                        name.clone(),
+                    )));
+                }
+                recursions.push(name.clone());
+                check_interactions(protocol, body, recursions)?;
+                recursions.pop();
+            }
+            crate::tree::Interaction::Continue { name } => {
+                if !recursions.contains(name) {
+                    return Err(std::boxed::Box::new(Error::UnknownRecursion(name.clone())));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_roles(
+    protocol: &crate::tree::Protocol,
+    sender: &std::string::String,
+    receiver: &std::string::String,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    for role in [sender, receiver] {
+        if !protocol.roles.contains(role) {
+            return Err(std::boxed::Box::new(Error::UnknownRole(role.clone())));
+        }
+    }
+    if sender == receiver {
+        return Err(std::boxed::Box::new(Error::SelfInteraction(sender.clone())));
+    }
+    Ok(())
+}
+
+struct Projection<'a> {
+    protocol: &'a crate::tree::Protocol,
+    left: &'a std::string::String,
+    right: &'a std::string::String,
+}
+
+impl<'a> Projection<'a> {
+    fn is_involved(&self, interactions: &[crate::tree::Interaction]) -> bool {
+        interactions.iter().any(|interaction| match interaction {
+            crate::tree::Interaction::Message {
+                sender, receiver, ..
+            } => {
+                (sender == self.left && receiver == self.right)
+                    || (sender == self.right && receiver == self.left)
+            }
+            crate::tree::Interaction::Choice {
+                sender,
+                receiver,
+                accepted,
+                denied,
+            } => {
+                (sender == self.left && receiver == self.right)
+                    || (sender == self.right && receiver == self.left)
+                    || self.is_involved(accepted)
+                    || self.is_involved(denied)
+            }
+            crate::tree::Interaction::Recursive { body, .. } => self.is_involved(body),
+            crate::tree::Interaction::Continue { .. } => false,
+        })
+    }
+    fn project(
+        &self,
+        interactions: &[crate::tree::Interaction],
+        continuation: &[&[crate::tree::Interaction]],
+    ) -> std::result::Result<crate::tree::TypeExpression, std::boxed::Box<dyn std::error::Error>>
+    {
+        let (interaction, rest) = if let Some((interaction, rest)) = interactions.split_first() {
+            (interaction, rest)
+        } else if let Some((interactions, continuation)) = continuation.split_last() {
+            return self.project(interactions, continuation);
+        } else {
+            return Ok(crate::tree::TypeExpression::One);
+        };
+        match interaction {
+            crate::tree::Interaction::Message {
+                sender,
+                receiver,
+                r#type,
+            } => {
+                if sender == self.left && receiver == self.right {
+                    Ok(crate::tree::TypeExpression::Lollipop {
+                        value: std::boxed::Box::new(r#type.clone()),
+                        next: std::boxed::Box::new(self.project(rest, continuation)?),
+                    })
+                } else if sender == self.right && receiver == self.left {
+                    Ok(crate::tree::TypeExpression::Times {
+                        value: std::boxed::Box::new(r#type.clone()),
+                        next: std::boxed::Box::new(self.project(rest, continuation)?),
+                    })
+                } else {
+                    self.project(rest, continuation)
+                }
+            }
+            crate::tree::Interaction::Choice {
+                sender,
+                receiver,
+                accepted,
+                denied,
+            } => {
+                let mut next_continuation = continuation.to_vec();
+                next_continuation.push(rest);
+                let accept = self.project(accepted, &next_continuation)?;
+                let deny = self.project(denied, &next_continuation)?;
+                if sender == self.left && receiver == self.right {
+                    Ok(crate::tree::TypeExpression::With {
+                        accept: std::boxed::Box::new(accept),
+                        deny: std::boxed::Box::new(deny),
+                    })
+                } else if sender == self.right && receiver == self.left {
+                    Ok(crate::tree::TypeExpression::Plus {
+                        accept: std::boxed::Box::new(accept),
+                        deny: std::boxed::Box::new(deny),
+                    })
+                } else if accept == deny {
+                    Ok(accept)
+                } else {
+                    Err(std::boxed::Box::new(Error::NotProjectable {
+                        protocol: self.protocol.name.clone(),
+                        left: self.left.clone(),
+                        right: self.right.clone(),
+                    }))
+                }
+            }
+            crate::tree::Interaction::Recursive { name, body } => {
+                if !self.is_involved(body) {
+                    return self.project(rest, continuation);
+                }
+                let mut next_continuation = continuation.to_vec();
+                next_continuation.push(rest);
+                match self.project(body, &next_continuation)? {
+                    crate::tree::TypeExpression::Variable {
+                        name: variable,
+                        is_dual: false,
+                    } => {
+                        if variable == *name {
+                            Ok(crate::tree::TypeExpression::One)
+                        } else {
+                            Ok(crate::tree::TypeExpression::Variable {
+                                name: variable,
+                                is_dual: false,
+                            })
+                        }
+                    }
+                    value => Ok(crate::tree::TypeExpression::Recursive {
+                        name: name.clone(),
+                        value: std::boxed::Box::new(value),
+                    }),
+                }
+            }
+            crate::tree::Interaction::Continue { name } => {
+                Ok(crate::tree::TypeExpression::Variable {
+                    name: name.clone(),
+                    is_dual: false,
+                })
+            }
+        }
+    }
+}
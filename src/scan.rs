@@ -1,8 +1,14 @@
+const MAX_INTEGER: u32 = 65535;
+
 lazy_static::lazy_static! {
     static ref KEYWORDS: std::collections::HashMap<&'static str, crate::token::TokenValue> = {
         let mut map = std::collections::HashMap::new();
         map.insert("type", crate::token::TokenValue::Type);
+        map.insert("rec", crate::token::TokenValue::Rec);
         map.insert("routine", crate::token::TokenValue::Routine);
+        map.insert("affine", crate::token::TokenValue::Affine);
+        map.insert("protocol", crate::token::TokenValue::Protocol);
+        map.insert("choice", crate::token::TokenValue::Choice);
         map.insert("receive", crate::token::TokenValue::Receive);
         map.insert("send", crate::token::TokenValue::Send);
         map.insert("offer", crate::token::TokenValue::Offer);
@@ -10,7 +16,31 @@ lazy_static::lazy_static! {
         map.insert("accept", crate::token::TokenValue::Accept);
         map.insert("deny", crate::token::TokenValue::Deny);
         map.insert("close", crate::token::TokenValue::Close);
+        map.insert("wait", crate::token::TokenValue::Wait);
+        map.insert("absurd", crate::token::TokenValue::Absurd);
+        map.insert("bottom", crate::token::TokenValue::Bottom);
+        map.insert("top", crate::token::TokenValue::Top);
         map.insert("connect", crate::token::TokenValue::Connect);
+        map.insert("loop", crate::token::TokenValue::Loop);
+        map.insert("while", crate::token::TokenValue::While);
+        map.insert("break", crate::token::TokenValue::Break);
+        map.insert("continue", crate::token::TokenValue::Continue);
+        map.insert("data", crate::token::TokenValue::Data);
+        map.insert("import", crate::token::TokenValue::Import);
+        map.insert("use", crate::token::TokenValue::Use);
+        map.insert("pub", crate::token::TokenValue::Pub);
+        map.insert("fork", crate::token::TokenValue::Fork);
+        map.insert("match", crate::token::TokenValue::Match);
+        map.insert("select", crate::token::TokenValue::Select);
+        map.insert("shared", crate::token::TokenValue::Shared);
+        map.insert("acquire", crate::token::TokenValue::Acquire);
+        map.insert("release", crate::token::TokenValue::Release);
+        map.insert("share", crate::token::TokenValue::Share);
+        map.insert("try", crate::token::TokenValue::Try);
+        map.insert("catch", crate::token::TokenValue::Catch);
+        map.insert("raise", crate::token::TokenValue::Raise);
+        map.insert("test", crate::token::TokenValue::Test);
+        map.insert("assert", crate::token::TokenValue::Assert);
         map
     };
 }
@@ -23,6 +53,10 @@ pub enum Error {
         character: char,
         location: crate::location::Location,
     },
+    IntegerTooLarge {
+        word: std::string::String,
+        location: crate::location::Location,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -37,6 +71,13 @@ impl std::fmt::Display for Error {
             } => {
                 write!(f, "unexpected character {:?} at {}", character, location)?;
             }
+            Error::IntegerTooLarge { word, location } => {
+                write!(
+                    f,
+                    "integer {} too large at {}, the maximum is {}",
+                    word, location, MAX_INTEGER
+                )?;
+            }
         }
         Ok(())
     }
@@ -54,7 +95,21 @@ pub fn scan(
     >,
     std::boxed::Box<dyn std::error::Error>,
 > {
-    Ok(Scanner::from_characters(characters)?)
+    Scanner::from_characters(characters, crate::location::Location::from_indexs(1, 1))
+}
+
+pub fn scan_file(
+    characters: impl std::iter::Iterator<
+        Item = std::result::Result<char, std::boxed::Box<dyn std::error::Error>>,
+    >,
+    file: std::rc::Rc<str>,
+) -> std::result::Result<
+    impl std::iter::Iterator<
+        Item = std::result::Result<crate::token::Token, Box<dyn std::error::Error>>,
+    >,
+    std::boxed::Box<dyn std::error::Error>,
+> {
+    Scanner::from_characters(characters, crate::location::Location::from_file(file))
 }
 
 pub struct Scanner<I> {
@@ -79,12 +134,13 @@ impl<
 {
     fn from_characters(
         mut characters: I,
+        location: crate::location::Location,
     ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error>> {
         let lookahead = characters.next().transpose()?;
         Ok(Scanner {
             characters,
             lookahead,
-            location: crate::location::Location::from_indexs(1, 1),
+            location,
         })
     }
     fn peek(&self) -> std::option::Option<char> {
@@ -136,7 +192,7 @@ impl<
         } else {
             return Ok(None);
         };
-        let location = self.location;
+        let location = self.location.clone();
         let value = match character {
             '(' => {
                 self.advance()?;
@@ -158,9 +214,36 @@ impl<
                 self.advance()?;
                 crate::token::TokenValue::Comma
             }
+            ';' => {
+                self.advance()?;
+                crate::token::TokenValue::Semicolon
+            }
             ':' => {
                 self.advance()?;
-                crate::token::TokenValue::Colon
+                if self.peek() == Some(':') {
+                    self.advance()?;
+                    crate::token::TokenValue::DoubleColon
+                } else {
+                    crate::token::TokenValue::Colon
+                }
+            }
+            '"' => {
+                self.advance()?;
+                let mut text = std::string::String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => break,
+                        Some(character) => text.push(character),
+                        None => return Err(std::boxed::Box::new(Error::UnexpectedEnd)),
+                    }
+                    self.advance()?;
+                }
+                self.advance()?;
+                crate::token::TokenValue::String(text)
+            }
+            '.' => {
+                self.advance()?;
+                crate::token::TokenValue::Dot
             }
             '=' => {
                 self.advance()?;
@@ -176,6 +259,9 @@ impl<
                 if character == 'o' {
                     self.advance()?;
                     crate::token::TokenValue::Lollipop
+                } else if character == '>' {
+                    self.advance()?;
+                    crate::token::TokenValue::Arrow
                 } else {
                     return Err(std::boxed::Box::new(Error::UnexpectedCharacter {
                         character,
@@ -183,6 +269,27 @@ impl<
                     }));
                 }
             }
+            '<' => {
+                self.advance()?;
+                let character = if let Some(character) = self.peek() {
+                    character
+                } else {
+                    return Err(std::boxed::Box::new(Error::UnexpectedEnd));
+                };
+                if character == '-' {
+                    self.advance()?;
+                    crate::token::TokenValue::LeftArrow
+                } else {
+                    return Err(std::boxed::Box::new(Error::UnexpectedCharacter {
+                        character,
+                        location,
+                    }));
+                }
+            }
+            '|' => {
+                self.advance()?;
+                crate::token::TokenValue::Bar
+            }
             '*' => {
                 self.advance()?;
                 crate::token::TokenValue::Times
@@ -195,11 +302,35 @@ impl<
                 self.advance()?;
                 crate::token::TokenValue::Plus
             }
-            '1' => {
-                self.advance()?;
-                crate::token::TokenValue::One
-            }
             character => {
+                if character.is_ascii_digit() {
+                    let word = self.read_word()?;
+                    return if let Ok(value) = word.parse().map_err(|_| ()).and_then(|value| {
+                        if value <= MAX_INTEGER {
+                            Ok(value)
+                        } else {
+                            Err(())
+                        }
+                    }) {
+                        Ok(Some(crate::token::Token {
+                            value: crate::token::TokenValue::Integer(value),
+                            location,
+                        }))
+                    } else if word.chars().all(|character| character.is_ascii_digit()) {
+                        Err(std::boxed::Box::new(Error::IntegerTooLarge {
+                            word,
+                            location,
+                        }))
+                    } else {
+                        Err(std::boxed::Box::new(Error::UnexpectedCharacter {
+                            character: word
+                                .chars()
+                                .find(|character| !character.is_ascii_digit())
+                                .unwrap(),
+                            location,
+                        }))
+                    };
+                }
                 if character.is_ascii_alphabetic() || character == '_' {
                     let word = self.read_word()?;
                     if let Some(value) = KEYWORDS.get(&*word) {
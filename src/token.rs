@@ -5,15 +5,24 @@ pub enum TokenValue {
     LeftBrace,
     RightBrace,
     Comma,
+    Semicolon,
     Colon,
+    DoubleColon,
+    Dot,
     Equals,
     Lollipop,
+    Arrow,
     Times,
     With,
     Plus,
-    One,
+    Bottom,
+    Top,
     Type,
+    Rec,
     Routine,
+    Affine,
+    Protocol,
+    Choice,
     Receive,
     Send,
     Offer,
@@ -21,8 +30,34 @@ pub enum TokenValue {
     Accept,
     Deny,
     Close,
+    Wait,
+    Absurd,
     Connect,
+    Bar,
+    Loop,
+    While,
+    Break,
+    Continue,
+    LeftArrow,
+    Data,
+    Import,
+    Use,
+    Pub,
+    Fork,
+    Match,
+    Select,
+    Shared,
+    Acquire,
+    Release,
+    Share,
+    Try,
+    Catch,
+    Raise,
+    Test,
+    Assert,
     Identifier(std::string::String),
+    Integer(u32),
+    String(std::string::String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -0,0 +1,91 @@
+pub fn to_json(event: &crate::interpret::Event) -> std::string::String {
+    let mut fields = vec![
+        ("step", event.step.to_string()),
+        ("routine", to_json_option(event.routine.as_deref())),
+        ("node", event.node.to_string()),
+        (
+            "location",
+            match &event.location {
+                Some(location) => format!(
+                    "{{\"file\":{},\"line\":{},\"column\":{}}}",
+                    to_json_option(location.file.as_deref()),
+                    location.line,
+                    location.column
+                ),
+                None => "null".to_string(),
+            },
+        ),
+    ];
+    let (name, channel, peer, accept) = match &event.action {
+        crate::interpret::Action::Entered { routine } => {
+            fields.push(("action", to_json_string("entered")));
+            fields.push(("entered", to_json_string(routine)));
+            return to_json_object(&fields);
+        }
+        crate::interpret::Action::Executed => {
+            fields.push(("action", to_json_string("executed")));
+            return to_json_object(&fields);
+        }
+        crate::interpret::Action::Flipped { channel, routine } => {
+            fields.push(("action", to_json_string("flipped")));
+            fields.push(("channel", to_json_string(channel)));
+            fields.push(("to", to_json_option(routine.as_deref())));
+            return to_json_object(&fields);
+        }
+        crate::interpret::Action::Sent { channel, peer } => ("sent", channel, peer, None),
+        crate::interpret::Action::Received { channel, peer } => ("received", channel, peer, None),
+        crate::interpret::Action::Chose {
+            channel,
+            peer,
+            accept,
+        } => ("chose", channel, peer, Some(*accept)),
+        crate::interpret::Action::Offered {
+            channel,
+            peer,
+            accept,
+        } => ("offered", channel, peer, Some(*accept)),
+        crate::interpret::Action::Closed { channel, peer } => ("closed", channel, peer, None),
+        crate::interpret::Action::Waited { channel, peer } => ("waited", channel, peer, None),
+    };
+    fields.push(("action", to_json_string(name)));
+    fields.push(("channel", to_json_string(channel)));
+    fields.push(("peer", to_json_option(peer.as_deref())));
+    if let Some(accept) = accept {
+        fields.push(("accept", accept.to_string()));
+    }
+    to_json_object(&fields)
+}
+
+fn to_json_object(fields: &[(&str, std::string::String)]) -> std::string::String {
+    let fields: std::vec::Vec<std::string::String> = fields
+        .iter()
+        .map(|(name, value)| format!("{}:{}", to_json_string(name), value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn to_json_option(text: std::option::Option<&str>) -> std::string::String {
+    match text {
+        Some(text) => to_json_string(text),
+        None => "null".to_string(),
+    }
+}
+
+fn to_json_string(text: &str) -> std::string::String {
+    let mut json = std::string::String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                json.push_str(&format!("\\u{:04x}", character as u32));
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
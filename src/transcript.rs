@@ -0,0 +1,423 @@
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Error {
+    UnknownRoutine(std::string::String),
+    UnknownFormal(std::string::String),
+    MissingTranscript(std::string::String),
+    Mismatch {
+        step: std::string::String,
+        r#type: &'static str,
+        location: crate::location::Location,
+    },
+    AfterEnd {
+        step: std::string::String,
+        location: crate::location::Location,
+    },
+    Incomplete {
+        r#type: &'static str,
+        location: std::option::Option<crate::location::Location>,
+    },
+    Deviation {
+        formal: std::string::String,
+        diff: std::string::String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownRoutine(name) => {
+                write!(f, "unknown routine {:?}", name)?;
+            }
+            Error::UnknownFormal(name) => {
+                write!(f, "unknown formal {:?}", name)?;
+            }
+            Error::MissingTranscript(name) => {
+                write!(f, "no transcript for formal {:?}", name)?;
+            }
+            Error::Mismatch {
+                step,
+                r#type,
+                location,
+            } => {
+                write!(
+                    f,
+                    "step {:?} at {} does not fit the routine's {} type",
+                    step, location, r#type
+                )?;
+            }
+            Error::AfterEnd { step, location } => {
+                write!(
+                    f,
+                    "step {:?} at {} comes after the session has ended",
+                    step, location
+                )?;
+            }
+            Error::Incomplete { r#type, location } => {
+                if let Some(location) = location {
+                    write!(f, "transcript stops at {}", location)?;
+                } else {
+                    write!(f, "transcript is empty")?;
+                }
+                write!(f, " while the routine's {} type continues", r#type)?;
+            }
+            Error::Deviation { formal, diff } => {
+                write!(
+                    f,
+                    "routine deviates from the transcript for {:?}:\n{}",
+                    formal, diff
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct Transcript {
+    pub formal: std::string::String,
+    pub steps: std::vec::Vec<crate::tree::Step>,
+}
+
+pub fn read(
+    path: &std::path::Path,
+) -> std::result::Result<std::vec::Vec<crate::tree::Step>, std::boxed::Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path).map_err(std::boxed::Box::new)?;
+    let reader = std::io::BufReader::new(file);
+    let characters =
+        utf8_decode::UnsafeDecoder::new(std::io::Read::bytes(reader)).map(|character| {
+            character.map_err(|error| {
+                std::boxed::Box::new(error) as std::boxed::Box<dyn std::error::Error>
+            })
+        });
+    let scanner = crate::scan::scan_file(characters, path.display().to_string().into())?;
+    crate::parse::parse_transcript(scanner)
+}
+
+pub fn replay(
+    graph: &crate::graph::Graph,
+    routine: &str,
+    transcripts: &[Transcript],
+    options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    for driver in transcripts.iter() {
+        replay_driver(
+            graph.clone(),
+            routine,
+            transcripts,
+            &driver.formal,
+            options.clone(),
+        )?;
+    }
+    Ok(())
+}
+
+fn replay_driver(
+    mut graph: crate::graph::Graph,
+    routine: &str,
+    transcripts: &[Transcript],
+    driver: &str,
+    options: crate::interpret::Options,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let formals = if let Some(routine) = graph.routinees.get(routine) {
+        routine.formals.clone()
+    } else {
+        return Err(std::boxed::Box::new(Error::UnknownRoutine(
+            routine.to_string(),
+        )));
+    };
+    for transcript in transcripts.iter() {
+        if !formals
+            .iter()
+            .any(|formal| formal.name == transcript.formal)
+        {
+            return Err(std::boxed::Box::new(Error::UnknownFormal(
+                transcript.formal.clone(),
+            )));
+        }
+    }
+    let end = graph.nodees.len();
+    graph.nodees.push(crate::graph::Node::End);
+    let mut lowering = Lowering {
+        graph: &mut graph,
+        end,
+        names: 0,
+    };
+    let mut parent = None;
+    let mut before = std::vec::Vec::new();
+    let mut after = std::vec::Vec::new();
+    for (index, formal) in formals.iter().enumerate() {
+        let transcript = if let Some(transcript) = transcripts
+            .iter()
+            .find(|transcript| transcript.formal == formal.name)
+        {
+            transcript
+        } else {
+            return Err(std::boxed::Box::new(Error::MissingTranscript(
+                formal.name.clone(),
+            )));
+        };
+        if transcript.formal == driver {
+            parent = Some(index);
+            continue;
+        }
+        let fork = lowering.lower_fork(formal.r#type, &transcript.steps, None)?;
+        let actual = crate::graph::Expression::Fork {
+            fork,
+            names: vec![],
+        };
+        if parent.is_some() {
+            after.push(actual);
+        } else {
+            before.push(actual);
+        }
+    }
+    let parent = parent.unwrap();
+    let name = lowering.add_name();
+    let start = lowering.lower(
+        &name,
+        formals[parent].r#type,
+        &transcripts
+            .iter()
+            .find(|transcript| transcript.formal == driver)
+            .unwrap()
+            .steps,
+        end,
+        None,
+    )?;
+    let start = lowering.add_node(crate::graph::Node::Assign {
+        name,
+        value: std::boxed::Box::new(crate::graph::Expression::Call {
+            name: routine.to_string(),
+            before,
+            after,
+        }),
+        next: start,
+    });
+    let test = crate::graph::Test {
+        name: routine.to_string(),
+        start,
+    };
+    let error = match crate::interpret::test(&graph, &test, options) {
+        Ok(()) => return Ok(()),
+        Err(error) => error,
+    };
+    if let Some(crate::interpret::Error::Failed(location)) =
+        error.downcast_ref::<crate::interpret::Error>()
+    {
+        for transcript in transcripts.iter() {
+            let mut lines = std::vec::Vec::new();
+            if render(&transcript.steps, location, 0, &mut lines) {
+                return Err(std::boxed::Box::new(Error::Deviation {
+                    formal: transcript.formal.clone(),
+                    diff: lines.join("\n"),
+                }));
+            }
+        }
+    }
+    Err(error)
+}
+
+fn render(
+    steps: &[crate::tree::Step],
+    location: &crate::location::Location,
+    depth: usize,
+    lines: &mut std::vec::Vec<std::string::String>,
+) -> bool {
+    let indent = "  ".repeat(depth);
+    for step in steps {
+        if step.location == *location {
+            let actual = match step.action {
+                crate::tree::Action::ExpectAccept => "expect deny",
+                crate::tree::Action::ExpectDeny => "expect accept",
+                _ => return false,
+            };
+            lines.push(format!("- {}{}", indent, step));
+            lines.push(format!("+ {}{}", indent, actual));
+            return true;
+        }
+        let (keyword, body) = match &step.action {
+            crate::tree::Action::Send { body } => ("send", body),
+            crate::tree::Action::Receive { body } => ("receive", body),
+            _ => {
+                lines.push(format!("  {}{}", indent, step));
+                continue;
+            }
+        };
+        let mark = lines.len();
+        lines.push(format!("  {}{} {{", indent, keyword));
+        if render(body, location, depth + 1, lines) {
+            return true;
+        }
+        lines.truncate(mark);
+        lines.push(format!("  {}{}", indent, step));
+    }
+    false
+}
+
+struct Lowering<'a> {
+    graph: &'a mut crate::graph::Graph,
+    end: usize,
+    names: usize,
+}
+
+impl<'a> Lowering<'a> {
+    fn add_node(&mut self, node: crate::graph::Node) -> usize {
+        let index = self.graph.nodees.len();
+        self.graph.nodees.push(node);
+        index
+    }
+    fn add_name(&mut self) -> std::string::String {
+        self.names += 1;
+        self.names.to_string()
+    }
+    fn resolve(&self, mut index: usize) -> usize {
+        while let crate::graph::TypeNode::Variable { node, is_dual, .. } = self.graph.typees[index]
+        {
+            index = if is_dual {
+                crate::graph::get_dual(&self.graph.typees, node)
+            } else {
+                node
+            };
+        }
+        index
+    }
+    fn lower_fork(
+        &mut self,
+        r#type: usize,
+        steps: &[crate::tree::Step],
+        location: std::option::Option<&crate::location::Location>,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let name = self.add_name();
+        let start = self.lower(&name, r#type, steps, self.end, location)?;
+        self.graph.forkes.push(crate::graph::Routine {
+            start,
+            formals: vec![crate::graph::Formal {
+                name,
+                r#type: crate::graph::get_dual(&self.graph.typees, r#type),
+            }],
+        });
+        Ok(self.graph.forkes.len() - 1)
+    }
+    fn lower(
+        &mut self,
+        name: &str,
+        r#type: usize,
+        steps: &[crate::tree::Step],
+        last: usize,
+        location: std::option::Option<&crate::location::Location>,
+    ) -> std::result::Result<usize, std::boxed::Box<dyn std::error::Error>> {
+        let r#type = self.resolve(r#type);
+        let (step, rest) = if let Some((step, rest)) = steps.split_first() {
+            (step, rest)
+        } else {
+            return Err(std::boxed::Box::new(Error::Incomplete {
+                r#type: crate::graph::get_name(&self.graph.typees, r#type),
+                location: location.cloned(),
+            }));
+        };
+        let location = Some(&step.location);
+        let node = match (&step.action, self.graph.typees[r#type]) {
+            (crate::tree::Action::Accept, crate::graph::TypeNode::Plus { accept, .. }) => {
+                let next = self.lower(name, accept, rest, last, location)?;
+                crate::graph::Node::Accept {
+                    server: name.to_string(),
+                    next,
+                }
+            }
+            (crate::tree::Action::Deny, crate::graph::TypeNode::Plus { deny, .. }) => {
+                let next = self.lower(name, deny, rest, last, location)?;
+                crate::graph::Node::Deny {
+                    server: name.to_string(),
+                    next,
+                }
+            }
+            (crate::tree::Action::ExpectAccept, crate::graph::TypeNode::With { accept, .. }) => {
+                let accepted = self.lower(name, accept, rest, last, location)?;
+                let denied = self.add_node(crate::graph::Node::Fail {
+                    location: step.location.clone(),
+                });
+                crate::graph::Node::Offer {
+                    client: name.to_string(),
+                    accepted,
+                    denied,
+                }
+            }
+            (crate::tree::Action::ExpectDeny, crate::graph::TypeNode::With { deny, .. }) => {
+                let accepted = self.add_node(crate::graph::Node::Fail {
+                    location: step.location.clone(),
+                });
+                let denied = self.lower(name, deny, rest, last, location)?;
+                crate::graph::Node::Offer {
+                    client: name.to_string(),
+                    accepted,
+                    denied,
+                }
+            }
+            (
+                crate::tree::Action::Send { body },
+                crate::graph::TypeNode::Times { value, next, .. },
+            ) => {
+                let fork = self.lower_fork(value, body, location)?;
+                let variable = self.add_name();
+                let next = self.lower(name, next, rest, last, location)?;
+                let next = self.add_node(crate::graph::Node::Send {
+                    destination: name.to_string(),
+                    variable: variable.clone(),
+                    next,
+                });
+                crate::graph::Node::Assign {
+                    name: variable,
+                    value: std::boxed::Box::new(crate::graph::Expression::Fork {
+                        fork,
+                        names: vec![],
+                    }),
+                    next,
+                }
+            }
+            (
+                crate::tree::Action::Receive { body },
+                crate::graph::TypeNode::Lollipop { value, next, .. },
+            ) => {
+                let variable = self.add_name();
+                let next = self.lower(name, next, rest, last, location)?;
+                let value = crate::graph::get_dual(&self.graph.typees, value);
+                let next = self.lower(&variable, value, body, next, location)?;
+                crate::graph::Node::Receive {
+                    source: name.to_string(),
+                    variable,
+                    next,
+                }
+            }
+            (crate::tree::Action::Close, crate::graph::TypeNode::Bottom { .. })
+            | (crate::tree::Action::Wait, crate::graph::TypeNode::One { .. }) => {
+                if let Some(step) = rest.first() {
+                    return Err(std::boxed::Box::new(Error::AfterEnd {
+                        step: step.to_string(),
+                        location: step.location.clone(),
+                    }));
+                }
+                if step.action == crate::tree::Action::Close {
+                    crate::graph::Node::Close {
+                        name: name.to_string(),
+                        next: last,
+                    }
+                } else {
+                    crate::graph::Node::Wait {
+                        name: name.to_string(),
+                        next: last,
+                    }
+                }
+            }
+            _ => {
+                return Err(std::boxed::Box::new(Error::Mismatch {
+                    step: step.to_string(),
+                    r#type: crate::graph::get_name(&self.graph.typees, r#type),
+                    location: step.location.clone(),
+                }));
+            }
+        };
+        Ok(self.add_node(node))
+    }
+}
@@ -2,6 +2,73 @@
 pub struct Tree {
     pub typees: std::vec::Vec<Type>,
     pub routinees: std::vec::Vec<Routine>,
+    pub protocoles: std::vec::Vec<Protocol>,
+    pub dataes: std::vec::Vec<Data>,
+    pub importes: std::vec::Vec<Import>,
+    pub publics: std::collections::BTreeSet<std::string::String>,
+    pub preludees: std::collections::BTreeSet<std::string::String>,
+    pub testes: std::vec::Vec<Test>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Test {
+    pub name: std::string::String,
+    pub body: std::vec::Vec<Statement>,
+    pub location: crate::location::Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Import {
+    File {
+        path: std::string::String,
+        location: crate::location::Location,
+    },
+    Use {
+        module: std::string::String,
+        name: std::string::String,
+        location: crate::location::Location,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Data {
+    pub name: std::string::String,
+    pub constructors: std::vec::Vec<Constructor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Constructor {
+    pub name: std::string::String,
+    pub fields: std::vec::Vec<TypeExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Protocol {
+    pub name: std::string::String,
+    pub roles: std::vec::Vec<std::string::String>,
+    pub body: std::vec::Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Interaction {
+    Message {
+        sender: std::string::String,
+        receiver: std::string::String,
+        r#type: TypeExpression,
+    },
+    Choice {
+        sender: std::string::String,
+        receiver: std::string::String,
+        accepted: std::vec::Vec<Interaction>,
+        denied: std::vec::Vec<Interaction>,
+    },
+    Recursive {
+        name: std::string::String,
+        body: std::vec::Vec<Interaction>,
+    },
+    Continue {
+        name: std::string::String,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -32,7 +99,23 @@ pub enum TypeExpression {
         accept: std::boxed::Box<TypeExpression>,
         deny: std::boxed::Box<TypeExpression>,
     },
+    Dual {
+        value: std::boxed::Box<TypeExpression>,
+    },
+    Recursive {
+        name: std::string::String,
+        value: std::boxed::Box<TypeExpression>,
+    },
+    Shared {
+        value: std::boxed::Box<TypeExpression>,
+    },
+    Release {
+        value: std::boxed::Box<TypeExpression>,
+    },
     One,
+    Bottom,
+    Zero,
+    Top,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -40,6 +123,7 @@ pub struct Routine {
     pub name: std::string::String,
     pub formals: std::vec::Vec<Formal>,
     pub body: std::vec::Vec<Statement>,
+    pub is_affine: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -56,6 +140,9 @@ pub enum Statement {
     Label {
         name: std::string::String,
     },
+    Mark {
+        location: crate::location::Location,
+    },
     Assign {
         name: std::string::String,
         value: std::boxed::Box<Expression>,
@@ -86,10 +173,77 @@ pub enum Statement {
     Close {
         name: std::string::String,
     },
+    Wait {
+        name: std::string::String,
+    },
+    Absurd {
+        name: std::string::String,
+    },
     Connect {
         left: std::string::String,
         right: std::string::String,
     },
+    Acquire {
+        name: std::string::String,
+    },
+    Release {
+        name: std::string::String,
+    },
+    Share {
+        name: std::string::String,
+        copy: std::string::String,
+    },
+    Select {
+        arms: std::vec::Vec<Arm>,
+    },
+    Loop {
+        body: std::vec::Vec<Statement>,
+    },
+    While {
+        client: std::string::String,
+        body: std::vec::Vec<Statement>,
+    },
+    Break,
+    Continue,
+    Construct {
+        name: std::string::String,
+        constructor: std::string::String,
+        fields: std::vec::Vec<std::string::String>,
+    },
+    Match {
+        name: std::string::String,
+        cases: std::vec::Vec<Case>,
+    },
+    Try {
+        body: std::vec::Vec<Statement>,
+        handler: std::vec::Vec<Statement>,
+    },
+    Raise,
+    Assert {
+        value: std::boxed::Box<Expression>,
+        location: crate::location::Location,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Case {
+    pub constructor: std::string::String,
+    pub variables: std::vec::Vec<std::string::String>,
+    pub body: std::vec::Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Arm {
+    Offer {
+        client: std::string::String,
+        accepted: std::vec::Vec<Statement>,
+        denied: std::vec::Vec<Statement>,
+    },
+    Receive {
+        source: std::string::String,
+        variable: std::string::String,
+        body: std::vec::Vec<Statement>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -102,4 +256,146 @@ pub enum Expression {
         before: std::vec::Vec<Expression>,
         after: std::vec::Vec<Expression>,
     },
+    Literal {
+        value: u32,
+        r#type: TypeExpression,
+    },
+    Fork {
+        formal: Formal,
+        body: std::vec::Vec<Statement>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Step {
+    pub action: Action,
+    pub location: crate::location::Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Accept,
+    Deny,
+    ExpectAccept,
+    ExpectDeny,
+    Send { body: std::vec::Vec<Step> },
+    Receive { body: std::vec::Vec<Step> },
+    Close,
+    Wait,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (keyword, body) = match &self.action {
+            Action::Accept => ("accept", None),
+            Action::Deny => ("deny", None),
+            Action::ExpectAccept => ("expect accept", None),
+            Action::ExpectDeny => ("expect deny", None),
+            Action::Send { body } => ("send", Some(body)),
+            Action::Receive { body } => ("receive", Some(body)),
+            Action::Close => ("close", None),
+            Action::Wait => ("wait", None),
+        };
+        write!(f, "{}", keyword)?;
+        if let Some(body) = body {
+            write!(f, " {{")?;
+            for (index, step) in body.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ";")?;
+                }
+                write!(f, " {}", step)?;
+            }
+            write!(f, " }}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "type {} = {}", self.name, self.value)?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for TypeExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl TypeExpression {
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, precedence: u32) -> std::fmt::Result {
+        let own = match self {
+            TypeExpression::Lollipop { .. } => 0,
+            TypeExpression::Times { .. } => 0,
+            TypeExpression::Recursive { .. } => 0,
+            TypeExpression::With { .. } => 1,
+            TypeExpression::Plus { .. } => 1,
+            _ => 2,
+        };
+        if own < precedence {
+            write!(f, "(")?;
+        }
+        match self {
+            TypeExpression::Variable { name, is_dual } => {
+                if *is_dual {
+                    write!(f, "*")?;
+                }
+                write!(f, "{}", name)?;
+            }
+            TypeExpression::Lollipop { value, next } => {
+                value.write(f, 1)?;
+                write!(f, " -o ")?;
+                next.write(f, 0)?;
+            }
+            TypeExpression::Times { value, next } => {
+                value.write(f, 1)?;
+                write!(f, " * ")?;
+                next.write(f, 0)?;
+            }
+            TypeExpression::With { accept, deny } => {
+                accept.write(f, 2)?;
+                write!(f, " & ")?;
+                deny.write(f, 1)?;
+            }
+            TypeExpression::Plus { accept, deny } => {
+                accept.write(f, 2)?;
+                write!(f, " + ")?;
+                deny.write(f, 1)?;
+            }
+            TypeExpression::Dual { value } => {
+                write!(f, "*")?;
+                value.write(f, 2)?;
+            }
+            TypeExpression::Shared { value } => {
+                write!(f, "shared ")?;
+                value.write(f, 2)?;
+            }
+            TypeExpression::Release { value } => {
+                write!(f, "release ")?;
+                value.write(f, 2)?;
+            }
+            TypeExpression::Recursive { name, value } => {
+                write!(f, "rec {}. ", name)?;
+                value.write(f, 0)?;
+            }
+            TypeExpression::One => {
+                write!(f, "1")?;
+            }
+            TypeExpression::Bottom => {
+                write!(f, "bottom")?;
+            }
+            TypeExpression::Zero => {
+                write!(f, "0")?;
+            }
+            TypeExpression::Top => {
+                write!(f, "top")?;
+            }
+        }
+        if own < precedence {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,51 @@
+mod common;
+
+#[test]
+fn affine_routines_may_drop_their_channels() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/affine.cr");
+    let output = common::cranus(&[path.to_str().unwrap()], "3\n");
+    common::assert_succeeded(&output, "3\n");
+    let output = common::run(
+        "affine_linear.cr",
+        "type Nat = 1 + Nat
+routine gen(n: *Nat) { n deny n deny }
+",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "n is not closed");
+}
+
+#[test]
+fn dropping_a_channel_cancels_the_processes_behind_it() {
+    let output = common::run(
+        "affine_chain.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+type Nat = 1 + Nat
+affine routine gen(n: *Nat) { n deny n deny }
+routine pass(m: Nat, n: *Nat) {
+  m offer { m close n accept n wait } else { n deny pass(m, n) }
+}
+routine main(c: Console) {
+  n = gen( )
+  k = pass(n, )
+  again:
+  k offer { k close c deny c deny c close } else { c deny c accept again }
+}
+",
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "cancelled the interaction");
+    assert!(output.stdout.is_empty());
+    let output = common::run(
+        "affine_main.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+routine done(p: *1) { p wait }
+affine routine main(c: Console) { c deny c accept }
+",
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "cancelled the interaction");
+}
@@ -0,0 +1,56 @@
+mod common;
+
+const TESTS: &str = "test \"two\" {
+  assert equal(2 : Nat, 2 : Nat, )
+}
+test \"sum\" {
+  n = add(2 : Nat, 3 : Nat, )
+  assert equal(n, 5 : Nat, )
+}
+";
+
+#[test]
+fn test_runs_every_test_and_prints_a_summary() {
+    let path = common::write("assert_pass.cr", TESTS);
+    let output = common::cranus(&["test", &path], "");
+    common::assert_succeeded(
+        &output,
+        "test \"two\" ... ok\ntest \"sum\" ... ok\n2 passed; 0 failed; 0 filtered out\n",
+    );
+    let output = common::cranus(&["test", &path, "su"], "");
+    common::assert_succeeded(
+        &output,
+        "test \"sum\" ... ok\n1 passed; 0 failed; 1 filtered out\n",
+    );
+}
+
+#[test]
+fn failed_assertion_fails_the_test_with_its_location() {
+    let path = common::write(
+        "assert_fail.cr",
+        &format!(
+            "{}test \"wrong\" {{
+  n = add(2 : Nat, 2 : Nat, )
+  assert equal(n, 5 : Nat, )
+}}
+",
+            TESTS
+        ),
+    );
+    let output = common::cranus(&["test", &path], "");
+    common::assert_failed(&output, "1 of the tests failed");
+    assert_eq!(
+        std::string::String::from_utf8_lossy(&output.stdout),
+        format!(
+            "test \"two\" ... ok\ntest \"sum\" ... ok\ntest \"wrong\" ... FAILED: assertion failed at {}:10:3\n2 passed; 1 failed; 0 filtered out\n",
+            path
+        )
+    );
+}
+
+#[test]
+fn test_names_are_unique() {
+    let path = common::write("assert_duplicate.cr", "test \"a\" { }\ntest \"a\" { }\n");
+    let output = common::cranus(&["test", &path], "");
+    common::assert_failed(&output, "duplicate test \"a\"");
+}
@@ -0,0 +1,127 @@
+mod common;
+
+const EXPECTEDS: &[(&str, &str)] = &[
+    ("absurd.cr", "1\n"),
+    ("affine.cr", "3\n"),
+    ("affine_pass.cr", "1\n"),
+    ("connect.cr", "0\n"),
+    ("copy.cr", "3\n"),
+    ("data.cr", "6\n"),
+    ("double.cr", "6\n"),
+    ("dual.cr", "6\n"),
+    ("fork.cr", "6\n"),
+    ("fork_connect.cr", "0\n"),
+    ("list.cr", "5\n"),
+    ("literal.cr", "94\n"),
+    ("loop.cr", "8\n"),
+    ("loop_jump.cr", "0\n"),
+    ("prelude.cr", "11\n"),
+    ("prelude_read.cr", "3\n"),
+    ("protocol.cr", "0\n"),
+    ("qualified.cr", "0\n"),
+    ("rec.cr", "6\n"),
+    ("select.cr", "5\n"),
+    ("select_receive.cr", "1\n"),
+    ("shared.cr", "2\n"),
+    ("top.cr", "1\n"),
+    ("try.cr", "2\n"),
+    ("try_cancel.cr", "1\n"),
+    ("try_handler.cr", "1\n"),
+    ("try_raise.cr", "2\n"),
+];
+
+const FILL: &str = "type Console = rec C. (C + C) & (C & 1)
+type Down = 1 & Down
+routine drain(n: *Down) { n offer { n wait } else { drain(n) } }
+routine main(c: Console) {
+  n = drain( )
+  n deny n deny n deny n accept n close
+  c deny c deny c close
+}
+";
+
+fn run(path: &std::path::Path, args: &[&str]) -> std::process::Output {
+    let mut arguments = vec![path.to_str().unwrap()];
+    arguments.extend(args);
+    common::cranus(&arguments, "3\n")
+}
+
+#[test]
+fn examples_print_expected_output_in_every_mode() {
+    let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut paths: std::vec::Vec<std::path::PathBuf> = std::fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "cr"))
+        .collect();
+    paths.sort();
+    assert_eq!(paths.len(), EXPECTEDS.len());
+    for path in paths {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let expected =
+            if let Some((_, expected)) = EXPECTEDS.iter().find(|(other, _)| *other == name) {
+                expected
+            } else {
+                panic!("{}: no expected output", path.display());
+            };
+        for args in [
+            &[][..],
+            &["--buffer", "0"],
+            &["--buffer", "1"],
+            &["--buffer", "2"],
+            &["--buffer", "unbounded"],
+        ] {
+            let output = run(&path, args);
+            assert!(
+                output.status.success(),
+                "{} with {:?}: {}",
+                path.display(),
+                args,
+                std::string::String::from_utf8_lossy(&output.stderr)
+            );
+            assert_eq!(
+                std::string::String::from_utf8_lossy(&output.stdout),
+                *expected,
+                "{} with {:?}",
+                path.display(),
+                args
+            );
+        }
+    }
+}
+
+#[test]
+fn sender_blocks_when_the_buffer_is_full() {
+    let path = common::write("fill.cr", FILL);
+    for (capacity, sent) in [("1", 1), ("2", 2), ("unbounded", 4)] {
+        let output = run(
+            std::path::Path::new(&path),
+            &["--buffer", capacity, "--trace"],
+        );
+        assert!(
+            output.status.success(),
+            "--buffer {}: {}",
+            capacity,
+            std::string::String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(output.stdout, b"0\n");
+        let trace = std::string::String::from_utf8(output.stderr).unwrap();
+        let blocked = trace
+            .lines()
+            .position(|line| line.contains("\"action\":\"flipped\""))
+            .unwrap();
+        let chosen = trace
+            .lines()
+            .take(blocked)
+            .filter(|line| line.contains("\"action\":\"chose\""))
+            .count();
+        assert_eq!(chosen, sent, "--buffer {}", capacity);
+        assert!(
+            trace
+                .lines()
+                .nth(blocked)
+                .unwrap()
+                .contains("\"to\":\"drain\"")
+        );
+    }
+}
@@ -0,0 +1,42 @@
+#![allow(dead_code)]
+
+pub fn write(name: &str, source: &str) -> std::string::String {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, source).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+pub fn cranus(args: &[&str], input: &str) -> std::process::Output {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_cranus"))
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let _ = std::io::Write::write_all(child.stdin.as_mut().unwrap(), input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+pub fn run(name: &str, source: &str, args: &[&str], input: &str) -> std::process::Output {
+    let path = write(name, source);
+    let mut arguments = vec![path.as_str()];
+    arguments.extend(args);
+    cranus(&arguments, input)
+}
+
+pub fn assert_succeeded(output: &std::process::Output, stdout: &str) {
+    assert!(
+        output.status.success(),
+        "{}",
+        std::string::String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(std::string::String::from_utf8_lossy(&output.stdout), stdout);
+}
+
+pub fn assert_failed(output: &std::process::Output, message: &str) {
+    let stderr = std::string::String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains(message), "{}", stderr);
+}
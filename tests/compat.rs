@@ -0,0 +1,116 @@
+mod common;
+
+const OLD: &str = "type P = 1 + 1
+routine srv(p: *P) { p accept p wait }
+";
+
+fn compat(name: &str, new: &str) -> std::process::Output {
+    let old = common::write(&format!("{}_old.cr", name), OLD);
+    let new = common::write(&format!("{}_new.cr", name), new);
+    common::cranus(&["compat", &old, &new], "")
+}
+
+fn assert_incompatible(output: &std::process::Output, report: &str) {
+    assert!(!output.status.success());
+    assert_eq!(std::string::String::from_utf8_lossy(&output.stdout), report);
+    assert!(std::string::String::from_utf8_lossy(&output.stderr).contains("incompatible changes"));
+}
+
+#[test]
+fn equivalent_types_are_unchanged_and_new_types_compatible() {
+    let output = compat(
+        "compat_same",
+        "type One = 1
+type P = One + 1
+routine srv(p: *(1 + 1)) { p deny p wait }
+",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        b"type One: added, compatible\ntype P: unchanged\nroutine srv: unchanged\n"
+    );
+}
+
+#[test]
+fn added_routine_is_compatible() {
+    let output = compat(
+        "compat_added",
+        "type P = 1 + 1
+routine srv(p: *P) { p accept p wait }
+routine other(p: *P) { p deny p wait }
+",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        b"type P: unchanged\nroutine other: added, compatible\nroutine srv: unchanged\n"
+    );
+}
+
+#[test]
+fn top_branch_breaks_clients_and_servers() {
+    let output = compat(
+        "compat_wide",
+        "type P = 1 + top
+routine srv(p: *P) { p accept p wait }
+",
+    );
+    assert_incompatible(
+        &output,
+        "type P: client-breaking after [deny]: old is one, new is top
+type P: server-breaking after [deny]: old is one, new is top
+routine srv: client-breaking at formal p after [deny]: old is bottom, new is zero
+",
+    );
+}
+
+#[test]
+fn narrowed_choice_breaks_servers() {
+    let output = compat(
+        "compat_narrow",
+        "type P = 1 + 0
+routine srv(p: *P) { p accept p wait }
+",
+    );
+    assert_incompatible(
+        &output,
+        "type P: server-breaking after [deny]: old is one, new is zero
+routine srv: client-breaking at formal p after [deny]: old is bottom, new is top
+routine srv: server-breaking at formal p after [deny]: old is bottom, new is top
+",
+    );
+}
+
+#[test]
+fn removed_routine_breaks_clients() {
+    let output = compat(
+        "compat_removed",
+        "type P = 1 + 1
+routine other(p: *P) { p deny p wait }
+",
+    );
+    assert_incompatible(
+        &output,
+        "type P: unchanged
+routine other: added, compatible
+routine srv: removed, client-breaking
+",
+    );
+}
+
+#[test]
+fn changed_formal_count_breaks_both() {
+    let output = compat(
+        "compat_count",
+        "type P = 1 + 1
+routine srv(p: *P, q: 1) { q close p accept p wait }
+",
+    );
+    assert_incompatible(
+        &output,
+        "type P: unchanged
+routine srv: formal count changed, client-breaking and server-breaking
+",
+    );
+}
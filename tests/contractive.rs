@@ -0,0 +1,32 @@
+mod common;
+
+fn run(name: &str, types: &str) -> std::process::Output {
+    common::run(
+        name,
+        &format!(
+            "type Console = rec C. (C + C) & (C & 1)
+{}routine main(c: Console) {{ c deny c deny c close }}
+",
+            types
+        ),
+        &[],
+        "0\n",
+    )
+}
+
+#[test]
+fn guarded_recursion_is_contractive() {
+    let output = run(
+        "contractive.cr",
+        "type Fine = rec X. 1 + X\ntype Also = 1 & Also\n",
+    );
+    common::assert_succeeded(&output, "0\n");
+}
+
+#[test]
+fn unguarded_recursion_is_not_contractive() {
+    let output = run("not_contractive_rec.cr", "type Bad = rec X. X\n");
+    common::assert_failed(&output, "type \"X\" is not contractive");
+    let output = run("not_contractive_name.cr", "type A = B\ntype B = *A\n");
+    common::assert_failed(&output, "type \"A\" is not contractive");
+}
@@ -0,0 +1,78 @@
+mod common;
+
+const NAT: &str = "type Console = rec C. (C + C) & (C & 1)
+data Nat = zero | succ Nat
+routine zero1(n: *Nat) { n <- zero }
+routine succ1(m: Nat, n: *Nat) { n <- succ(m) }
+";
+
+#[test]
+fn match_takes_apart_what_constructors_build() {
+    let output = common::run(
+        "data_count.cr",
+        &format!(
+            "{}routine count(c: Console, m: Nat) {{
+  match m {{
+    zero {{ c deny c deny c close }}
+    succ m {{ c deny c accept count(c, m) }}
+  }}
+}}
+routine main(c: Console) {{ z = zero1( ) o = succ1(z, ) t = succ1(o, ) count(c, t) }}
+",
+            NAT
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "2\n");
+}
+
+#[test]
+fn match_needs_every_constructor() {
+    let output = common::run(
+        "data_missing.cr",
+        &format!(
+            "{}routine count(c: Console, m: Nat) {{ match m {{ zero {{ c deny c deny c close }} }} }}\n",
+            NAT
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "missing case for constructor \"succ\"");
+}
+
+#[test]
+fn constructors_take_their_fields() {
+    let output = common::run(
+        "data_fields.cr",
+        &format!("{}routine bad(n: *Nat) {{ n <- succ }}\n", NAT),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "incorrect number of fields for \"succ\"");
+    let output = common::run(
+        "data_unknown.cr",
+        &format!("{}routine bad(n: *Nat) {{ n <- three }}\n", NAT),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "unknown constructor \"three\"");
+}
+
+#[test]
+fn data_and_type_share_one_namespace() {
+    let output = common::run(
+        "data_duplicate.cr",
+        &format!("{}type Nat = 1\n", NAT),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "duplicate type \"Nat\"");
+    let output = common::run(
+        "data_duplicate_data.cr",
+        &format!("{}data Nat = none\n", NAT),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "duplicate type \"Nat\"");
+}
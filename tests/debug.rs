@@ -0,0 +1,262 @@
+mod common;
+
+const PROGRAM: &str = "type Console = rec C. (C + C) & (C & 1)
+type Bit = 1 + 1
+routine no(p: *Bit) { p deny p wait }
+routine finish(c: Console) { c deny c deny c close }
+routine main(c: Console) {
+  b = no( )
+  b offer { b close } else { b close }
+  again:
+  c accept
+  c offer { again } else { finish(c) }
+}
+";
+
+fn debug(name: &str, args: &[&str], script: &str) -> std::string::String {
+    let path = common::write(name, PROGRAM);
+    let mut arguments = vec!["debug", &path, "--no-prelude"];
+    arguments.extend(args);
+    let output = common::cranus(&arguments, script);
+    assert!(output.status.success());
+    std::string::String::from_utf8_lossy(&output.stdout).replace(&path, "FILE")
+}
+
+#[test]
+fn breakpoints_stop_on_routines_labels_and_lines() {
+    let stdout = debug(
+        "debug_break.cr",
+        &[],
+        "2
+break routine finish
+break label again
+break line 7
+breakpoints
+continue
+delete 3
+continue
+delete 2
+continue
+continue
+",
+    );
+    assert_eq!(
+        stdout,
+        "input: next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) breakpoint 1: routine finish
+(debug) breakpoint 2: label again
+(debug) breakpoint 3: line 7
+(debug) breakpoint 1: routine finish
+breakpoint 2: label again
+breakpoint 3: line 7
+(debug) breakpoint 3: line 7
+next: main at node 12 (FILE:7:3) after 1 steps with 2 processes and 1 channels
+    7 |   b offer { b close } else { b close }
+(debug) (debug) breakpoint 2: label again
+next: main at node 9 (FILE:9:3) after 4 steps with 1 processes and 1 channels
+    9 |   c accept
+(debug) (debug) breakpoint 1: routine finish
+next: finish at node 5 (FILE:4:30) after 13 steps with 1 processes and 1 channels
+    4 | routine finish(c: Console) { c deny c deny c close }
+(debug) step 16: finish at node 3 (FILE:4:44) closed \"c\"
+run ended with output 0
+(debug) 
+"
+    );
+}
+
+#[test]
+fn children_and_tree_show_channels_and_processes() {
+    let stdout = debug(
+        "debug_children.cr",
+        &[],
+        "2
+children
+step
+children
+tree
+",
+    );
+    assert_eq!(
+        stdout,
+        "input: next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug)   c: Console, the interaction
+(debug) step 1: main at node 13 (FILE:6:3) executed
+next: main at node 12 (FILE:7:3) after 1 steps with 2 processes and 1 channels
+    7 |   b offer { b close } else { b close }
+(debug)   b: Bit, no at node 2 (FILE:3:23)
+  c: Console, the interaction
+(debug) next: main at node 12 (FILE:7:3) after 1 steps with 2 processes and 1 channels
+    7 |   b offer { b close } else { b close }
+  b: Bit, no at node 2 (FILE:3:23)
+  c: Console, the interaction
+(debug) 
+"
+    );
+}
+
+#[test]
+fn step_where_and_until_follow_the_run() {
+    let stdout = debug(
+        "debug_step.cr",
+        &[],
+        "2
+step 3
+where
+until c
+quit
+",
+    );
+    assert_eq!(
+        stdout,
+        "input: next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) step 1: main at node 13 (FILE:6:3) executed
+step 2: main at node 12 (FILE:7:3) switched over \"b\" to no
+step 3: no at node 2 (FILE:3:23) denied on \"p\" with main
+next: no at node 1 (FILE:3:30) after 3 steps with 2 processes and 1 channels
+    3 | routine no(p: *Bit) { p deny p wait }
+(debug) next: no at node 1 (FILE:3:30) after 3 steps with 2 processes and 1 channels
+    3 | routine no(p: *Bit) { p deny p wait }
+(debug) step 5: main at node 9 (FILE:9:3) accepted on \"c\"
+next: main at node 8 (FILE:10:3) after 5 steps with 1 processes and 1 channels
+   10 |   c offer { again } else { finish(c) }
+(debug) "
+    );
+}
+
+#[test]
+fn commands_report_their_errors() {
+    let stdout = debug(
+        "debug_errors.cr",
+        &[],
+        "2
+frobnicate
+step 1 2
+break routine nobody
+break label nowhere
+break line x
+delete 1
+goto 0
+continue
+continue
+quit
+",
+    );
+    assert_eq!(
+        stdout,
+        "input: next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) unknown command \"frobnicate\"; try \"help\"
+(debug) wrong argument count
+(debug) unknown routine \"nobody\"
+(debug) unknown label \"nowhere\"
+(debug) invalid argument \"x\"
+(debug) unknown breakpoint 1
+(debug) next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) step 16: finish at node 3 (FILE:4:44) closed \"c\"
+run ended with output 0
+(debug) the run has ended
+(debug) "
+    );
+}
+
+#[test]
+fn reverse_step_and_goto_revisit_earlier_steps() {
+    let stdout = debug(
+        "debug_reverse.cr",
+        &["--record", "4"],
+        "2
+goto 10
+reverse-step
+reverse-step 6
+goto 16
+goto 2
+quit
+",
+    );
+    assert_eq!(
+        stdout,
+        "input: next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) next: main at node 9 (FILE:9:3) after 10 steps with 1 processes and 1 channels
+    9 |   c accept
+(debug) next: main at node 6 (FILE:10:13) after 9 steps with 1 processes and 1 channels
+   10 |   c offer { again } else { finish(c) }
+(debug) next: no at node 1 (FILE:3:30) after 3 steps with 2 processes and 1 channels
+    3 | routine no(p: *Bit) { p deny p wait }
+(debug) step 16: finish at node 3 (FILE:4:44) closed \"c\"
+run ended with output 0
+next: finish at node 3 (FILE:4:44) after 16 steps with 1 processes and 1 channels
+    4 | routine finish(c: Console) { c deny c deny c close }
+(debug) next: no at node 2 (FILE:3:23) after 2 steps with 2 processes and 1 channels
+    3 | routine no(p: *Bit) { p deny p wait }
+(debug) "
+    );
+}
+
+#[test]
+fn reverse_continue_stops_at_earlier_breakpoints() {
+    let stdout = debug(
+        "debug_reverse_continue.cr",
+        &["--record", "4"],
+        "2
+break label again
+goto 10
+reverse-continue
+reverse-continue
+reverse-continue
+continue
+quit
+",
+    );
+    assert_eq!(
+        stdout,
+        "input: next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) breakpoint 1: label again
+(debug) next: main at node 9 (FILE:9:3) after 10 steps with 1 processes and 1 channels
+    9 |   c accept
+(debug) breakpoint 1: label again
+next: main at node 9 (FILE:9:3) after 7 steps with 1 processes and 1 channels
+    9 |   c accept
+(debug) breakpoint 1: label again
+next: main at node 9 (FILE:9:3) after 4 steps with 1 processes and 1 channels
+    9 |   c accept
+(debug) reached the start of the recording
+next: main at node 13 (FILE:6:3) after 0 steps with 1 processes and 0 channels
+    6 |   b = no( )
+(debug) breakpoint 1: label again
+next: main at node 9 (FILE:9:3) after 4 steps with 1 processes and 1 channels
+    9 |   c accept
+(debug) "
+    );
+}
+
+#[test]
+fn reverse_commands_need_a_recording() {
+    let stdout = debug(
+        "debug_not_recording.cr",
+        &[],
+        "2
+step 2
+reverse-step
+reverse-continue
+goto 1
+quit
+",
+    );
+    assert!(
+        stdout.ends_with(
+            "(debug) not recording; debug with --record INTERVAL to go back
+(debug) not recording; debug with --record INTERVAL to go back
+(debug) not recording; debug with --record INTERVAL to go back
+(debug) "
+        ),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,36 @@
+mod common;
+
+#[test]
+fn dual_applies_to_the_whole_parenthesised_type() {
+    let output = common::run(
+        "dual_choice.cr",
+        "type Console = (Console + Console) & (Console & 1)
+routine srv(p: *(1 + 1)) { p accept p wait }
+routine main(c: Console) {
+  p = srv( )
+  p offer { p close c deny c accept c deny c deny c close } else { p close c deny c deny c close }
+}
+",
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "1\n");
+    let output = common::run(
+        "dual_offer.cr",
+        "routine srv(p: *(1 + 1)) { p offer { p wait } else { p wait } }\n",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "p is not plus");
+}
+
+#[test]
+fn dual_without_parentheses_binds_tighter_than_choice() {
+    let output = common::run(
+        "dual_tight.cr",
+        "routine srv(p: *1 + 1) { p accept p wait }\n",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "p is not with");
+}
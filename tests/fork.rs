@@ -0,0 +1,63 @@
+mod common;
+
+const SERVER: &str = "type Console = rec C. (C + C) & (C & 1)
+type P = 1 + 1
+routine srv(p: *P) { p accept p wait }
+";
+
+#[test]
+fn fork_runs_its_body_with_the_captured_channels() {
+    let output = common::run(
+        "fork_capture.cr",
+        &format!(
+            "{}routine main(c: Console) {{
+  p = srv( )
+  q = fork(r: *(1 + 1)) {{ p offer {{ p close r accept r wait }} else {{ p close r deny r wait }} }}
+  q offer {{ q close c deny c accept c deny c deny c close }} else {{ q close c deny c deny c close }}
+}}
+",
+            SERVER
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "1\n");
+}
+
+#[test]
+fn fork_takes_captured_channels_away_from_the_context() {
+    let output = common::run(
+        "fork_reuse.cr",
+        &format!(
+            "{}routine main(c: Console) {{
+  p = srv( )
+  q = fork(r: *1) {{ p offer {{ p close r wait }} else {{ p close r wait }} }}
+  p offer {{ p close }} else {{ p close }}
+  q close
+  c deny c deny c close
+}}
+",
+            SERVER
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "p is closed");
+}
+
+#[test]
+fn fork_body_is_checked_like_a_routine() {
+    let output = common::run(
+        "fork_close_bottom.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+routine main(c: Console) {
+  q = fork(r: *1) { r close }
+  q close
+  c deny c deny c close
+}
+",
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "r is not one");
+}
@@ -0,0 +1,88 @@
+mod common;
+
+const PROGRAM: &str = "type Flag = 1 + 1
+type More = rec M. M + 1
+type Marks = rec M. (M + M) + 1
+routine no(f: *Flag) { f deny f wait }
+routine fine(p: More) { while p offer { } p close }
+routine hang(p: More) { p offer { loop { } } else { p close } }
+routine third(p: More) {
+  p offer {
+    p offer {
+      p offer { f = no( ) assert f while p offer { } p close } else { p close }
+    } else { p close }
+  } else { p close }
+}
+routine clean(p: Marks) { p offer { p offer { clean(p) } else { dirty(p) } } else { p close } }
+routine dirty(p: Marks) {
+  p offer { p offer { dirty(p) } else { dirty(p) } } else { f = no( ) assert f p close }
+}
+";
+
+fn fuzz(routine: &str) -> (std::process::Output, std::string::String) {
+    let path = common::write(&format!("fuzz_{}.cr", routine), PROGRAM);
+    let output = common::cranus(
+        &[
+            "fuzz",
+            &path,
+            routine,
+            "--no-prelude",
+            "--runs",
+            "20",
+            "--budget",
+            "1000",
+        ],
+        "",
+    );
+    let stdout = std::string::String::from_utf8_lossy(&output.stdout).to_string();
+    (output, stdout)
+}
+
+#[test]
+fn fuzz_passes_a_routine_that_always_ends() {
+    let (output, stdout) = fuzz("fine");
+    assert!(output.status.success());
+    assert_eq!(stdout, "20 runs; 0 hung; 0 failed\n");
+}
+
+#[test]
+fn fuzz_detects_a_hang() {
+    let (output, stdout) = fuzz("hang");
+    common::assert_failed(&output, "of the runs failed");
+    assert!(
+        stdout.contains("driving \"p\": hangs after 1000 steps\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("  shrunk choices: accept\n"), "{}", stdout);
+    assert!(!stdout.contains("0 hung;"), "{}", stdout);
+}
+
+#[test]
+fn fuzz_detects_a_failure() {
+    let (output, stdout) = fuzz("third");
+    common::assert_failed(&output, "of the runs failed");
+    assert!(
+        stdout.contains("driving \"p\": assertion failed at "),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("  shrunk choices: accept accept accept\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains(" 0 hung; "), "{}", stdout);
+}
+
+#[test]
+fn fuzz_shrinks_to_a_minimal_input() {
+    let (output, stdout) = fuzz("clean");
+    common::assert_failed(&output, "of the runs failed");
+    assert_eq!(stdout.matches("shrunk choices").count(), 1, "{}", stdout);
+    assert!(
+        stdout.contains("  shrunk choices: accept deny deny\n"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+const FOREVER: &str = "type Console = rec C. (C + C) & (C & 1)
+routine main(c: Console) {
+  loop { c deny c accept }
+}
+";
+
+const GROW: &str = "type Console = rec C. (C + C) & (C & 1)
+type Nat = 1 + Nat
+routine grow(n: *Nat) { m = grow( ) n deny pass(m, n) }
+routine pass(m: Nat, n: *Nat) {
+  m offer { m close n accept n wait } else { n deny pass(m, n) }
+}
+routine main(c: Console) {
+  n = grow( )
+  again:
+  n offer { n close c deny c deny c close } else { again }
+}
+";
+
+const SHORT: &str = "type Console = rec C. (C + C) & (C & 1)
+routine main(c: Console) { c deny c deny c close }
+";
+
+fn run(name: &str, source: &str, args: &[&str]) -> std::process::Output {
+    common::run(name, source, args, "0\n")
+}
+
+#[test]
+fn max_steps_stops_after_that_many_steps() {
+    let output = run("steps_forever.cr", FOREVER, &["--max-steps", "1000"]);
+    common::assert_failed(
+        &output,
+        "step limit exceeded after 1000 steps with 1 processes and 0 channels",
+    );
+    assert!(output.stdout.is_empty());
+    let output = run("steps_short.cr", SHORT, &["--max-steps", "3"]);
+    common::assert_succeeded(&output, "0\n");
+    let output = run("steps_short.cr", SHORT, &["--max-steps", "2"]);
+    common::assert_failed(&output, "step limit exceeded after 2 steps");
+}
+
+#[test]
+fn max_processes_stops_a_growing_program() {
+    let output = run("processes.cr", GROW, &["--max-processes", "10"]);
+    common::assert_failed(&output, "process limit exceeded");
+    assert!(
+        std::string::String::from_utf8_lossy(&output.stderr).contains("with 11 processes"),
+        "{}",
+        std::string::String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn max_processes_counts_only_live_processes() {
+    let output = run(
+        "processes_live.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+routine done(p: *1) { p wait }
+routine main(c: Console) {
+  p = done( ) p close q = done( ) q close r = done( ) r close
+  c deny c deny c close
+}
+",
+        &["--max-processes", "2"],
+    );
+    common::assert_succeeded(&output, "0\n");
+}
+
+#[test]
+fn max_channels_stops_a_growing_program() {
+    let output = run("channels.cr", GROW, &["--max-channels", "10"]);
+    common::assert_failed(&output, "channel limit exceeded");
+    assert!(
+        std::string::String::from_utf8_lossy(&output.stderr).contains("and 11 channels"),
+        "{}",
+        std::string::String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn timeout_cancels_a_program_that_does_not_end() {
+    let output = run("timeout.cr", FOREVER, &["--timeout", "0.2"]);
+    common::assert_failed(&output, "cancelled after");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn timeout_leaves_a_program_that_ends_in_time() {
+    let output = run("timeout_short.cr", SHORT, &["--timeout", "60"]);
+    common::assert_succeeded(&output, "0\n");
+}
+
+#[test]
+fn options_are_rejected_where_they_do_not_apply() {
+    let output = run("options_record.cr", SHORT, &["--record", "3"]);
+    common::assert_failed(
+        &output,
+        "option \"--record\" does not apply to this command",
+    );
+    let output = run("options_runs.cr", SHORT, &["--runs", "3"]);
+    common::assert_failed(&output, "option \"--runs\" does not apply to this command");
+    let output = run("options_unknown.cr", SHORT, &["--frobnicate", "3"]);
+    common::assert_failed(&output, "unknown option \"--frobnicate\"");
+    let output = run("options_negative.cr", SHORT, &["--timeout", "-1"]);
+    common::assert_failed(&output, "value is negative");
+}
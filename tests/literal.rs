@@ -0,0 +1,56 @@
+mod common;
+
+const PROGRAM: &str = "type Console = Input & Output
+type Input = Console + Console
+type Output = Console & 1
+data Nat = zero | succ Nat
+type Bad = 1 & Bad
+type Pair = 1 + 1
+routine write(c: Console, n: Nat) {
+  match n { zero { c deny c deny c close } succ n { c deny c accept write(c, n) } }
+}
+";
+
+fn run(name: &str, main: &str) -> std::process::Output {
+    common::run(
+        name,
+        &format!("{}routine main(c: Console) {{ {} }}\n", PROGRAM, main),
+        &[],
+        "0\n",
+    )
+}
+
+#[test]
+fn literal_of_unary_type_runs() {
+    let output = run("literal_unary.cr", "n = 65535 : Nat write(c, n)");
+    common::assert_succeeded(&output, "65535\n");
+}
+
+#[test]
+fn literal_of_other_type_is_not_unary() {
+    let output = run(
+        "literal_with.cr",
+        "n = 3 : Bad n close c deny c deny c close",
+    );
+    common::assert_failed(
+        &output,
+        "literal 3 : Bad does not have a type like zero | succ",
+    );
+    let output = run(
+        "literal_pair.cr",
+        "n = 3 : Pair n close c deny c deny c close",
+    );
+    common::assert_failed(
+        &output,
+        "literal 3 : Pair does not have a type like zero | succ",
+    );
+}
+
+#[test]
+fn literal_above_maximum_is_too_large() {
+    let output = run("literal_large.cr", "n = 4000000000 : Nat write(c, n)");
+    common::assert_failed(&output, "integer 4000000000 too large at");
+    let output = run("literal_above.cr", "n = 65536 : Nat write(c, n)");
+    common::assert_failed(&output, "integer 65536 too large at");
+    common::assert_failed(&output, "the maximum is 65535");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+fn run(name: &str, main: &str, args: &[&str]) -> std::process::Output {
+    common::run(
+        name,
+        &format!(
+            "type Console = rec C. (C + C) & (C & 1)\nroutine main(c: Console) {{ {} }}\n",
+            main
+        ),
+        args,
+        "0\n",
+    )
+}
+
+#[test]
+fn loops_break_and_continue() {
+    let output = run(
+        "loop_count.cr",
+        "loop { c accept c offer { c deny c accept continue } else { break } } c deny c deny c close",
+        &[],
+    );
+    common::assert_succeeded(&output, "0\n");
+    let output = run(
+        "loop_jump.cr",
+        "loop { break } loop { c accept c offer { continue } else { break } } c deny c deny c close",
+        &[],
+    );
+    common::assert_succeeded(&output, "0\n");
+    let output = run("loop_outside.cr", "break", &[]);
+    common::assert_failed(&output, "break outside loop");
+}
+
+#[test]
+fn code_after_a_jump_is_unreachable() {
+    let output = run(
+        "dead_break.cr",
+        "loop { c accept c offer { } else { break c deny c accept } } c deny c deny c close",
+        &[],
+    );
+    common::assert_failed(&output, "unreachable statement at ");
+    let stderr = std::string::String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.ends_with("dead_break.cr:2:69\n"), "{}", stderr);
+    let output = run(
+        "dead_continue.cr",
+        "loop { c accept c offer { continue c deny c accept } else { break } } c deny c deny c close",
+        &[],
+    );
+    common::assert_failed(&output, "unreachable statement at ");
+    let output = run(
+        "dead_forever.cr",
+        "loop { c accept c offer { } else { } } c deny c deny c close",
+        &[],
+    );
+    common::assert_failed(&output, "unreachable statement at ");
+}
+
+#[test]
+fn label_after_a_jump_is_reachable_when_used() {
+    let output = run(
+        "dead_label.cr",
+        "c accept c offer { again } else { } c deny c deny c close break again: c deny c accept c deny c deny c close",
+        &[],
+    );
+    common::assert_failed(&output, "break outside loop");
+    let output = run(
+        "dead_label_used.cr",
+        "loop { c accept c offer { again } else { } break again: c deny c accept break } c deny c deny c close",
+        &[],
+    );
+    common::assert_succeeded(&output, "0\n");
+    let output = run(
+        "dead_label_unused.cr",
+        "loop { c accept c offer { } else { } break again: c deny c accept break } c deny c deny c close",
+        &[],
+    );
+    common::assert_failed(&output, "unreachable statement at ");
+}
+
+#[test]
+fn loop_without_break_needs_no_end() {
+    let output = run(
+        "forever.cr",
+        "loop { c accept c offer { } else { } }",
+        &["--max-steps", "100"],
+    );
+    common::assert_failed(&output, "step limit exceeded");
+}
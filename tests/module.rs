@@ -0,0 +1,137 @@
+mod common;
+
+const LIB: &str = "pub type Unit = 1
+routine hidden(u: *Unit) { u wait }
+pub routine make(u: *Unit) { u wait }
+";
+
+const CONSOLE: &str = "type Console = rec C. (C + C) & (C & 1)\n";
+
+fn run(directory: &str, files: &[(&str, &str)], main: &str) -> std::process::Output {
+    for (name, source) in files {
+        common::write(&format!("{}/{}", directory, name), source);
+    }
+    let path = common::write(
+        &format!("{}/main.cr", directory),
+        &format!("{}{}", main, CONSOLE),
+    );
+    common::cranus(&[&path], "0\n")
+}
+
+#[test]
+fn import_cycle_is_rejected() {
+    let output = run(
+        "module_cycle",
+        &[
+            ("a.cr", "import \"b.cr\"\npub type A = 1\n"),
+            ("b.cr", "import \"a.cr\"\npub type B = 1\n"),
+        ],
+        "import \"a.cr\"
+routine main(c: Console) { c deny c deny c close }
+",
+    );
+    common::assert_failed(&output, "import cycle ");
+    common::assert_failed(&output, "a.cr -> ");
+}
+
+#[test]
+fn shared_import_is_not_a_cycle() {
+    let output = run(
+        "module_diamond",
+        &[
+            ("lib.cr", LIB),
+            ("a.cr", "import \"lib.cr\"\npub type A = lib::Unit\n"),
+            ("b.cr", "import \"lib.cr\"\npub type B = lib::Unit\n"),
+        ],
+        "import \"a.cr\"
+import \"b.cr\"
+import \"lib.cr\"
+routine main(c: Console) { u = lib::make( ) u close c deny c deny c close }
+",
+    );
+    common::assert_succeeded(&output, "0\n");
+}
+
+#[test]
+fn private_name_is_not_public() {
+    let output = run(
+        "module_private",
+        &[("lib.cr", LIB)],
+        "import \"lib.cr\"
+routine main(c: Console) { u = lib::hidden( ) u close c deny c deny c close }
+",
+    );
+    common::assert_failed(&output, "\"lib::hidden\" is not public in ");
+    let output = run(
+        "module_public",
+        &[("lib.cr", LIB)],
+        "import \"lib.cr\"
+routine main(c: Console) { u = lib::make( ) u close c deny c deny c close }
+",
+    );
+    common::assert_succeeded(&output, "0\n");
+}
+
+#[test]
+fn modules_with_the_same_name_conflict() {
+    let output = run(
+        "module_duplicate",
+        &[("a/lib.cr", LIB), ("b/lib.cr", LIB)],
+        "import \"a/lib.cr\"
+import \"b/lib.cr\"
+routine main(c: Console) { c deny c deny c close }
+",
+    );
+    common::assert_failed(
+        &output,
+        "main.cr:2:8 conflicts with another module of the same name",
+    );
+    let output = run(
+        "module_distinct",
+        &[("a/lib.cr", LIB), ("b/other.cr", LIB)],
+        "import \"a/lib.cr\"
+import \"b/other.cr\"
+routine main(c: Console) {
+  u = lib::make( ) u close v = other::make( ) v close c deny c deny c close
+}
+",
+    );
+    common::assert_succeeded(&output, "0\n");
+}
+
+#[test]
+fn qualified_names_resolve_in_their_module() {
+    let output = run(
+        "module_unqualified",
+        &[("lib.cr", LIB)],
+        "import \"lib.cr\"
+routine main(c: Console) { u = make( ) u close c deny c deny c close }
+",
+    );
+    common::assert_failed(&output, "unknown routine \"make\"");
+    let output = run(
+        "module_unknown_module",
+        &[("lib.cr", LIB)],
+        "import \"lib.cr\"
+routine main(c: Console) { u = nope::make( ) u close c deny c deny c close }
+",
+    );
+    common::assert_failed(&output, "unknown module \"nope\" in ");
+    let output = run(
+        "module_unknown_name",
+        &[("lib.cr", LIB)],
+        "import \"lib.cr\"
+routine main(c: Console) { u = lib::other( ) u close c deny c deny c close }
+",
+    );
+    common::assert_failed(&output, "unknown name \"lib::other\" in ");
+    let output = run(
+        "module_use",
+        &[("lib.cr", LIB)],
+        "import \"lib.cr\"
+use lib::make
+routine main(c: Console) { u = make( ) u close c deny c deny c close }
+",
+    );
+    common::assert_succeeded(&output, "0\n");
+}
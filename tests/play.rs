@@ -0,0 +1,30 @@
+mod common;
+
+const SOURCE: &str = "type Q = 1 & 1
+routine srv(p: *Q) { p offer { p wait } else { p wait } }
+routine cli(p: *(1 + 1)) { p accept p wait }
+";
+
+#[test]
+fn play_asks_until_it_gets_a_choice() {
+    let path = common::write("play.cr", SOURCE);
+    let output = common::cranus(&["play", &path, "srv", "p"], "x\nd\n");
+    common::assert_succeeded(
+        &output,
+        "p: 1 & 1\np: accept (1) or deny (1)? p: accept (1) or deny (1)? p: session ends\n",
+    );
+    let output = common::cranus(&["play", &path, "cli", "p"], "");
+    common::assert_succeeded(
+        &output,
+        "p: 1 + 1\np: routine accepts, now 1\np: session ends\n",
+    );
+}
+
+#[test]
+fn play_rejects_ended_input_and_unknown_formals() {
+    let path = common::write("play_rejected.cr", SOURCE);
+    let output = common::cranus(&["play", &path, "srv", "p"], "");
+    common::assert_failed(&output, "input ended before the session did");
+    let output = common::cranus(&["play", &path, "cli", "q"], "");
+    common::assert_failed(&output, "unknown formal \"q\"");
+}
@@ -0,0 +1,36 @@
+mod common;
+
+#[test]
+fn prelude_tests_pass() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/prelude.cr");
+    let output = common::cranus(&["test", path.to_str().unwrap(), "--no-prelude"], "");
+    assert!(
+        output.status.success(),
+        "{}{}",
+        std::string::String::from_utf8_lossy(&output.stdout),
+        std::string::String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn compat_leaves_out_the_prelude() {
+    let old = common::write("prelude_compat/old.cr", "type P = 1 + 1\n");
+    let new = common::write("prelude_compat/new.cr", "type P = 1 + 1\n");
+    let output = common::cranus(&["compat", &old, &new], "");
+    common::assert_succeeded(&output, "type P: unchanged\n");
+}
+
+#[test]
+fn compat_keeps_a_module_named_prelude() {
+    common::write("prelude_module_old/prelude.cr", "pub type T = 1\n");
+    common::write("prelude_module_new/prelude.cr", "pub type T = bottom\n");
+    let source = "import \"prelude.cr\"\ntype P = prelude::T\n";
+    let old = common::write("prelude_module_old/main.cr", source);
+    let new = common::write("prelude_module_new/main.cr", source);
+    let output = common::cranus(&["compat", &old, &new, "--no-prelude"], "");
+    common::assert_failed(&output, "incompatible changes");
+    let stdout = std::string::String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("type prelude::T: "), "{}", stdout);
+    let output = common::cranus(&["compat", &old, &new], "");
+    common::assert_failed(&output, "conflicts with another module of the same name");
+}
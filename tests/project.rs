@@ -0,0 +1,96 @@
+mod common;
+
+fn project(name: &str, source: &str) -> std::process::Output {
+    let path = common::write(name, source);
+    common::cranus(&["project", &path], "")
+}
+
+#[test]
+fn project_prints_a_type_for_each_pair_of_roles() {
+    let output = project(
+        "project_ping.cr",
+        "protocol Ping(A, B) {
+  A -> B: 1
+  B -> A: bottom
+}
+",
+    );
+    common::assert_succeeded(
+        &output,
+        "type Ping_B_A = *Ping_A_B
+type Ping_A_B = 1 -o bottom * 1
+",
+    );
+}
+
+#[test]
+fn project_merges_choices_and_recursion() {
+    let output = project(
+        "project_loop.cr",
+        "protocol Ask(A, B) {
+  rec Loop { A -> B: choice { B -> A: 1 Loop } else { } }
+}
+",
+    );
+    common::assert_succeeded(
+        &output,
+        "type Ask_B_A = *Ask_A_B
+type Ask_A_B = rec Loop. (1 * Loop) & 1
+",
+    );
+}
+
+#[test]
+fn project_rejects_ill_formed_protocols() {
+    let output = project(
+        "project_duplicate.cr",
+        "protocol P(A, B, A) { A -> B: 1 }\n",
+    );
+    common::assert_failed(&output, "duplicate role \"A\"");
+    let output = project("project_unknown.cr", "protocol P(A, B) { A -> C: 1 }\n");
+    common::assert_failed(&output, "unknown role \"C\"");
+    let output = project("project_self.cr", "protocol P(A, B) { A -> A: 1 }\n");
+    common::assert_failed(&output, "role \"A\" interacts with itself");
+    let output = project("project_recursion.cr", "protocol P(A, B) { Loop }\n");
+    common::assert_failed(&output, "unknown recursion \"Loop\"");
+}
+
+#[test]
+fn project_rejects_a_choice_unknown_to_a_role() {
+    let output = project(
+        "project_choice.cr",
+        "protocol P(A, B, C) {
+  A -> B: choice { B -> C: 1 } else { }
+}
+",
+    );
+    common::assert_failed(
+        &output,
+        "protocol \"P\" is not projectable onto \"B\" and \"C\"",
+    );
+}
+
+#[test]
+fn project_rejects_names_that_collide() {
+    let output = project(
+        "project_twice.cr",
+        "protocol P(A, B) { A -> B: 1 }\nprotocol P(A, C) { A -> C: 1 }\n",
+    );
+    common::assert_failed(&output, "duplicate protocol \"P\"");
+    let output = project(
+        "project_ambiguous.cr",
+        "protocol P(A_B, C, A, B_C) { A -> B_C: 1 }\n",
+    );
+    common::assert_failed(
+        &output,
+        "projected type \"P_A_B_C\" names more than one pair of roles",
+    );
+    let output = project(
+        "project_across.cr",
+        "protocol P(A_B, C) { A_B -> C: 1 }\nprotocol P_A(B, C) { B -> C: 1 }\n",
+    );
+    common::assert_failed(
+        &output,
+        "projected type \"P_A_B_C\" names more than one pair of roles",
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+
+const RACE: &str = "type Console = rec C. (C + C) & (C & 1)
+type Nat = 1 + Nat
+routine zero(n: *Nat) { n accept n wait }
+routine succ(k: Nat, n: *Nat) { n deny n connect k }
+routine drain(a: Nat, c: Console) {
+  a offer { a close c deny c deny c close } else { drain(a, c) }
+}
+routine race(a: Nat, b: Nat, c: Console) {
+  select {
+    a offer { a close drain(b, c) } else { c deny c accept race(a, b, c) }
+    b offer { b close drain(a, c) } else { race(a, b, c) }
+  }
+}
+routine main(c: Console) {
+  z = zero( )
+  a1 = succ(z, )
+  a2 = succ(a1, )
+  a3 = succ(a2, )
+  a4 = succ(a3, )
+  a = succ(a4, )
+  y = zero( )
+  b1 = succ(y, )
+  b2 = succ(b1, )
+  b3 = succ(b2, )
+  b4 = succ(b3, )
+  b = succ(b4, )
+  race(a, b, c)
+}
+";
+
+fn run(name: &str, args: &[&str]) -> std::process::Output {
+    let mut arguments = vec!["--trace"];
+    arguments.extend(args);
+    common::run(name, RACE, &arguments, "0\n")
+}
+
+#[test]
+fn seed_is_deterministic() {
+    let first = run("seed.cr", &["--seed", "7"]);
+    let second = run("seed.cr", &["--seed", "7"]);
+    assert!(first.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    assert_eq!(first.stderr, second.stderr);
+}
+
+#[test]
+fn seed_with_zero_state_still_varies() {
+    let unseeded = run("zero_state.cr", &[]);
+    let seeded = run("zero_state.cr", &["--seed", "11400714819323198485"]);
+    assert!(unseeded.status.success());
+    assert!(seeded.status.success());
+    assert_ne!(unseeded.stderr, seeded.stderr);
+}
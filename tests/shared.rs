@@ -0,0 +1,32 @@
+mod common;
+
+fn run(name: &str, source: &str) -> std::process::Output {
+    common::run(name, source, &[], "0\n")
+}
+
+#[test]
+fn release_at_another_type_is_rejected() {
+    let output = run(
+        "release_mismatch.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+type Other = shared (release (1 * Other))
+type Bad = shared (release Other)
+routine hold(s: Bad, c: Console) { s acquire s release c deny c deny c close }
+routine main(c: Console) { c deny c deny c close }
+",
+    );
+    common::assert_failed(&output, "s is not released at the type it was acquired at");
+}
+
+#[test]
+fn release_at_the_acquired_type_is_accepted() {
+    let output = run(
+        "release_match.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+type Good = shared (release Good)
+routine hold(s: Good, c: Console) { s acquire s release c deny c deny c close }
+routine main(c: Console) { c deny c deny c close }
+",
+    );
+    common::assert_succeeded(&output, "0\n");
+}
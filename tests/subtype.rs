@@ -0,0 +1,162 @@
+mod common;
+
+const PROGRAM: &str = "type Console = rec C. (C + C) & (C & 1)
+type Narrow = 1 + 0
+type Wide = 1 + 1
+routine narrow(n: *Narrow) { n accept n wait }
+routine wide(n: *Wide) { n accept n wait }
+routine out(a: Wide, c: Console) {
+  a offer { a close c deny c accept c deny c deny c close } else { a close c deny c deny c close }
+}
+routine only(a: Narrow, c: Console) {
+  a offer { a close c deny c accept c deny c deny c close } else { a absurd }
+}
+routine give(k: Narrow, s: *(Wide * bottom)) { s send k s close }
+";
+
+fn is_subtype(sub: &str, sup: &str) -> bool {
+    let output = common::cranus(&["subtype", sub, sup], "");
+    assert!(output.status.success());
+    match &*output.stdout {
+        b"true\n" => true,
+        b"false\n" => false,
+        stdout => panic!("{}", std::string::String::from_utf8_lossy(stdout)),
+    }
+}
+
+#[test]
+fn subtype_accepts_narrower_types() {
+    assert!(is_subtype("1", "1"));
+    assert!(is_subtype("0", "1"));
+    assert!(is_subtype("1 + 0", "1 + 1"));
+    assert!(is_subtype("1 & 0", "1 & 1"));
+    assert!(is_subtype("0 * 1", "1 * 1"));
+    assert!(is_subtype("1 -o 1", "0 -o 1"));
+}
+
+#[test]
+fn subtype_rejects_wider_types() {
+    assert!(!is_subtype("1", "bottom"));
+    assert!(!is_subtype("1", "0"));
+    assert!(!is_subtype("top", "1"));
+    assert!(!is_subtype("1 + 1", "1 + 0"));
+    assert!(!is_subtype("1 & top", "1 & 1"));
+    assert!(!is_subtype("1", "top"));
+    assert!(!is_subtype("1 & 1", "1 & top"));
+    assert!(!is_subtype("1 * 1", "0 * 1"));
+    assert!(!is_subtype("0 -o 1", "1 -o 1"));
+}
+
+#[test]
+fn subtype_unfolds_recursive_types() {
+    assert!(is_subtype("rec X. 1 + X", "rec Y. 1 + (1 + Y)"));
+    assert!(is_subtype("rec X. 1 & X", "1 & (rec X. 1 & X)"));
+    assert!(is_subtype("rec X. 0 + X", "rec X. 1 + X"));
+    assert!(!is_subtype("rec X. 1 + X", "rec X. 0 + X"));
+    assert!(!is_subtype("rec X. 1 + X", "rec Y. 1 + 1"));
+}
+
+#[test]
+fn subtype_resolves_names_from_a_file() {
+    let path = common::write("subtype_names.cr", PROGRAM);
+    let output = common::cranus(&["subtype", &path, "Narrow", "Wide"], "");
+    common::assert_succeeded(&output, "true\n");
+    let output = common::cranus(&["subtype", &path, "Wide", "Narrow"], "");
+    common::assert_succeeded(&output, "false\n");
+}
+
+#[test]
+fn call_accepts_a_subtype() {
+    let output = common::run(
+        "subtype_call.cr",
+        &format!(
+            "{}routine main(c: Console) {{ n = narrow( ) out(n, c) }}\n",
+            PROGRAM
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "1\n");
+    let output = common::run(
+        "subtype_call_wide.cr",
+        &format!(
+            "{}routine main(c: Console) {{ n = wide( ) only(n, c) }}\n",
+            PROGRAM
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "type mismatch");
+}
+
+#[test]
+fn send_accepts_a_subtype() {
+    let output = common::run(
+        "subtype_send.cr",
+        &format!(
+            "{}routine main(c: Console) {{ n = narrow( ) b = give(n, ) b receive m b wait out(m, c) }}\n",
+            PROGRAM
+        ),
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "1\n");
+    let output = common::run(
+        "subtype_send_wide.cr",
+        "type Wide = 1 + 1
+type Narrow = 1 + 0
+routine take(k: Wide, s: *(Narrow * bottom)) { s send k s close }
+",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "type mismatch");
+}
+
+#[test]
+fn top_is_not_a_supertype() {
+    let output = common::run(
+        "subtype_drop.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+routine drop(y: top) { }
+routine main(c: Console) { drop(c) }
+",
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "closed");
+    let output = common::run(
+        "subtype_drop_affine.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+affine routine drop(y: top) { }
+routine main(c: Console) { drop(c) }
+",
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "type mismatch");
+}
+
+#[test]
+fn top_branch_is_not_a_supertype() {
+    for (name, modifier) in [
+        ("subtype_branch.cr", ""),
+        ("subtype_branch_affine.cr", "affine "),
+    ] {
+        let output = common::run(
+            name,
+            &format!(
+                "type Console = rec C. (C + C) & (C & 1)
+{}routine r(y: 1 & top) {{ y deny }}
+routine both(y: *(1 & 1)) {{ y offer {{ y wait }} else {{ y wait }} }}
+routine main(c: Console) {{ y = both( ) r(y) c deny c deny c close }}
+",
+                modifier
+            ),
+            &[],
+            "0\n",
+        );
+        common::assert_failed(&output, "type mismatch");
+        assert!(output.stdout.is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+mod common;
+
+const SEND: &str = "type Console = rec C. (C + C) & (C & 1)
+type Bit = 1 + 1
+type Box = Bit * bottom
+routine no(p: *Bit) { p deny p wait }
+routine give(s: *Box) { b = no( ) s send b s close }
+routine main(c: Console) {
+  s = give( )
+  s receive b
+  s wait
+  b offer { b close } else { b close }
+  c deny c deny c close
+}
+";
+
+const RECEIVE: &str = "type Console = rec C. (C + C) & (C & 1)
+type Bit = 1 + 1
+type Box = Bit * bottom
+routine no(p: *Bit) { p deny p wait }
+routine finish(c: Console) { c deny c deny c close }
+routine give(c: Console, s: *Box) { b = no( ) s send b s close c accept c offer { finish(c) } else { finish(c) } }
+routine main(c: Console) {
+  s = give(c, )
+  s receive b
+  s wait
+  b offer { b close } else { b close }
+}
+";
+
+fn run(name: &str, source: &str, args: &[&str]) -> (std::process::Output, std::string::String) {
+    let path = common::write(&format!("trace \"quoted\" \\ dir\t/{}", name), source);
+    let mut arguments = vec![path.as_str(), "--no-prelude"];
+    arguments.extend(args);
+    let file = path
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\t', "\\t");
+    (common::cranus(&arguments, "0\n"), file)
+}
+
+fn assert_trace(output: &std::process::Output, file: &str, expected: &str) {
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"0\n");
+    let expected = expected.replace("FILE", &format!("\"{}\"", file));
+    assert_eq!(
+        std::string::String::from_utf8_lossy(&output.stderr),
+        expected
+    );
+}
+
+#[test]
+fn trace_goes_to_stderr_and_leaves_stdout_alone() {
+    let (output, _) = run("quiet.cr", SEND, &[]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"0\n");
+    assert!(output.stderr.is_empty());
+    let (output, _) = run("loud.cr", SEND, &["--trace"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"0\n");
+    let stderr = std::string::String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.lines().count(), 11);
+    assert!(
+        stderr
+            .lines()
+            .all(|line| line.starts_with("{\"step\":") && line.ends_with('}'))
+    );
+}
+
+#[test]
+fn trace_writes_sent_closed_chose_waited_and_flipped() {
+    let (output, file) = run("send.cr", SEND, &["--trace"]);
+    assert_trace(
+        &output,
+        &file,
+        r#"{"step":1,"routine":"main","node":14,"location":{"file":FILE,"line":7,"column":3},"action":"executed"}
+{"step":2,"routine":"main","node":13,"location":{"file":FILE,"line":8,"column":3},"action":"flipped","channel":"s","to":"give"}
+{"step":3,"routine":"give","node":5,"location":{"file":FILE,"line":5,"column":25},"action":"executed"}
+{"step":4,"routine":"give","node":4,"location":{"file":FILE,"line":5,"column":35},"action":"sent","channel":"s","peer":"main"}
+{"step":5,"routine":"give","node":3,"location":{"file":FILE,"line":5,"column":44},"action":"closed","channel":"s","peer":"main"}
+{"step":6,"routine":"main","node":11,"location":{"file":FILE,"line":10,"column":3},"action":"flipped","channel":"b","to":"no"}
+{"step":7,"routine":"no","node":2,"location":{"file":FILE,"line":4,"column":23},"action":"chose","channel":"p","peer":"main","accept":false}
+{"step":8,"routine":"no","node":1,"location":{"file":FILE,"line":4,"column":30},"action":"waited","channel":"p","peer":"main"}
+{"step":9,"routine":"main","node":8,"location":{"file":FILE,"line":11,"column":3},"action":"chose","channel":"c","peer":null,"accept":false}
+{"step":10,"routine":"main","node":7,"location":{"file":FILE,"line":11,"column":10},"action":"chose","channel":"c","peer":null,"accept":false}
+{"step":11,"routine":"main","node":6,"location":{"file":FILE,"line":11,"column":17},"action":"closed","channel":"c","peer":null}
+"#,
+    );
+}
+
+#[test]
+fn trace_writes_received_offered_and_entered() {
+    let (output, file) = run("receive.cr", RECEIVE, &["--trace"]);
+    assert_trace(
+        &output,
+        &file,
+        r#"{"step":1,"routine":"main","node":18,"location":{"file":FILE,"line":8,"column":3},"action":"executed"}
+{"step":2,"routine":"main","node":17,"location":{"file":FILE,"line":9,"column":3},"action":"flipped","channel":"s","to":"give"}
+{"step":3,"routine":"give","node":12,"location":{"file":FILE,"line":6,"column":37},"action":"executed"}
+{"step":4,"routine":"give","node":11,"location":{"file":FILE,"line":6,"column":47},"action":"flipped","channel":"s","to":"main"}
+{"step":5,"routine":"main","node":17,"location":{"file":FILE,"line":9,"column":3},"action":"received","channel":"s","peer":"give"}
+{"step":6,"routine":"main","node":16,"location":{"file":FILE,"line":10,"column":3},"action":"waited","channel":"s","peer":"give"}
+{"step":7,"routine":"give","node":9,"location":{"file":FILE,"line":6,"column":64},"action":"chose","channel":"c","peer":null,"accept":true}
+{"step":8,"routine":"give","node":8,"location":{"file":FILE,"line":6,"column":73},"action":"offered","channel":"c","peer":null,"accept":false}
+{"step":9,"routine":"give","node":7,"location":{"file":FILE,"line":6,"column":102},"action":"entered","entered":"finish"}
+{"step":10,"routine":"finish","node":5,"location":{"file":FILE,"line":5,"column":30},"action":"chose","channel":"c","peer":null,"accept":false}
+{"step":11,"routine":"finish","node":4,"location":{"file":FILE,"line":5,"column":37},"action":"chose","channel":"c","peer":null,"accept":false}
+{"step":12,"routine":"finish","node":3,"location":{"file":FILE,"line":5,"column":44},"action":"closed","channel":"c","peer":null}
+"#,
+    );
+}
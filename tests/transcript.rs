@@ -0,0 +1,87 @@
+mod common;
+
+const PROGRAM: &str = "type Bit = 1 + 1
+type Two = 1 + Bit
+type Box = Bit * bottom
+routine no(p: *Bit) { p deny p wait }
+routine second(p: *Two) { p deny p deny p wait }
+routine give(s: *Box) { b = no( ) s send b s close }
+";
+
+fn transcript(name: &str, routine: &str, formal: &str, steps: &str) -> std::process::Output {
+    let path = common::write(&format!("{}.cr", name), PROGRAM);
+    let steps_path = common::write(name, steps);
+    common::cranus(
+        &[
+            "transcript",
+            &path,
+            routine,
+            formal,
+            &steps_path,
+            "--no-prelude",
+        ],
+        "",
+    )
+}
+
+fn assert_deviates(output: &std::process::Output, diff: &str) {
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = std::string::String::from_utf8_lossy(&output.stderr);
+    let (_, message) = stderr.split_once(": ").unwrap();
+    assert_eq!(message, diff);
+}
+
+#[test]
+fn matching_transcript_passes() {
+    let output = transcript("transcript_pass.txt", "no", "p", "expect deny\nclose\n");
+    common::assert_succeeded(&output, "ok\n");
+}
+
+#[test]
+fn failing_transcript_reports_the_diff() {
+    let output = transcript("transcript_fail.txt", "no", "p", "expect accept\nclose\n");
+    assert_deviates(
+        &output,
+        "routine deviates from the transcript for \"p\":
+- expect accept
++ expect deny
+",
+    );
+}
+
+#[test]
+fn diff_keeps_the_steps_before_the_deviation() {
+    let output = transcript(
+        "transcript_later.txt",
+        "second",
+        "p",
+        "expect deny\nexpect accept\nclose\n",
+    );
+    assert_deviates(
+        &output,
+        "routine deviates from the transcript for \"p\":
+  expect deny
+- expect accept
++ expect deny
+",
+    );
+}
+
+#[test]
+fn diff_indents_nested_steps() {
+    let output = transcript(
+        "transcript_nested.txt",
+        "give",
+        "s",
+        "receive {\n  expect accept\n  close\n}\nwait\n",
+    );
+    assert_deviates(
+        &output,
+        "routine deviates from the transcript for \"s\":
+  receive {
+-   expect accept
++   expect deny
+",
+    );
+}
@@ -0,0 +1,47 @@
+mod common;
+
+#[test]
+fn raise_over_the_interaction_is_an_error() {
+    let output = common::run(
+        "raise_interaction.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+routine srv(p: *(bottom & 1)) { p offer { p close } else { p wait } }
+routine main(c: Console) {
+  p = srv( )
+  try {
+    c accept
+    c offer { raise } else { }
+    c deny c accept c deny c deny c close
+  } catch { }
+  p accept p wait
+}
+",
+        &[],
+        "3\n",
+    );
+    common::assert_failed(&output, "cancelled the interaction");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn raise_over_a_peer_resumes_the_peer_after_the_handler() {
+    let output = common::run(
+        "raise_peer.cr",
+        "type Console = rec C. (C + C) & (C & 1)
+type Nat = 1 + Nat
+routine gen(n: *Nat) {
+  try { n deny raise } catch { }
+}
+routine main(c: Console) {
+  n = gen( )
+  try {
+    again: n offer { n close } else { again }
+  } catch { c deny c accept }
+  c deny c deny c close
+}
+",
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "1\n");
+}
@@ -0,0 +1,66 @@
+mod common;
+
+#[test]
+fn close_pairs_with_wait() {
+    let output = common::run(
+        "unit_wait.cr",
+        "type Console = (Console + Console) & (Console & 1)
+type One = 1
+routine done(p: *One) { p wait }
+routine main(c: Console) { p = done( ) p close c deny c deny c close }
+",
+        &[],
+        "0\n",
+    );
+    common::assert_succeeded(&output, "0\n");
+    let output = common::run(
+        "unit_close_bottom.cr",
+        "type One = 1\nroutine done(p: *One) { p close }\n",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "p is not one");
+    let output = common::run(
+        "unit_wait_one.cr",
+        "routine done(p: 1) { p wait }\n",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "p is not bottom");
+}
+
+#[test]
+fn absurd_absorbs_the_context() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/top.cr");
+    let output = common::cranus(&[path.to_str().unwrap()], "0\n");
+    common::assert_succeeded(&output, "1\n");
+    let output = common::run(
+        "unit_absurd_one.cr",
+        "routine done(p: 1) { p absurd }\n",
+        &[],
+        "",
+    );
+    common::assert_failed(&output, "p is not zero");
+}
+
+#[test]
+fn top_does_not_absorb_the_context_at_end() {
+    let output = common::run(
+        "unit_top_end.cr",
+        "type Console = (Console + Console) & (Console & 1)
+type Top = top
+routine hole(x: *Top) { x absurd }
+routine sink(x: top, d: 1) { }
+routine main(c: Console) {
+  h = hole( )
+  d = sink(h, )
+  d wait
+  c deny c deny c close
+}
+",
+        &[],
+        "0\n",
+    );
+    common::assert_failed(&output, "closed");
+    assert!(output.stdout.is_empty());
+}